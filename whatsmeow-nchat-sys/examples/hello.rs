@@ -1,7 +1,10 @@
 fn main() {
     unsafe {
-        let r =
-            whatsmeow_nchat_sys::CWmInit(c"./prof".as_ptr() as *mut _, c"".as_ptr() as *mut _, 1);
-        println!("{r}")
+        let r = whatsmeow_nchat_sys::create_connection(
+            c"./prof".as_ptr() as *mut _,
+            c"".as_ptr() as *mut _,
+            1,
+        );
+        println!("{:?}", r.map(|(id, _)| id));
     }
 }
@@ -58,18 +58,24 @@ unsafe extern "C" {
     pub fn WmNewChatsNotify(
         p_ConnId: ::std::os::raw::c_int,
         p_ChatId: *mut ::std::os::raw::c_char,
+        p_Name: *mut ::std::os::raw::c_char,
         p_IsUnread: ::std::os::raw::c_int,
+        p_UnreadCount: ::std::os::raw::c_int,
         p_IsMuted: ::std::os::raw::c_int,
+        p_MutedUntil: ::std::os::raw::c_int,
         p_IsPinned: ::std::os::raw::c_int,
         p_IsArchived: ::std::os::raw::c_int,
+        p_EphemeralTimer: ::std::os::raw::c_int,
         p_LastMessageTime: ::std::os::raw::c_int,
     );
 }
 unsafe extern "C" {
-    pub fn WmNewGroupMembersNotify(
+    pub fn WmNewGroupInfoNotify(
         p_ConnId: ::std::os::raw::c_int,
         p_ChatId: *mut ::std::os::raw::c_char,
-        p_MembersJson: *mut ::std::os::raw::c_char,
+        p_InfoJson: *mut ::std::os::raw::c_char,
+        p_ErrCode: ::std::os::raw::c_int,
+        p_ErrMsg: *mut ::std::os::raw::c_char,
     );
 }
 unsafe extern "C" {
@@ -79,12 +85,14 @@ unsafe extern "C" {
         p_MsgId: *mut ::std::os::raw::c_char,
         p_SenderId: *mut ::std::os::raw::c_char,
         p_Text: *mut ::std::os::raw::c_char,
+        p_ContentJson: *mut ::std::os::raw::c_char,
         p_FromMe: ::std::os::raw::c_int,
         p_QuotedId: *mut ::std::os::raw::c_char,
         p_FileId: *mut ::std::os::raw::c_char,
         p_FilePath: *mut ::std::os::raw::c_char,
         p_FileStatus: ::std::os::raw::c_int,
         p_TimeSent: ::std::os::raw::c_int,
+        p_ExpiresAt: ::std::os::raw::c_int,
         p_IsRead: ::std::os::raw::c_int,
         p_IsEdited: ::std::os::raw::c_int,
     );
@@ -133,6 +141,30 @@ unsafe extern "C" {
         p_FromMe: ::std::os::raw::c_int,
     );
 }
+unsafe extern "C" {
+    pub fn WmNewPollUpdateNotify(
+        p_ConnId: ::std::os::raw::c_int,
+        p_ChatId: *mut ::std::os::raw::c_char,
+        p_PollId: *mut ::std::os::raw::c_char,
+        p_ResultsJson: *mut ::std::os::raw::c_char,
+    );
+}
+unsafe extern "C" {
+    pub fn WmNewEphemeralSettingNotify(
+        p_ConnId: ::std::os::raw::c_int,
+        p_ChatId: *mut ::std::os::raw::c_char,
+        p_Timer: ::std::os::raw::c_int,
+    );
+}
+unsafe extern "C" {
+    pub fn WmNewGroupChangeNotify(
+        p_ConnId: ::std::os::raw::c_int,
+        p_ChatId: *mut ::std::os::raw::c_char,
+        p_ActorId: *mut ::std::os::raw::c_char,
+        p_TimeSent: ::std::os::raw::c_int,
+        p_ChangeJson: *mut ::std::os::raw::c_char,
+    );
+}
 unsafe extern "C" {
     pub fn WmDeleteChatNotify(
         p_ConnId: ::std::os::raw::c_int,
@@ -184,13 +216,24 @@ unsafe extern "C" {
     pub fn WmClearStatus(p_ConnId: ::std::os::raw::c_int, p_Flags: ::std::os::raw::c_int);
 }
 unsafe extern "C" {
-    pub fn WmAppConfigGetNum(p_Param: *mut ::std::os::raw::c_char) -> ::std::os::raw::c_int;
+    pub fn WmAppConfigGetNum(
+        p_ConnId: ::std::os::raw::c_int,
+        p_Param: *mut ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn WmAppConfigSetNum(
+        p_ConnId: ::std::os::raw::c_int,
+        p_Param: *mut ::std::os::raw::c_char,
+        p_Value: ::std::os::raw::c_int,
+    );
 }
 unsafe extern "C" {
-    pub fn WmAppConfigSetNum(p_Param: *mut ::std::os::raw::c_char, p_Value: ::std::os::raw::c_int);
+    pub fn WmLogLevelEnabled(p_Level: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
 }
 unsafe extern "C" {
     pub fn WmLogTrace(
+        p_ConnId: ::std::os::raw::c_int,
         p_Filename: *mut ::std::os::raw::c_char,
         p_LineNo: ::std::os::raw::c_int,
         p_Message: *mut ::std::os::raw::c_char,
@@ -198,6 +241,7 @@ unsafe extern "C" {
 }
 unsafe extern "C" {
     pub fn WmLogDebug(
+        p_ConnId: ::std::os::raw::c_int,
         p_Filename: *mut ::std::os::raw::c_char,
         p_LineNo: ::std::os::raw::c_int,
         p_Message: *mut ::std::os::raw::c_char,
@@ -205,6 +249,7 @@ unsafe extern "C" {
 }
 unsafe extern "C" {
     pub fn WmLogInfo(
+        p_ConnId: ::std::os::raw::c_int,
         p_Filename: *mut ::std::os::raw::c_char,
         p_LineNo: ::std::os::raw::c_int,
         p_Message: *mut ::std::os::raw::c_char,
@@ -212,6 +257,7 @@ unsafe extern "C" {
 }
 unsafe extern "C" {
     pub fn WmLogWarning(
+        p_ConnId: ::std::os::raw::c_int,
         p_Filename: *mut ::std::os::raw::c_char,
         p_LineNo: ::std::os::raw::c_int,
         p_Message: *mut ::std::os::raw::c_char,
@@ -219,16 +265,47 @@ unsafe extern "C" {
 }
 unsafe extern "C" {
     pub fn WmLogError(
+        p_ConnId: ::std::os::raw::c_int,
         p_Filename: *mut ::std::os::raw::c_char,
         p_LineNo: ::std::os::raw::c_int,
         p_Message: *mut ::std::os::raw::c_char,
     );
 }
 unsafe extern "C" {
-    pub fn WmExtQrCode(p_path: *mut ::std::os::raw::c_char);
+    pub fn WmExtQrCode(p_ConnId: ::std::os::raw::c_int, p_QrCode: *mut ::std::os::raw::c_char);
+}
+unsafe extern "C" {
+    pub fn WmExtQrCodeRefresh(
+        p_ConnId: ::std::os::raw::c_int,
+        p_QrCode: *mut ::std::os::raw::c_char,
+    );
+}
+unsafe extern "C" {
+    pub fn WmExtQrCodeExpired(p_ConnId: ::std::os::raw::c_int);
+}
+unsafe extern "C" {
+    pub fn WmExtLoginPairingCode(
+        p_ConnId: ::std::os::raw::c_int,
+        p_Code: *mut ::std::os::raw::c_char,
+    );
+}
+unsafe extern "C" {
+    pub fn WmLoginProgressNotify(
+        p_ConnId: ::std::os::raw::c_int,
+        p_Progress: ::std::os::raw::c_int,
+    );
+}
+unsafe extern "C" {
+    pub fn WmLoginInstructions(
+        p_ConnId: ::std::os::raw::c_int,
+        p_Text: *mut ::std::os::raw::c_char,
+    );
 }
 unsafe extern "C" {
-    pub fn WmExtLoginPairingCode(p_code: *mut ::std::os::raw::c_char);
+    pub fn WmFatalLoginError(
+        p_ConnId: ::std::os::raw::c_int,
+        p_Reason: *mut ::std::os::raw::c_char,
+    );
 }
 pub type GoInt8 = ::std::os::raw::c_schar;
 pub type GoUint8 = ::std::os::raw::c_uchar;
@@ -282,16 +359,26 @@ unsafe extern "C" {
         path: *mut ::std::os::raw::c_char,
         proxy: *mut ::std::os::raw::c_char,
         sendType: GoInt,
+        connId: *mut ::std::os::raw::c_int,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmLogin(connId: GoInt) -> GoInt;
+    pub fn CWmLogin(
+        connId: GoInt,
+        mode: GoInt,
+        phone: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmLogout(connId: GoInt) -> GoInt;
+    pub fn CWmLoginCancel(connId: GoInt, errMsg: *mut *mut ::std::os::raw::c_char) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmCleanup(connId: GoInt) -> GoInt;
+    pub fn CWmLogout(connId: GoInt, errMsg: *mut *mut ::std::os::raw::c_char) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmCleanup(connId: GoInt, errMsg: *mut *mut ::std::os::raw::c_char) -> GoInt;
 }
 unsafe extern "C" {
     pub fn CWmGetVersion() -> GoInt;
@@ -303,6 +390,7 @@ unsafe extern "C" {
         limit: GoInt,
         fromMsgId: *mut ::std::os::raw::c_char,
         owner: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
 unsafe extern "C" {
@@ -315,19 +403,209 @@ unsafe extern "C" {
         quotedSender: *mut ::std::os::raw::c_char,
         filePath: *mut ::std::os::raw::c_char,
         fileType: *mut ::std::os::raw::c_char,
+        fileInfoJson: *mut ::std::os::raw::c_char,
         editMsgId: *mut ::std::os::raw::c_char,
         editMsgSent: GoInt,
         mentionsJson: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSendLocation(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        lat: GoFloat64,
+        lon: GoFloat64,
+        name: *mut ::std::os::raw::c_char,
+        address: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSendLiveLocation(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        liveMsgId: *mut ::std::os::raw::c_char,
+        lat: GoFloat64,
+        lon: GoFloat64,
+        accuracy: GoInt,
+        caption: *mut ::std::os::raw::c_char,
+        sequence: GoInt,
+        isStop: GoInt,
+        msgId: *mut *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSendContacts(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        contactsJson: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmGetGroupMembers(connId: GoInt, chatId: *mut ::std::os::raw::c_char) -> GoInt;
+    pub fn CWmSetDisappearingTimer(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        seconds: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmGetContacts(connId: GoInt) -> GoInt;
+    pub fn CWmSetDefaultDisappearingTimer(
+        connId: GoInt,
+        seconds: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmGetStatus(connId: GoInt, userId: *mut ::std::os::raw::c_char) -> GoInt;
+    pub fn CWmCreatePoll(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        name: *mut ::std::os::raw::c_char,
+        optionsJson: *mut ::std::os::raw::c_char,
+        selectableCount: GoInt,
+        msgId: *mut *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmVotePoll(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        senderId: *mut ::std::os::raw::c_char,
+        pollId: *mut ::std::os::raw::c_char,
+        optionsJson: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmCreateGroup(
+        connId: GoInt,
+        name: *mut ::std::os::raw::c_char,
+        userIdsJson: *mut ::std::os::raw::c_char,
+        groupId: *mut *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSetGroupName(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        name: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSetGroupDescription(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        description: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSetGroupPhoto(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        filePath: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSetGroupAnnounce(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        announce: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSetGroupLocked(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        locked: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSetGroupMemberAddMode(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        adminsOnly: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmSetGroupJoinApprovalMode(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        approval: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmGetGroupInviteLink(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        reset: GoInt,
+        link: *mut *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmPreviewGroupInvite(
+        connId: GoInt,
+        link: *mut ::std::os::raw::c_char,
+        infoJson: *mut *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmJoinGroupWithLink(
+        connId: GoInt,
+        link: *mut ::std::os::raw::c_char,
+        groupId: *mut *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmAcceptGroupInvite(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        inviterId: *mut ::std::os::raw::c_char,
+        code: *mut ::std::os::raw::c_char,
+        expiration: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmUpdateGroupParticipants(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        userIdsJson: *mut ::std::os::raw::c_char,
+        action: *mut ::std::os::raw::c_char,
+        resultsJson: *mut *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmGetGroupInfo(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmGetContacts(connId: GoInt, errMsg: *mut *mut ::std::os::raw::c_char) -> GoInt;
+}
+unsafe extern "C" {
+    pub fn CWmGetStatus(
+        connId: GoInt,
+        userId: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
 }
 unsafe extern "C" {
     pub fn CWmMarkMessageRead(
@@ -335,6 +613,7 @@ unsafe extern "C" {
         chatId: *mut ::std::os::raw::c_char,
         senderId: *mut ::std::os::raw::c_char,
         msgId: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
 unsafe extern "C" {
@@ -343,31 +622,46 @@ unsafe extern "C" {
         chatId: *mut ::std::os::raw::c_char,
         senderId: *mut ::std::os::raw::c_char,
         msgId: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmDeleteChat(connId: GoInt, chatId: *mut ::std::os::raw::c_char) -> GoInt;
+    pub fn CWmDeleteChat(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
 }
 unsafe extern "C" {
     pub fn CWmArchiveChat(
         connId: GoInt,
         chatId: *mut ::std::os::raw::c_char,
         isArchived: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmPinChat(connId: GoInt, chatId: *mut ::std::os::raw::c_char, isPinned: GoInt)
-    -> GoInt;
+    pub fn CWmPinChat(
+        connId: GoInt,
+        chatId: *mut ::std::os::raw::c_char,
+        isPinned: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
 }
 unsafe extern "C" {
     pub fn CWmSendTyping(
         connId: GoInt,
         chatId: *mut ::std::os::raw::c_char,
         isTyping: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
 unsafe extern "C" {
-    pub fn CWmSendStatus(connId: GoInt, isOnline: GoInt) -> GoInt;
+    pub fn CWmSendStatus(
+        connId: GoInt,
+        isOnline: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
+    ) -> GoInt;
 }
 unsafe extern "C" {
     pub fn CWmDownloadFile(
@@ -376,6 +670,7 @@ unsafe extern "C" {
         msgId: *mut ::std::os::raw::c_char,
         fileId: *mut ::std::os::raw::c_char,
         action: GoInt,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
 unsafe extern "C" {
@@ -385,8 +680,15 @@ unsafe extern "C" {
         senderId: *mut ::std::os::raw::c_char,
         msgId: *mut ::std::os::raw::c_char,
         emoji: *mut ::std::os::raw::c_char,
+        errMsg: *mut *mut ::std::os::raw::c_char,
     ) -> GoInt;
 }
+unsafe extern "C" {
+    pub fn CWmSetLogRedaction(level: GoInt);
+}
+unsafe extern "C" {
+    pub fn CWmSetHeadless(isHeadless: GoInt);
+}
 unsafe extern "C" {
     pub fn CWmExtGetState(connId: GoInt) -> GoInt64;
 }
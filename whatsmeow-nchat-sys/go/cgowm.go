@@ -10,14 +10,17 @@ package main
 // #cgo linux LDFLAGS: -Wl,-unresolved-symbols=ignore-all
 // #cgo darwin LDFLAGS: -Wl,-undefined,dynamic_lookup
 // extern void WmNewContactsNotify(int p_ConnId, char* p_ChatId, char* p_Name, char* p_Phone, int p_IsSelf, int p_IsAlias, int p_Notify);
-// extern void WmNewChatsNotify(int p_ConnId, char* p_ChatId, int p_IsUnread, int p_IsMuted, int p_IsPinned, int p_IsArchived, int p_LastMessageTime);
-// extern void WmNewGroupMembersNotify(int p_ConnId, char* p_ChatId, char* p_MembersJson);
-// extern void WmNewMessagesNotify(int p_ConnId, char* p_ChatId, char* p_MsgId, char* p_SenderId, char* p_Text, int p_FromMe, char* p_QuotedId, char* p_FileId, char* p_FilePath, int p_FileStatus, int p_TimeSent, int p_IsRead, int p_IsEdited);
+// extern void WmNewChatsNotify(int p_ConnId, char* p_ChatId, char* p_Name, int p_IsUnread, int p_UnreadCount, int p_IsMuted, int p_MutedUntil, int p_IsPinned, int p_IsArchived, int p_EphemeralTimer, int p_LastMessageTime);
+// extern void WmNewGroupInfoNotify(int p_ConnId, char* p_ChatId, char* p_InfoJson, int p_ErrCode, char* p_ErrMsg);
+// extern void WmNewMessagesNotify(int p_ConnId, char* p_ChatId, char* p_MsgId, char* p_SenderId, char* p_Text, char* p_ContentJson, int p_FromMe, char* p_QuotedId, char* p_FileId, char* p_FilePath, int p_FileStatus, int p_TimeSent, int p_ExpiresAt, int p_IsRead, int p_IsEdited);
 // extern void WmNewStatusNotify(int p_ConnId, char* p_UserId, int p_IsOnline, int p_TimeSeen);
 // extern void WmNewTypingNotify(int p_ConnId, char* p_ChatId, char* p_UserId, int p_IsTyping);
 // extern void WmNewMessageStatusNotify(int p_ConnId, char* p_ChatId, char* p_MsgId, int p_IsRead);
 // extern void WmNewMessageFileNotify(int p_ConnId, char* p_ChatId, char* p_MsgId, char* p_FilePath, int p_FileStatus, int p_Action);
 // extern void WmNewMessageReactionNotify(int p_ConnId, char* p_ChatId, char* p_MsgId, char* p_SenderId, char* p_Text, int p_FromMe);
+// extern void WmNewPollUpdateNotify(int p_ConnId, char* p_ChatId, char* p_PollId, char* p_ResultsJson);
+// extern void WmNewEphemeralSettingNotify(int p_ConnId, char* p_ChatId, int p_Timer);
+// extern void WmNewGroupChangeNotify(int p_ConnId, char* p_ChatId, char* p_ActorId, int p_TimeSent, char* p_ChangeJson);
 // extern void WmDeleteChatNotify(int p_ConnId, char* p_ChatId);
 // extern void WmDeleteMessageNotify(int p_ConnId, char* p_ChatId, char* p_MsgId);
 // extern void WmUpdateMuteNotify(int p_ConnId, char* p_ChatId, int p_IsMuted);
@@ -27,40 +30,54 @@ package main
 // extern void WmSetProtocolUiControl(int p_ConnId, int p_IsTakeControl);
 // extern void WmSetStatus(int p_ConnId, int p_Flags);
 // extern void WmClearStatus(int p_ConnId, int p_Flags);
-// extern int WmAppConfigGetNum(char* p_Param);
-// extern void WmAppConfigSetNum(char* p_Param, int p_Value);
-// extern void WmLogTrace(char* p_Filename, int p_LineNo, char* p_Message);
-// extern void WmLogDebug(char* p_Filename, int p_LineNo, char* p_Message);
-// extern void WmLogInfo(char* p_Filename, int p_LineNo, char* p_Message);
-// extern void WmLogWarning(char* p_Filename, int p_LineNo, char* p_Message);
-// extern void WmLogError(char* p_Filename, int p_LineNo, char* p_Message);
-// extern void WmExtQrCode(char* p_path);
-// extern void WmExtLoginPairingCode(char* p_code);
+// extern int WmAppConfigGetNum(int p_ConnId, char* p_Param);
+// extern void WmAppConfigSetNum(int p_ConnId, char* p_Param, int p_Value);
+// extern int WmLogLevelEnabled(int p_Level);
+// extern void WmLogTrace(int p_ConnId, char* p_Filename, int p_LineNo, char* p_Message);
+// extern void WmLogDebug(int p_ConnId, char* p_Filename, int p_LineNo, char* p_Message);
+// extern void WmLogInfo(int p_ConnId, char* p_Filename, int p_LineNo, char* p_Message);
+// extern void WmLogWarning(int p_ConnId, char* p_Filename, int p_LineNo, char* p_Message);
+// extern void WmLogError(int p_ConnId, char* p_Filename, int p_LineNo, char* p_Message);
+// extern void WmExtQrCode(int p_ConnId, char* p_QrCode);
+// extern void WmExtQrCodeRefresh(int p_ConnId, char* p_QrCode);
+// extern void WmExtQrCodeExpired(int p_ConnId);
+// extern void WmExtLoginPairingCode(int p_ConnId, char* p_Code);
+// extern void WmLoginProgressNotify(int p_ConnId, int p_Progress);
+// extern void WmLoginInstructions(int p_ConnId, char* p_Text);
+// extern void WmFatalLoginError(int p_ConnId, char* p_Reason);
 import "C"
 
 import (
+	"errors"
 	"path/filepath"
 	"runtime"
 )
 
 //export CWmInit
-func CWmInit(path *C.char, proxy *C.char, sendType int) int {
-	return WmInit(C.GoString(path), C.GoString(proxy), sendType)
+func CWmInit(path *C.char, proxy *C.char, sendType int, connId *C.int, errMsg **C.char) int {
+	id, err := WmInit(C.GoString(path), C.GoString(proxy), sendType)
+	*connId = C.int(id)
+	return CErr(err, errMsg)
 }
 
 //export CWmLogin
-func CWmLogin(connId int) int {
-	return WmLogin(connId)
+func CWmLogin(connId int, mode int, phone *C.char, errMsg **C.char) int {
+	return CErr(WmLogin(connId, mode, C.GoString(phone)), errMsg)
+}
+
+//export CWmLoginCancel
+func CWmLoginCancel(connId int, errMsg **C.char) int {
+	return CErr(WmLoginCancel(connId), errMsg)
 }
 
 //export CWmLogout
-func CWmLogout(connId int) int {
-	return WmLogout(connId)
+func CWmLogout(connId int, errMsg **C.char) int {
+	return CErr(WmLogout(connId), errMsg)
 }
 
 //export CWmCleanup
-func CWmCleanup(connId int) int {
-	return WmCleanup(connId)
+func CWmCleanup(connId int, errMsg **C.char) int {
+	return CErr(WmCleanup(connId), errMsg)
 }
 
 //export CWmGetVersion
@@ -69,73 +86,211 @@ func CWmGetVersion() int {
 }
 
 //export CWmGetMessages
-func CWmGetMessages(connId int, chatId *C.char, limit int, fromMsgId *C.char, owner int) int {
-	return WmGetMessages(connId, C.GoString(chatId), limit, C.GoString(fromMsgId), owner)
+func CWmGetMessages(connId int, chatId *C.char, limit int, fromMsgId *C.char, owner int, errMsg **C.char) int {
+	return CErr(WmGetMessages(connId, C.GoString(chatId), limit, C.GoString(fromMsgId), owner), errMsg)
 }
 
 //export CWmSendMessage
-func CWmSendMessage(connId int, chatId *C.char, text *C.char, quotedId *C.char, quotedText *C.char, quotedSender *C.char, filePath *C.char, fileType *C.char, editMsgId *C.char, editMsgSent int, mentionsJson *C.char) int {
-	return WmSendMessage(connId, C.GoString(chatId), C.GoString(text), C.GoString(quotedId), C.GoString(quotedText), C.GoString(quotedSender), C.GoString(filePath), C.GoString(fileType), C.GoString(editMsgId), editMsgSent, C.GoString(mentionsJson))
+func CWmSendMessage(connId int, chatId *C.char, text *C.char, quotedId *C.char, quotedText *C.char, quotedSender *C.char, filePath *C.char, fileType *C.char, fileInfoJson *C.char, editMsgId *C.char, editMsgSent int, mentionsJson *C.char, errMsg **C.char) int {
+	return CErr(WmSendMessage(connId, C.GoString(chatId), C.GoString(text), C.GoString(quotedId), C.GoString(quotedText), C.GoString(quotedSender), C.GoString(filePath), C.GoString(fileType), C.GoString(fileInfoJson), C.GoString(editMsgId), editMsgSent, C.GoString(mentionsJson)), errMsg)
+}
+
+//export CWmSendLocation
+func CWmSendLocation(connId int, chatId *C.char, lat float64, lon float64, name *C.char, address *C.char, errMsg **C.char) int {
+	return CErr(WmSendLocation(connId, C.GoString(chatId), lat, lon, C.GoString(name), C.GoString(address)), errMsg)
+}
+
+//export CWmSendLiveLocation
+func CWmSendLiveLocation(connId int, chatId *C.char, liveMsgId *C.char, lat float64, lon float64, accuracy int, caption *C.char, sequence int, isStop int, msgId **C.char, errMsg **C.char) int {
+	id, err := WmSendLiveLocation(connId, C.GoString(chatId), C.GoString(liveMsgId), lat, lon, accuracy, C.GoString(caption), sequence, IntToBool(isStop))
+	if err == nil {
+		*msgId = C.CString(id)
+	}
+	return CErr(err, errMsg)
+}
+
+//export CWmSendContacts
+func CWmSendContacts(connId int, chatId *C.char, contactsJson *C.char, errMsg **C.char) int {
+	return CErr(WmSendContacts(connId, C.GoString(chatId), C.GoString(contactsJson)), errMsg)
+}
+
+//export CWmSetDisappearingTimer
+func CWmSetDisappearingTimer(connId int, chatId *C.char, seconds int, errMsg **C.char) int {
+	return CErr(WmSetDisappearingTimer(connId, C.GoString(chatId), seconds), errMsg)
+}
+
+//export CWmSetDefaultDisappearingTimer
+func CWmSetDefaultDisappearingTimer(connId int, seconds int, errMsg **C.char) int {
+	return CErr(WmSetDefaultDisappearingTimer(connId, seconds), errMsg)
+}
+
+//export CWmCreatePoll
+func CWmCreatePoll(connId int, chatId *C.char, name *C.char, optionsJson *C.char, selectableCount int, msgId **C.char, errMsg **C.char) int {
+	id, err := WmCreatePoll(connId, C.GoString(chatId), C.GoString(name), C.GoString(optionsJson), selectableCount)
+	if err == nil {
+		*msgId = C.CString(id)
+	}
+	return CErr(err, errMsg)
+}
+
+//export CWmVotePoll
+func CWmVotePoll(connId int, chatId *C.char, senderId *C.char, pollId *C.char, optionsJson *C.char, errMsg **C.char) int {
+	return CErr(WmVotePoll(connId, C.GoString(chatId), C.GoString(senderId), C.GoString(pollId), C.GoString(optionsJson)), errMsg)
+}
+
+//export CWmCreateGroup
+func CWmCreateGroup(connId int, name *C.char, userIdsJson *C.char, groupId **C.char, errMsg **C.char) int {
+	id, err := WmCreateGroup(connId, C.GoString(name), C.GoString(userIdsJson))
+	if err == nil {
+		*groupId = C.CString(id)
+	}
+	return CErr(err, errMsg)
+}
+
+//export CWmSetGroupName
+func CWmSetGroupName(connId int, chatId *C.char, name *C.char, errMsg **C.char) int {
+	return CErr(WmSetGroupName(connId, C.GoString(chatId), C.GoString(name)), errMsg)
+}
+
+//export CWmSetGroupDescription
+func CWmSetGroupDescription(connId int, chatId *C.char, description *C.char, errMsg **C.char) int {
+	return CErr(WmSetGroupDescription(connId, C.GoString(chatId), C.GoString(description)), errMsg)
 }
 
-//export CWmGetGroupMembers
-func CWmGetGroupMembers(connId int, chatId *C.char) int {
-	return WmGetGroupMembers(connId, C.GoString(chatId))
+//export CWmSetGroupPhoto
+func CWmSetGroupPhoto(connId int, chatId *C.char, filePath *C.char, errMsg **C.char) int {
+	return CErr(WmSetGroupPhoto(connId, C.GoString(chatId), C.GoString(filePath)), errMsg)
+}
+
+//export CWmSetGroupAnnounce
+func CWmSetGroupAnnounce(connId int, chatId *C.char, announce int, errMsg **C.char) int {
+	return CErr(WmSetGroupAnnounce(connId, C.GoString(chatId), announce), errMsg)
+}
+
+//export CWmSetGroupLocked
+func CWmSetGroupLocked(connId int, chatId *C.char, locked int, errMsg **C.char) int {
+	return CErr(WmSetGroupLocked(connId, C.GoString(chatId), locked), errMsg)
+}
+
+//export CWmSetGroupMemberAddMode
+func CWmSetGroupMemberAddMode(connId int, chatId *C.char, adminsOnly int, errMsg **C.char) int {
+	return CErr(WmSetGroupMemberAddMode(connId, C.GoString(chatId), adminsOnly), errMsg)
+}
+
+//export CWmSetGroupJoinApprovalMode
+func CWmSetGroupJoinApprovalMode(connId int, chatId *C.char, approval int, errMsg **C.char) int {
+	return CErr(WmSetGroupJoinApprovalMode(connId, C.GoString(chatId), approval), errMsg)
+}
+
+//export CWmGetGroupInviteLink
+func CWmGetGroupInviteLink(connId int, chatId *C.char, reset int, link **C.char, errMsg **C.char) int {
+	res, err := WmGetGroupInviteLink(connId, C.GoString(chatId), reset)
+	if err == nil {
+		*link = C.CString(res)
+	}
+	return CErr(err, errMsg)
+}
+
+//export CWmPreviewGroupInvite
+func CWmPreviewGroupInvite(connId int, link *C.char, infoJson **C.char, errMsg **C.char) int {
+	res, err := WmPreviewGroupInvite(connId, C.GoString(link))
+	if err == nil {
+		*infoJson = C.CString(res)
+	}
+	return CErr(err, errMsg)
+}
+
+//export CWmJoinGroupWithLink
+func CWmJoinGroupWithLink(connId int, link *C.char, groupId **C.char, errMsg **C.char) int {
+	res, err := WmJoinGroupWithLink(connId, C.GoString(link))
+	if err == nil {
+		*groupId = C.CString(res)
+	}
+	return CErr(err, errMsg)
+}
+
+//export CWmAcceptGroupInvite
+func CWmAcceptGroupInvite(connId int, chatId *C.char, inviterId *C.char, code *C.char, expiration int, errMsg **C.char) int {
+	return CErr(WmAcceptGroupInvite(connId, C.GoString(chatId), C.GoString(inviterId), C.GoString(code), expiration), errMsg)
+}
+
+//export CWmUpdateGroupParticipants
+func CWmUpdateGroupParticipants(connId int, chatId *C.char, userIdsJson *C.char, action *C.char, resultsJson **C.char, errMsg **C.char) int {
+	results, err := WmUpdateGroupParticipants(connId, C.GoString(chatId), C.GoString(userIdsJson), C.GoString(action))
+	if err == nil {
+		*resultsJson = C.CString(results)
+	}
+	return CErr(err, errMsg)
+}
+
+//export CWmGetGroupInfo
+func CWmGetGroupInfo(connId int, chatId *C.char, errMsg **C.char) int {
+	return CErr(WmGetGroupInfo(connId, C.GoString(chatId)), errMsg)
 }
 
 //export CWmGetContacts
-func CWmGetContacts(connId int) int {
-	return WmGetContacts(connId)
+func CWmGetContacts(connId int, errMsg **C.char) int {
+	return CErr(WmGetContacts(connId), errMsg)
 }
 
 //export CWmGetStatus
-func CWmGetStatus(connId int, userId *C.char) int {
-	return WmGetStatus(connId, C.GoString(userId))
+func CWmGetStatus(connId int, userId *C.char, errMsg **C.char) int {
+	return CErr(WmGetStatus(connId, C.GoString(userId)), errMsg)
 }
 
 //export CWmMarkMessageRead
-func CWmMarkMessageRead(connId int, chatId *C.char, senderId *C.char, msgId *C.char) int {
-	return WmMarkMessageRead(connId, C.GoString(chatId), C.GoString(senderId), C.GoString(msgId))
+func CWmMarkMessageRead(connId int, chatId *C.char, senderId *C.char, msgId *C.char, errMsg **C.char) int {
+	return CErr(WmMarkMessageRead(connId, C.GoString(chatId), C.GoString(senderId), C.GoString(msgId)), errMsg)
 }
 
 //export CWmDeleteMessage
-func CWmDeleteMessage(connId int, chatId *C.char, senderId *C.char, msgId *C.char) int {
-	return WmDeleteMessage(connId, C.GoString(chatId), C.GoString(senderId), C.GoString(msgId))
+func CWmDeleteMessage(connId int, chatId *C.char, senderId *C.char, msgId *C.char, errMsg **C.char) int {
+	return CErr(WmDeleteMessage(connId, C.GoString(chatId), C.GoString(senderId), C.GoString(msgId)), errMsg)
 }
 
 //export CWmDeleteChat
-func CWmDeleteChat(connId int, chatId *C.char) int {
-	return WmDeleteChat(connId, C.GoString(chatId))
+func CWmDeleteChat(connId int, chatId *C.char, errMsg **C.char) int {
+	return CErr(WmDeleteChat(connId, C.GoString(chatId)), errMsg)
 }
 
 //export CWmArchiveChat
-func CWmArchiveChat(connId int, chatId *C.char, isArchived int) int {
-	return WmArchiveChat(connId, C.GoString(chatId), isArchived)
+func CWmArchiveChat(connId int, chatId *C.char, isArchived int, errMsg **C.char) int {
+	return CErr(WmArchiveChat(connId, C.GoString(chatId), isArchived), errMsg)
 }
 
 //export CWmPinChat
-func CWmPinChat(connId int, chatId *C.char, isPinned int) int {
-	return WmPinChat(connId, C.GoString(chatId), isPinned)
+func CWmPinChat(connId int, chatId *C.char, isPinned int, errMsg **C.char) int {
+	return CErr(WmPinChat(connId, C.GoString(chatId), isPinned), errMsg)
 }
 
 //export CWmSendTyping
-func CWmSendTyping(connId int, chatId *C.char, isTyping int) int {
-	return WmSendTyping(connId, C.GoString(chatId), isTyping)
+func CWmSendTyping(connId int, chatId *C.char, isTyping int, errMsg **C.char) int {
+	return CErr(WmSendTyping(connId, C.GoString(chatId), isTyping), errMsg)
 }
 
 //export CWmSendStatus
-func CWmSendStatus(connId int, isOnline int) int {
-	return WmSendStatus(connId, isOnline)
+func CWmSendStatus(connId int, isOnline int, errMsg **C.char) int {
+	return CErr(WmSendStatus(connId, isOnline), errMsg)
 }
 
 //export CWmDownloadFile
-func CWmDownloadFile(connId int, chatId *C.char, msgId *C.char, fileId *C.char, action int) int {
-	return WmDownloadFile(connId, C.GoString(chatId), C.GoString(msgId), C.GoString(fileId), action)
+func CWmDownloadFile(connId int, chatId *C.char, msgId *C.char, fileId *C.char, action int, errMsg **C.char) int {
+	return CErr(WmDownloadFile(connId, C.GoString(chatId), C.GoString(msgId), C.GoString(fileId), action), errMsg)
 }
 
 //export CWmSendReaction
-func CWmSendReaction(connId int, chatId *C.char, senderId *C.char, msgId *C.char, emoji *C.char) int {
-	return WmSendReaction(connId, C.GoString(chatId), C.GoString(senderId), C.GoString(msgId), C.GoString(emoji))
+func CWmSendReaction(connId int, chatId *C.char, senderId *C.char, msgId *C.char, emoji *C.char, errMsg **C.char) int {
+	return CErr(WmSendReaction(connId, C.GoString(chatId), C.GoString(senderId), C.GoString(msgId), C.GoString(emoji)), errMsg)
+}
+
+//export CWmSetLogRedaction
+func CWmSetLogRedaction(level int) {
+	WmSetLogRedaction(level)
+}
+
+//export CWmSetHeadless
+func CWmSetHeadless(isHeadless int) {
+	WmSetHeadless(isHeadless)
 }
 
 //export CWmExtGetState
@@ -147,16 +302,16 @@ func CWmNewContactsNotify(connId int, chatId string, name string, phone string,
 	C.WmNewContactsNotify(C.int(connId), C.CString(chatId), C.CString(name), C.CString(phone), C.int(isSelf), C.int(isAlias), C.int(notify))
 }
 
-func CWmNewChatsNotify(connId int, chatId string, isUnread int, isMuted int, isPinned int, isArchived int, lastMessageTime int) {
-	C.WmNewChatsNotify(C.int(connId), C.CString(chatId), C.int(isUnread), C.int(isMuted), C.int(isPinned), C.int(isArchived), C.int(lastMessageTime))
+func CWmNewChatsNotify(connId int, chatId string, name string, isUnread int, unreadCount int, isMuted int, mutedUntil int, isPinned int, isArchived int, ephemeralTimer int, lastMessageTime int) {
+	C.WmNewChatsNotify(C.int(connId), C.CString(chatId), C.CString(name), C.int(isUnread), C.int(unreadCount), C.int(isMuted), C.int(mutedUntil), C.int(isPinned), C.int(isArchived), C.int(ephemeralTimer), C.int(lastMessageTime))
 }
 
-func CWmNewGroupMembersNotify(connId int, chatId string, membersJson string) {
-	C.WmNewGroupMembersNotify(C.int(connId), C.CString(chatId), C.CString(membersJson))
+func CWmNewGroupInfoNotify(connId int, chatId string, infoJson string, errCode int, errMsg string) {
+	C.WmNewGroupInfoNotify(C.int(connId), C.CString(chatId), C.CString(infoJson), C.int(errCode), C.CString(errMsg))
 }
 
-func CWmNewMessagesNotify(connId int, chatId string, msgId string, senderId string, text string, fromMe int, quotedId string, fileId string, filePath string, fileStatus int, timeSent int, isRead int, isEdited int) {
-	C.WmNewMessagesNotify(C.int(connId), C.CString(chatId), C.CString(msgId), C.CString(senderId), C.CString(text), C.int(fromMe), C.CString(quotedId), C.CString(fileId), C.CString(filePath), C.int(fileStatus), C.int(timeSent), C.int(isRead), C.int(isEdited))
+func CWmNewMessagesNotify(connId int, chatId string, msgId string, senderId string, text string, contentJson string, fromMe int, quotedId string, fileId string, filePath string, fileStatus int, timeSent int, expiresAt int, isRead int, isEdited int) {
+	C.WmNewMessagesNotify(C.int(connId), C.CString(chatId), C.CString(msgId), C.CString(senderId), C.CString(text), C.CString(contentJson), C.int(fromMe), C.CString(quotedId), C.CString(fileId), C.CString(filePath), C.int(fileStatus), C.int(timeSent), C.int(expiresAt), C.int(isRead), C.int(isEdited))
 }
 
 func CWmNewStatusNotify(connId int, userId string, isOnline int, timeSeen int) {
@@ -179,6 +334,18 @@ func CWmNewMessageReactionNotify(connId int, chatId string, msgId string, sender
 	C.WmNewMessageReactionNotify(C.int(connId), C.CString(chatId), C.CString(msgId), C.CString(senderId), C.CString(text), C.int(fromMe))
 }
 
+func CWmNewPollUpdateNotify(connId int, chatId string, pollId string, resultsJson string) {
+	C.WmNewPollUpdateNotify(C.int(connId), C.CString(chatId), C.CString(pollId), C.CString(resultsJson))
+}
+
+func CWmNewEphemeralSettingNotify(connId int, chatId string, timer int) {
+	C.WmNewEphemeralSettingNotify(C.int(connId), C.CString(chatId), C.int(timer))
+}
+
+func CWmNewGroupChangeNotify(connId int, chatId string, actorId string, timeSent int, changeJson string) {
+	C.WmNewGroupChangeNotify(C.int(connId), C.CString(chatId), C.CString(actorId), C.int(timeSent), C.CString(changeJson))
+}
+
 func CWmDeleteChatNotify(connId int, chatId string) {
 	C.WmDeleteChatNotify(C.int(connId), C.CString(chatId))
 }
@@ -215,23 +382,84 @@ func CWmClearStatus(connId int, flags int) {
 	C.WmClearStatus(C.int(connId), C.int(flags))
 }
 
-func CWmAppConfigGetNum(param string) int {
-	return int(C.WmAppConfigGetNum(C.CString(param)))
+func CWmAppConfigGetNum(connId int, param string) int {
+	return int(C.WmAppConfigGetNum(C.int(connId), C.CString(param)))
+}
+
+func CWmAppConfigSetNum(connId int, param string, value int) {
+	C.WmAppConfigSetNum(C.int(connId), C.CString(param), C.int(value))
+}
+
+func CWmExtQrCode(connId int, qrcode string) {
+	C.WmExtQrCode(C.int(connId), C.CString(qrcode))
+}
+
+func CWmExtQrCodeRefresh(connId int, qrcode string) {
+	C.WmExtQrCodeRefresh(C.int(connId), C.CString(qrcode))
+}
+
+func CWmExtQrCodeExpired(connId int) {
+	C.WmExtQrCodeExpired(C.int(connId))
+}
+
+func CWmExtLoginPairingCode(connId int, code string) {
+	C.WmExtLoginPairingCode(C.int(connId), C.CString(code))
+}
+
+func CWmLoginProgressNotify(connId int, progress int) {
+	C.WmLoginProgressNotify(C.int(connId), C.int(progress))
+}
+
+func CWmLoginInstructions(connId int, text string) {
+	C.WmLoginInstructions(C.int(connId), C.CString(text))
+}
+
+func CWmFatalLoginError(connId int, reason string) {
+	C.WmFatalLoginError(C.int(connId), C.CString(reason))
 }
 
-func CWmAppConfigSetNum(param string, value int) {
-	C.WmAppConfigSetNum(C.CString(param), C.int(value))
+func LogLevelEnabled(level int) bool {
+	return C.WmLogLevelEnabled(C.int(level)) != 0
 }
 
-func CWmExtQrCode(qrcode string) {
-	C.WmExtQrCode(C.CString(qrcode))
+func NewWmError(connId int, code int, message string) error {
+	if !LogLevelEnabled(LogLevelWarning) {
+		return &WmError{Code: code, Message: message}
+	}
+
+	_, filename, lineNo, ok := runtime.Caller(1)
+	if ok {
+		filename = filepath.Base(filename)
+	} else {
+		filename = "???"
+		lineNo = 0
+	}
+
+	C.WmLogWarning(C.int(connId), C.CString(filename), C.int(lineNo), C.CString(RedactJids(message)))
+	return &WmError{Code: code, Message: message}
 }
 
-func CWmExtLoginPairingCode(code string) {
-	C.WmExtLoginPairingCode(C.CString(code))
+func CErr(err error, errMsg **C.char) int {
+	if err == nil {
+		return ErrNone
+	}
+
+	var wmErr *WmError
+	if !errors.As(err, &wmErr) {
+		wmErr = &WmError{Code: ErrUnknown, Message: err.Error()}
+	}
+
+	if errMsg != nil {
+		*errMsg = C.CString(wmErr.Message)
+	}
+	return wmErr.Code
 }
 
-func LOG_TRACE(message string) {
+func LOG_TRACE(connId int, message string) {
+	if !LogLevelEnabled(LogLevelTrace) {
+		return
+	}
+
 	_, filename, lineNo, ok := runtime.Caller(1)
 	if ok {
 		filename = filepath.Base(filename)
@@ -240,10 +468,14 @@ func LOG_TRACE(message string) {
 		lineNo = 0
 	}
 
-	C.WmLogTrace(C.CString(filename), C.int(lineNo), C.CString(message))
+	C.WmLogTrace(C.int(connId), C.CString(filename), C.int(lineNo), C.CString(RedactJids(message)))
 }
 
-func LOG_DEBUG(message string) {
+func LOG_DEBUG(connId int, message string) {
+	if !LogLevelEnabled(LogLevelDebug) {
+		return
+	}
+
 	_, filename, lineNo, ok := runtime.Caller(1)
 	if ok {
 		filename = filepath.Base(filename)
@@ -252,10 +484,14 @@ func LOG_DEBUG(message string) {
 		lineNo = 0
 	}
 
-	C.WmLogDebug(C.CString(filename), C.int(lineNo), C.CString(message))
+	C.WmLogDebug(C.int(connId), C.CString(filename), C.int(lineNo), C.CString(RedactJids(message)))
 }
 
-func LOG_INFO(message string) {
+func LOG_INFO(connId int, message string) {
+	if !LogLevelEnabled(LogLevelInfo) {
+		return
+	}
+
 	_, filename, lineNo, ok := runtime.Caller(1)
 	if ok {
 		filename = filepath.Base(filename)
@@ -264,10 +500,14 @@ func LOG_INFO(message string) {
 		lineNo = 0
 	}
 
-	C.WmLogInfo(C.CString(filename), C.int(lineNo), C.CString(message))
+	C.WmLogInfo(C.int(connId), C.CString(filename), C.int(lineNo), C.CString(RedactJids(message)))
 }
 
-func LOG_WARNING(message string) {
+func LOG_WARNING(connId int, message string) {
+	if !LogLevelEnabled(LogLevelWarning) {
+		return
+	}
+
 	_, filename, lineNo, ok := runtime.Caller(1)
 	if ok {
 		filename = filepath.Base(filename)
@@ -276,10 +516,14 @@ func LOG_WARNING(message string) {
 		lineNo = 0
 	}
 
-	C.WmLogWarning(C.CString(filename), C.int(lineNo), C.CString(message))
+	C.WmLogWarning(C.int(connId), C.CString(filename), C.int(lineNo), C.CString(RedactJids(message)))
 }
 
-func LOG_ERROR(message string) {
+func LOG_ERROR(connId int, message string) {
+	if !LogLevelEnabled(LogLevelError) {
+		return
+	}
+
 	_, filename, lineNo, ok := runtime.Caller(1)
 	if ok {
 		filename = filepath.Base(filename)
@@ -288,5 +532,5 @@ func LOG_ERROR(message string) {
 		lineNo = 0
 	}
 
-	C.WmLogError(C.CString(filename), C.int(lineNo), C.CString(message))
+	C.WmLogError(C.int(connId), C.CString(filename), C.int(lineNo), C.CString(RedactJids(message)))
 }
@@ -8,18 +8,21 @@
 package main
 
 import (
+	"bytes"
 	"context"
+	"crypto/hmac"
+	"crypto/rand"
+	"crypto/sha256"
 	"encoding/gob"
+	"encoding/hex"
 	"encoding/json"
 	"errors"
 	"fmt"
-	"io"
-	"io/ioutil"
 	"math"
 	"mime"
 	"os"
-	"os/exec"
 	"path/filepath"
+	"regexp"
 	"runtime"
 	"runtime/debug"
 	"slices"
@@ -30,6 +33,7 @@ import (
 	"time"
 
 	"google.golang.org/protobuf/proto"
+	"google.golang.org/protobuf/reflect/protoreflect"
 
 	"go.mau.fi/whatsmeow/proto/waCompanionReg"
 	"go.mau.fi/whatsmeow/proto/waE2E"
@@ -76,9 +80,15 @@ var (
 	states      map[int]State                = make(map[int]State)
 	timeReads   map[int]map[string]time.Time = make(map[int]map[string]time.Time)
 	expirations map[int]map[string]uint32    = make(map[int]map[string]uint32)
+	polls       map[int]map[string]*PollState = make(map[int]map[string]*PollState)
 	handlers    map[int]*WmEventHandler      = make(map[int]*WmEventHandler)
+	handlerIds  map[int]uint32               = make(map[int]uint32)
 	sendTypes   map[int]int                  = make(map[int]int)
 	namesSynced map[int]bool                 = make(map[int]bool)
+	stateSignal map[int]chan struct{}        = make(map[int]chan struct{})
+	loginCancel map[int]context.CancelFunc   = make(map[int]context.CancelFunc)
+	headless    bool                         = false
+	redaction   int                          = RedactStripContent
 )
 
 // keep in sync with enum AttachmentSendType in appconfig.h
@@ -109,6 +119,69 @@ var NotifyDirect = 0
 var NotifyCache = 1
 var NotifySendCached = 2
 
+// keep in sync with WmLogLevelEnabled in whatsmeow-nchat-sys/src/handlers.rs
+var LogLevelTrace = 0
+var LogLevelDebug = 1
+var LogLevelInfo = 2
+var LogLevelWarning = 3
+var LogLevelError = 4
+
+// keep in sync with enum LogRedaction in whatsmeow-nchat-sys/src/types.rs
+var RedactOff = 0
+var RedactHashJids = 1
+var RedactStripContent = 2
+
+// keep in sync with enum LoginProgress in whatsmeow-nchat-sys/src/types.rs
+var LoginConnecting = 0
+var LoginQrAvailable = 1
+var LoginPairSuccess = 2
+var LoginConnected = 3
+var LoginTimeout = 4
+var LoginOutdated = 5
+var LoginFailed = 6
+var LoginCancelled = 7
+var LoginPairError = 8
+
+// keep in sync with enum LoginMode in whatsmeow-nchat-sys/src/types.rs
+var LoginModeDefault = 0
+var LoginModeQr = 1
+var LoginModePairingCode = 2
+
+// keep in sync with enum ErrorCode in whatsmeow-nchat-sys/src/types.rs
+var ErrNone = 0
+var ErrUnknown = 1
+var ErrClientMissing = 2
+var ErrNotLoggedIn = 3
+var ErrInvalidJid = 4
+var ErrFileRead = 5
+var ErrUploadFailed = 6
+var ErrSendFailed = 7
+var ErrTimeout = 8
+var ErrOutdated = 9
+var ErrInvalidArgument = 10
+var ErrRequestFailed = 11
+var ErrNotSupported = 12
+
+type WmError struct {
+	Code    int
+	Message string
+}
+
+func (e *WmError) Error() string {
+	return e.Message
+}
+
+func ErrorCodeFor(err error, defaultCode int) int {
+	switch {
+	case errors.Is(err, whatsmeow.ErrNotLoggedIn), errors.Is(err, whatsmeow.ErrNotConnected):
+		return ErrNotLoggedIn
+	case errors.Is(err, whatsmeow.ErrIQTimedOut), errors.Is(err, context.DeadlineExceeded):
+		return ErrTimeout
+	default:
+		return defaultCode
+	}
+}
+
 func SaveMap(path string, m map[string]string) error {
 	f, err := os.Create(path)
 	if err != nil {
@@ -131,6 +204,67 @@ func LoadMap(path string) (map[string]string, error) {
 	return m, nil
 }
 
+// poll options and votes, to tally the encrypted votes (which only hold option hashes)
+type PollState struct {
+	Options []string
+	Votes   map[string][]string // voter id to selected options
+	Created int64               // unix time, zero for polls saved before it was added
+}
+
+// newest polls kept, votes for older ones are dropped (documented on ChatEvent::PollUpdate)
+var pollsMax = 1000
+
+func SavePolls(path string, m map[string]*PollState) error {
+	// write a temp file first, so a crash doesn't lose all polls
+	tmpPath := path + ".tmp"
+	f, err := os.Create(tmpPath)
+	if err != nil {
+		return err
+	}
+	if err := gob.NewEncoder(f).Encode(m); err != nil {
+		f.Close()
+		return err
+	}
+	if err := f.Close(); err != nil {
+		return err
+	}
+	return os.Rename(tmpPath, path)
+}
+
+func PrunePolls(m map[string]*PollState, max int) {
+	if len(m) <= max {
+		return
+	}
+
+	pollIds := make([]string, 0, len(m))
+	for pollId := range m {
+		pollIds = append(pollIds, pollId)
+	}
+	sort.Slice(pollIds, func(i, j int) bool {
+		return m[pollIds[i]].Created > m[pollIds[j]].Created
+	})
+	for _, pollId := range pollIds[max:] {
+		delete(m, pollId)
+	}
+}
+
+func LoadPolls(path string) (map[string]*PollState, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return make(map[string]*PollState), err
+	}
+	defer f.Close()
+	var m map[string]*PollState
+	if err := gob.NewDecoder(f).Decode(&m); err != nil {
+		return make(map[string]*PollState), err
+	}
+	return m, nil
+}
+
+func GetPollsStorePath(connPath string) string {
+	return connPath + "/polls.dat"
+}
+
 func GetContactsStorePath(connPath string) string {
 	return connPath + "/contacts.dat"
 }
@@ -150,17 +284,30 @@ func AddConn(conn *whatsmeow.Client, path string, sendType int) int {
 	states[connId] = None
 	timeReads[connId] = make(map[string]time.Time)
 	expirations[connId] = make(map[string]uint32)
+	polls[connId], _ = LoadPolls(GetPollsStorePath(path))
 	handlers[connId] = &WmEventHandler{connId}
+	// register once, each login reconnects the same client
+	handlerIds[connId] = conn.AddEventHandler(handlers[connId].HandleEvent)
 	sendTypes[connId] = sendType
 	namesSynced[connId] = false
+	stateSignal[connId] = make(chan struct{}, 1)
 	mx.Unlock()
 	return connId
 }
 
 func RemoveConn(connId int) {
+	// unregister without holding mx, removal waits for running handlers, which may take it
+	mx.Lock()
+	client, handlerId := clients[connId], handlerIds[connId]
+	mx.Unlock()
+	if client != nil {
+		client.RemoveEventHandler(handlerId)
+	}
+
 	mx.Lock()
 	SaveMap(GetContactsStorePath(paths[connId]), contacts[connId])
 	SaveMap(GetSendersStorePath(paths[connId]), senders[connId])
+	SavePolls(GetPollsStorePath(paths[connId]), polls[connId])
 	delete(clients, connId)
 	delete(paths, connId)
 	delete(contacts, connId)
@@ -168,9 +315,12 @@ func RemoveConn(connId int) {
 	delete(states, connId)
 	delete(timeReads, connId)
 	delete(expirations, connId)
+	delete(polls, connId)
 	delete(handlers, connId)
+	delete(handlerIds, connId)
 	delete(sendTypes, connId)
 	delete(namesSynced, connId)
+	delete(stateSignal, connId)
 	mx.Unlock()
 }
 
@@ -212,6 +362,69 @@ func GetState(connId int) State {
 func SetState(connId int, status State) {
 	mx.Lock()
 	states[connId] = status
+	// wake up login waiting for a state change, if any
+	select {
+	case stateSignal[connId] <- struct{}{}:
+	default:
+	}
+	mx.Unlock()
+}
+
+func GetStateSignal(connId int) chan struct{} {
+	mx.Lock()
+	var signal chan struct{} = stateSignal[connId]
+	mx.Unlock()
+	return signal
+}
+
+func SetLoginCancel(connId int, cancel context.CancelFunc) bool {
+	mx.Lock()
+	_, busy := loginCancel[connId]
+	if !busy {
+		loginCancel[connId] = cancel
+	}
+	mx.Unlock()
+	return !busy
+}
+
+func CancelLogin(connId int) {
+	mx.Lock()
+	var cancel context.CancelFunc = loginCancel[connId]
+	mx.Unlock()
+	if cancel != nil {
+		cancel()
+	}
+}
+
+func RemoveLoginCancel(connId int) {
+	mx.Lock()
+	delete(loginCancel, connId)
+	mx.Unlock()
+}
+
+func IsHeadless() bool {
+	mx.Lock()
+	var isHeadless bool = headless
+	mx.Unlock()
+	return isHeadless
+}
+
+func SetHeadless(isHeadless bool) {
+	mx.Lock()
+	headless = isHeadless
+	mx.Unlock()
+}
+
+func GetRedaction() int {
+	mx.Lock()
+	var level int = redaction
+	mx.Unlock()
+	return level
+}
+
+func SetRedaction(level int) {
+	mx.Lock()
+	redaction = level
 	mx.Unlock()
 }
 
@@ -332,6 +545,74 @@ func SetExpiration(connId int, chatId string, expiration uint32) {
 	mx.Unlock()
 }
 
+func AddPoll(connId int, pollId string, options []string, created time.Time) {
+	mx.Lock()
+	defer mx.Unlock()
+	if polls[connId] == nil {
+		polls[connId] = make(map[string]*PollState)
+	}
+	if _, ok := polls[connId][pollId]; ok {
+		return
+	}
+
+	polls[connId][pollId] = &PollState{Options: options, Votes: make(map[string][]string), Created: created.Unix()}
+	PrunePolls(polls[connId], pollsMax)
+	if err := SavePolls(GetPollsStorePath(paths[connId]), polls[connId]); err != nil {
+		LOG_WARNING(connId, fmt.Sprintf("save polls err %#v", err))
+	}
+}
+
+type PollOptionResult struct {
+	Name   string   `json:"name"`
+	Voters []string `json:"voters"`
+}
+
+// replaces the earlier vote of voterId, returns the poll results json (false if poll unknown)
+func SetPollVote(connId int, pollId string, voterId string, optionHashes [][]byte) (string, bool) {
+	mx.Lock()
+	defer mx.Unlock()
+	poll, ok := polls[connId][pollId]
+	if !ok {
+		return "", false
+	}
+
+	selected := []string{}
+	for _, option := range poll.Options {
+		optionHash := sha256.Sum256([]byte(option))
+		for _, hash := range optionHashes {
+			if bytes.Equal(optionHash[:], hash) {
+				selected = append(selected, option)
+				break
+			}
+		}
+	}
+
+	if len(selected) == 0 {
+		delete(poll.Votes, voterId) // vote retracted
+	} else {
+		poll.Votes[voterId] = selected
+	}
+
+	if err := SavePolls(GetPollsStorePath(paths[connId]), polls[connId]); err != nil {
+		LOG_WARNING(connId, fmt.Sprintf("save polls err %#v", err))
+	}
+
+	results := []PollOptionResult{}
+	for _, option := range poll.Options {
+		voters := []string{}
+		for voter, voterSelected := range poll.Votes {
+			if slices.Contains(voterSelected, option) {
+				voters = append(voters, voter)
+			}
+		}
+		sort.Strings(voters)
+		results = append(results, PollOptionResult{Name: option, Voters: voters})
+	}
+
+	resultsJson, _ := json.Marshal(results)
+	return string(resultsJson), true
+}
+
 // download info
 var downloadInfoVersion = 1 // bump version upon any struct change
 type DownloadInfo struct {
@@ -360,7 +641,7 @@ func DownloadableMessageToFileId(client *whatsmeow.Client, msg whatsmeow.Downloa
 
 	info.MediaType = whatsmeow.GetMediaType(msg)
 	if len(info.MediaType) == 0 {
-		LOG_WARNING(fmt.Sprintf("unknown mediatype in msg %+v", msg))
+		LOG_WARNING(-1, fmt.Sprintf("unknown mediatype in msg %s", DumpValue(msg)))
 		return ""
 	}
 
@@ -370,41 +651,41 @@ func DownloadableMessageToFileId(client *whatsmeow.Client, msg whatsmeow.Downloa
 	} else if len(msg.GetDirectPath()) > 0 {
 		info.DirectPath = msg.GetDirectPath()
 	} else {
-		LOG_WARNING("url and path not present")
+		LOG_WARNING(-1, "url and path not present")
 		return ""
 	}
 
-	LOG_TRACE(fmt.Sprintf("fileInfo %#v", info))
+	LOG_TRACE(-1, fmt.Sprintf("fileInfo %s", DumpValue(info)))
 	bytes, err := json.Marshal(info)
 	if err != nil {
-		LOG_WARNING("json encode failed")
+		LOG_WARNING(-1, "json encode failed")
 		return ""
 	}
 
 	str := string(bytes)
-	LOG_TRACE(fmt.Sprintf("fileId %s", str))
+	LOG_TRACE(-1, fmt.Sprintf("fileId %s", RedactKey(str)))
 
 	return str
 }
 
 func DownloadFromFileId(connId int, fileId string) (string, int) {
-	LOG_TRACE(fmt.Sprintf("fileId %s", fileId))
+	LOG_TRACE(connId, fmt.Sprintf("fileId %s", RedactKey(fileId)))
 	var info DownloadInfo
 	if err := json.Unmarshal([]byte(fileId), &info); err != nil {
-		LOG_WARNING(fmt.Sprintf("unmarshal fileId failed: %v", err))
+		LOG_WARNING(connId, fmt.Sprintf("unmarshal fileId failed: %v", err))
 		return "", FileStatusDownloadFailed
 	}
 	if info.Version != downloadInfoVersion {
-		LOG_WARNING(fmt.Sprintf("unsupported version %d", info.Version))
+		LOG_WARNING(connId, fmt.Sprintf("unsupported version %d", info.Version))
 		return "", FileStatusDownloadFailed
 	}
 
-	LOG_TRACE(fmt.Sprintf("fileInfo %#v", info))
+	LOG_TRACE(connId, fmt.Sprintf("fileInfo %s", DumpValue(info)))
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return "", FileStatusDownloadFailed
 	}
 
@@ -414,26 +695,26 @@ func DownloadFromFileId(connId int, fileId string) (string, int) {
 
 	// download if not yet present
 	if _, statErr := os.Stat(targetPath); os.IsNotExist(statErr) {
-		LOG_TRACE(fmt.Sprintf("download new %#v", targetPath))
+		LOG_TRACE(connId, fmt.Sprintf("download new %#v", targetPath))
 		CWmSetStatus(connId, FlagFetching)
 
 		data, err := DownloadFromFileInfo(client, info)
 		if err != nil {
-			LOG_WARNING(fmt.Sprintf("download error %#v", err))
+			LOG_WARNING(connId, fmt.Sprintf("download error %#v", err))
 			fileStatus = FileStatusDownloadFailed
 		} else {
 			file, err := os.Create(targetPath)
 			if err != nil {
-				LOG_WARNING(fmt.Sprintf("create error %#v", err))
+				LOG_WARNING(connId, fmt.Sprintf("create error %#v", err))
 				fileStatus = FileStatusDownloadFailed
 			} else {
 				defer file.Close()
 				_, err = file.Write(data)
 				if err != nil {
-					LOG_WARNING(fmt.Sprintf("write error %#v", err))
+					LOG_WARNING(connId, fmt.Sprintf("write error %#v", err))
 					fileStatus = FileStatusDownloadFailed
 				} else {
-					LOG_TRACE("download ok")
+					LOG_TRACE(connId, "download ok")
 					filePath = targetPath
 					fileStatus = FileStatusDownloaded
 				}
@@ -441,7 +722,7 @@ func DownloadFromFileId(connId int, fileId string) (string, int) {
 		}
 		CWmClearStatus(connId, FlagFetching)
 	} else {
-		LOG_TRACE(fmt.Sprintf("download cached %#v", targetPath))
+		LOG_TRACE(connId, fmt.Sprintf("download cached %#v", targetPath))
 		filePath = targetPath
 		fileStatus = FileStatusDownloaded
 	}
@@ -452,31 +733,82 @@ func DownloadFromFileId(connId int, fileId string) (string, int) {
 func DownloadFromFileInfo(client *whatsmeow.Client, info DownloadInfo) ([]byte, error) {
 	ctx := context.TODO()
 	if len(info.Url) > 0 {
-		LOG_TRACE(fmt.Sprintf("download url: %s", info.Url))
+		LOG_TRACE(-1, fmt.Sprintf("download url: %s", info.Url))
 		return client.DownloadMediaWithUrl(ctx, info.Url, info.MediaKey, info.MediaType, info.Size, info.FileEncSha256, info.FileSha256)
 	} else if len(info.DirectPath) > 0 {
-		LOG_TRACE(fmt.Sprintf("download directpath: %s", info.DirectPath))
+		LOG_TRACE(-1, fmt.Sprintf("download directpath: %s", info.DirectPath))
 		return client.DownloadMediaWithPath(ctx, info.DirectPath, info.FileEncSha256, info.FileSha256, info.MediaKey, info.Size, info.MediaType, whatsmeow.GetMMSType(info.MediaType))
 	} else {
-		LOG_WARNING("url and path not present")
+		LOG_WARNING(-1, "url and path not present")
 		return nil, whatsmeow.ErrNoURLPresent
 	}
 }
 
-// utils
-func ShowImage(path string) {
-	switch runtime.GOOS {
-	case "linux":
-		LOG_DEBUG("xdg-open " + path)
-		exec.Command("xdg-open", path).Start()
-	case "darwin":
-		LOG_DEBUG("open " + path)
-		exec.Command("open", path).Start()
-	default:
-		LOG_WARNING(fmt.Sprintf("unsupported os \"%s\"", runtime.GOOS))
+// log redaction
+var jidRegexp = regexp.MustCompile(`([0-9A-Za-z._-]+)((?::[0-9]+)?@(?:s\.whatsapp\.net|g\.us|c\.us|lid|broadcast|newsletter|msgr|interop|hosted\.lid|hosted|bot))`)
+
+// per-process key, so hashes can't be matched against precomputed phone numbers
+var jidHashKey = NewJidHashKey()
+
+func NewJidHashKey() []byte {
+	key := make([]byte, 32)
+	if _, err := rand.Read(key); err != nil {
+		panic(fmt.Sprintf("jid hash key: %#v", err))
+	}
+	return key
+}
+
+func HashJid(user string) string {
+	mac := hmac.New(sha256.New, jidHashKey)
+	mac.Write([]byte(user))
+	return "#" + hex.EncodeToString(mac.Sum(nil))[:8]
+}
+
+func RedactJids(message string) string {
+	if GetRedaction() < RedactHashJids {
+		return message
+	}
+
+	return jidRegexp.ReplaceAllStringFunc(message, func(jid string) string {
+		parts := jidRegexp.FindStringSubmatch(jid)
+		return HashJid(parts[1]) + parts[2]
+	})
+}
+
+func RedactPhone(phone string) string {
+	if GetRedaction() < RedactHashJids || phone == "" {
+		return phone
+	}
+
+	return HashJid(phone)
+}
+
+func RedactText(text string) string {
+	if GetRedaction() < RedactStripContent {
+		return text
 	}
+
+	return fmt.Sprintf("<%d bytes>", len(text))
+}
+
+func RedactKey(key string) string {
+	if GetRedaction() < RedactStripContent {
+		return key
+	}
+
+	return "<redacted>"
+}
+
+func DumpValue(v interface{}) string {
+	// full dumps contain jids, message bodies and media keys
+	if GetRedaction() >= RedactHashJids {
+		return fmt.Sprintf("%T", v)
+	}
+
+	return fmt.Sprintf("%#v", v)
 }
 
+// utils
 func GetOSName() string {
 	switch runtime.GOOS {
 	case "linux":
@@ -501,89 +833,22 @@ func GetPhoneNumberFromPath(path string) string {
 	return ""
 }
 
-func GetConfigOrEnvFlag(envVarName string) bool {
+func GetConfigOrEnvFlag(connId int, envVarName string) bool {
 	configParamName := strings.ToLower(envVarName)
-	isConfigSet := CWmAppConfigGetNum(configParamName)
+	isConfigSet := CWmAppConfigGetNum(connId, configParamName)
 	if IntToBool(isConfigSet) {
 		return true
 	}
 
 	_, isEnvSet := os.LookupEnv(envVarName)
 	if isEnvSet {
-		CWmAppConfigSetNum(configParamName, 1)
+		CWmAppConfigSetNum(connId, configParamName, 1)
 		return true
 	}
 
 	return false
 }
 
-func HasGUI() bool {
-	useQrTerminal := GetConfigOrEnvFlag("USE_QR_TERMINAL")
-	if useQrTerminal {
-		return false
-	}
-
-	switch runtime.GOOS {
-	case "darwin":
-		LOG_INFO("has gui")
-		LOG_DEBUG("gui check: [darwin default true]")
-		return true
-
-	case "linux":
-		_, isDisplaySet := os.LookupEnv("DISPLAY")
-		file, err := ioutil.TempFile("/tmp", "nchat-x11check.*.sh")
-		if err != nil {
-			LOG_WARNING(fmt.Sprintf("create file failed %#v", err))
-			return isDisplaySet
-		}
-
-		defer os.Remove(file.Name())
-		content := "#!/usr/bin/env bash\n\n" +
-			"if command -v timeout &> /dev/null; then\n" +
-			"  CMD=\"timeout 1s xset q\"\n" +
-			"else\n" +
-			"  CMD=\"xset q\"\n" +
-			"fi\n" +
-			"echo \"${CMD}\"\n" +
-			"${CMD} > /dev/null\n" +
-			"exit ${?}\n"
-
-		_, err = io.WriteString(file, content)
-		if err != nil {
-			LOG_WARNING(fmt.Sprintf("write file failed %#v", err))
-			return isDisplaySet
-		}
-
-		err = file.Close()
-		if err != nil {
-			LOG_WARNING(fmt.Sprintf("close file failed %#v", err))
-			return isDisplaySet
-		}
-
-		err = os.Chmod(file.Name(), 0777)
-		if err != nil {
-			LOG_WARNING(fmt.Sprintf("chmod file failed %#v", err))
-			return isDisplaySet
-		}
-
-		cmdout, err := exec.Command(file.Name()).CombinedOutput()
-		if err == nil {
-			LOG_INFO("has gui")
-			LOG_DEBUG(fmt.Sprintf("gui check: %s", strings.TrimSuffix(string(cmdout), "\n")))
-			return true
-		} else {
-			LOG_INFO("no gui")
-			LOG_DEBUG(fmt.Sprintf("gui check: %s", strings.TrimSuffix(string(cmdout), "\n")))
-			return false
-		}
-
-	default:
-		LOG_INFO("no gui")
-		LOG_DEBUG(fmt.Sprintf("gui check: [other \"%s\" default false]", runtime.GOOS))
-		return false
-	}
-}
-
 func BoolToInt(b bool) int {
 	if b {
 		return 1
@@ -595,6 +860,23 @@ func IntToBool(i int) bool {
 	return i != 0
 }
 
+// json of the typed message content passed with CWmNewMessagesNotify,
+// keep in sync with enum MessageContent in types.rs
+func ContentJson(contentType string, fields map[string]interface{}) string {
+	content := map[string]interface{}{"type": contentType}
+	for key, value := range fields {
+		content[key] = value
+	}
+
+	bytes, err := json.Marshal(content)
+	if err != nil {
+		LOG_WARNING(-1, fmt.Sprintf("content json encode failed %#v", err))
+		return "{\"type\":\"" + contentType + "\"}"
+	}
+
+	return string(bytes)
+}
+
 func StringToInt(s string) int {
 	i, err := strconv.Atoi(s)
 	if err != nil {
@@ -606,7 +888,7 @@ func StringToInt(s string) int {
 func SanitizeName(text string) string {
 	newText := strings.ReplaceAll(text, "\n", " ")
 	if newText != text {
-		LOG_DEBUG(fmt.Sprintf("sanitized '%s' -> '%s'", text, newText))
+		LOG_DEBUG(-1, fmt.Sprintf("sanitized '%s' -> '%s'", RedactText(text), RedactText(newText)))
 	}
 	return newText
 }
@@ -635,7 +917,7 @@ func IsSelfChat(client *whatsmeow.Client, chatId string) bool {
 // Get chat id
 func GetChatId(client *whatsmeow.Client, chatJid *types.JID, senderJid *types.JID) string {
 	if chatJid == nil {
-		LOG_WARNING(fmt.Sprintf("chatJid is nil\n%s", string(debug.Stack())))
+		LOG_WARNING(-1, fmt.Sprintf("chatJid is nil\n%s", string(debug.Stack())))
 		return ""
 	} else if chatJid.Server == types.BroadcastServer && chatJid.User == "status" {
 		// place status messages under a dedicated chat
@@ -669,7 +951,7 @@ func GetChatId(client *whatsmeow.Client, chatJid *types.JID, senderJid *types.JI
 // Get user id
 func GetUserId(client *whatsmeow.Client, chatJid *types.JID, userJid *types.JID) string {
 	if userJid == nil {
-		LOG_WARNING(fmt.Sprintf("userJid is nil\n%s", string(debug.Stack())))
+		LOG_WARNING(-1, fmt.Sprintf("userJid is nil\n%s", string(debug.Stack())))
 		return ""
 	} else if chatJid != nil && chatJid.Server == types.GroupServer {
 		// use sender jid as-is in group
@@ -745,49 +1027,59 @@ func ExtensionByType(mimeType string, defaultExt string) string {
 }
 
 // logger
-type ncLogger struct{}
+type ncLogger struct {
+	connId int
+}
 
 func (s *ncLogger) Debugf(msg string, args ...interface{}) {
-	LOG_TRACE(fmt.Sprintf("whatsmeow %s", fmt.Sprintf(msg, args...)))
+	// skip formatting of (very verbose) messages nobody will see
+	if !LogLevelEnabled(LogLevelTrace) {
+		return
+	}
+	LOG_TRACE(s.connId, fmt.Sprintf("whatsmeow %s", fmt.Sprintf(msg, args...)))
 }
 
 func (s *ncLogger) Infof(msg string, args ...interface{}) {
-	LOG_INFO(fmt.Sprintf("whatsmeow %s", fmt.Sprintf(msg, args...)))
+	if !LogLevelEnabled(LogLevelInfo) {
+		return
+	}
+	LOG_INFO(s.connId, fmt.Sprintf("whatsmeow %s", fmt.Sprintf(msg, args...)))
 }
 
 func (s *ncLogger) Warnf(msg string, args ...interface{}) {
-	LOG_WARNING(fmt.Sprintf("whatsmeow %s", fmt.Sprintf(msg, args...)))
+	LOG_WARNING(s.connId, fmt.Sprintf("whatsmeow %s", fmt.Sprintf(msg, args...)))
 }
 
 func (s *ncLogger) Errorf(msg string, args ...interface{}) {
-	LOG_ERROR(fmt.Sprintf("whatsmeow %s", fmt.Sprintf(msg, args...)))
+	LOG_ERROR(s.connId, fmt.Sprintf("whatsmeow %s", fmt.Sprintf(msg, args...)))
 }
 
 func (s *ncLogger) Sub(mod string) waLog.Logger {
 	return s
 }
 
-func NcLogger() waLog.Logger {
-	return &ncLogger{}
+func NcLogger() *ncLogger {
+	// connId is set once known, i.e. after AddConn
+	return &ncLogger{connId: -1}
 }
 
 // loggable
 type ncSignalLogger struct{}
 
 func (s *ncSignalLogger) Debug(caller, msg string) {
-	LOG_DEBUG(fmt.Sprintf("whatsmeow %s", fmt.Sprintf("%s %s", caller, msg)))
+	LOG_DEBUG(-1, fmt.Sprintf("whatsmeow %s", fmt.Sprintf("%s %s", caller, msg)))
 }
 
 func (s *ncSignalLogger) Info(caller, msg string) {
-	LOG_INFO(fmt.Sprintf("whatsmeow %s", fmt.Sprintf("%s %s", caller, msg)))
+	LOG_INFO(-1, fmt.Sprintf("whatsmeow %s", fmt.Sprintf("%s %s", caller, msg)))
 }
 
 func (s *ncSignalLogger) Warning(caller, msg string) {
-	LOG_WARNING(fmt.Sprintf("whatsmeow %s", fmt.Sprintf("%s %s", caller, msg)))
+	LOG_WARNING(-1, fmt.Sprintf("whatsmeow %s", fmt.Sprintf("%s %s", caller, msg)))
 }
 
 func (s *ncSignalLogger) Error(caller, msg string) {
-	LOG_ERROR(fmt.Sprintf("whatsmeow %s", fmt.Sprintf("%s %s", caller, msg)))
+	LOG_ERROR(-1, fmt.Sprintf("whatsmeow %s", fmt.Sprintf("%s %s", caller, msg)))
 }
 
 func (s *ncSignalLogger) Configure(ss string) {
@@ -803,27 +1095,27 @@ func (handler *WmEventHandler) HandleEvent(rawEvt interface{}) {
 
 	case *events.AppStateSyncComplete:
 		// this happens after initial logon via QR code
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		if evt.Name == appstate.WAPatchCriticalBlock {
-			LOG_TRACE("AppStateSyncComplete WAPatchCriticalBlock")
+			LOG_TRACE(handler.connId, "AppStateSyncComplete WAPatchCriticalBlock")
 			handler.HandleConnected()
 		} else if evt.Name == appstate.WAPatchRegular {
-			LOG_TRACE("AppStateSyncComplete WAPatchRegular")
+			LOG_TRACE(handler.connId, "AppStateSyncComplete WAPatchRegular")
 			handler.HandleSyncContacts()
 		}
 
 	case *events.PushNameSetting:
 		// send presence when the pushname is changed remotely
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleConnected()
 
 	case *events.PushName:
 		// other device changed our friendly name
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 
 	case *events.Connected:
 		// connected
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleConnected()
 		SetState(handler.connId, Connected)
 		CWmSetStatus(handler.connId, FlagOnline)
@@ -831,93 +1123,102 @@ func (handler *WmEventHandler) HandleEvent(rawEvt interface{}) {
 
 	case *events.Disconnected:
 		// disconnected
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		CWmClearStatus(handler.connId, FlagOnline)
 
 	case *events.StreamReplaced:
 		// TODO: find out when exactly this happens and how to handle it
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 
 	case *events.Message:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleMessage(evt.Info, evt.Message, false /*isSyncRead*/)
 
 	case *events.Receipt:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleReceipt(evt)
 
 	case *events.Presence:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandlePresence(evt)
 
 	case *events.ChatPresence:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleChatPresence(evt)
 
 	case *events.HistorySync:
 		// This happens after initial logon via QR code (after AppStateSyncComplete)
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleHistorySync(evt)
 
 	case *events.AppState:
-		LOG_TRACE(fmt.Sprintf("%#v - %#v / %#v", evt, evt.Index, evt.SyncActionValue))
+		LOG_TRACE(handler.connId, fmt.Sprintf("%s - %s / %s", DumpValue(evt), DumpValue(evt.Index), DumpValue(evt.SyncActionValue)))
 
 	case *events.LoggedOut:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleLoggedOut()
 
 	case *events.QR:
 		// handled in WmLogin
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 
 	case *events.PairSuccess:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
+		CWmLoginProgressNotify(handler.connId, LoginPairSuccess)
+
+	case *events.PairError:
+		LOG_WARNING(handler.connId, fmt.Sprintf("pair error %#v", evt.Error))
+		CWmLoginProgressNotify(handler.connId, LoginPairError)
 
 	case *events.JoinedGroup:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 
 	case *events.OfflineSyncCompleted:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleSyncContacts()
 
 	case *events.GroupInfo:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleGroupInfo(evt)
 
+	case *events.Picture:
+		LOG_TRACE(handler.connId, DumpValue(evt))
+		handler.HandlePicture(evt)
+
 	case *events.DeleteChat:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleDeleteChat(evt)
 
 	case *events.Mute:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleMute(evt)
 
 	case *events.Archive:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleArchive(evt)
 
 	case *events.Pin:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandlePin(evt)
 
 	case *events.ClientOutdated:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleClientOutdated()
 
 	case *events.DeleteForMe:
-		LOG_TRACE(fmt.Sprintf("%#v", evt))
+		LOG_TRACE(handler.connId, DumpValue(evt))
 		handler.HandleDeleteForMe(evt)
 
 	default:
-		LOG_TRACE(fmt.Sprintf("Event type not handled: %#v", rawEvt))
+		LOG_TRACE(handler.connId, fmt.Sprintf("Event type not handled: %s", DumpValue(rawEvt)))
 	}
 }
 
 func (handler *WmEventHandler) HandleConnected() {
-	LOG_TRACE("HandleConnected")
+	LOG_TRACE(handler.connId, "HandleConnected")
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(handler.connId, "client is nil")
 		return
 	}
 
@@ -928,17 +1229,17 @@ func (handler *WmEventHandler) HandleConnected() {
 
 func (handler *WmEventHandler) HandleReceipt(receipt *events.Receipt) {
 	if receipt.Type == events.ReceiptTypeRead || receipt.Type == events.ReceiptTypeReadSelf {
-		LOG_TRACE(fmt.Sprintf("%#v was read by %s at %s", receipt.MessageIDs, receipt.SourceString(), receipt.Timestamp))
+		LOG_TRACE(handler.connId, fmt.Sprintf("%#v was read by %s at %s", receipt.MessageIDs, receipt.SourceString(), receipt.Timestamp))
 		connId := handler.connId
 		var client *whatsmeow.Client = GetClient(connId)
 		if client == nil {
-			LOG_WARNING("client is nil")
+			LOG_WARNING(connId, "client is nil")
 			return
 		}
 		chatId := GetChatId(client, &receipt.MessageSource.Chat, nil)
 		isRead := true
 		for _, msgId := range receipt.MessageIDs {
-			LOG_TRACE("Call CWmNewMessageStatusNotify")
+			LOG_TRACE(connId, "Call CWmNewMessageStatusNotify")
 			CWmNewMessageStatusNotify(connId, chatId, msgId, BoolToInt(isRead))
 		}
 	}
@@ -949,13 +1250,13 @@ func (handler *WmEventHandler) HandlePresence(presence *events.Presence) {
 		connId := handler.connId
 		var client *whatsmeow.Client = GetClient(connId)
 		if client == nil {
-			LOG_WARNING("client is nil")
+			LOG_WARNING(connId, "client is nil")
 			return
 		}
 		userId := GetUserId(client, nil, &presence.From)
 		isOnline := !presence.Unavailable
 		timeSeen := int(presence.LastSeen.Unix())
-		LOG_TRACE("Call CWmNewStatusNotify")
+		LOG_TRACE(connId, "Call CWmNewStatusNotify")
 		CWmNewStatusNotify(connId, userId, BoolToInt(isOnline), timeSeen)
 	}
 }
@@ -964,39 +1265,40 @@ func (handler *WmEventHandler) HandleChatPresence(chatPresence *events.ChatPrese
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 	chatId := GetChatId(client, &chatPresence.MessageSource.Chat, &chatPresence.MessageSource.Sender)
 	userId := GetUserId(client, &chatPresence.MessageSource.Chat, &chatPresence.MessageSource.Sender)
 	isTyping := (chatPresence.State == types.ChatPresenceComposing)
-	LOG_TRACE("Call CWmNewTypingNotify")
+	LOG_TRACE(connId, "Call CWmNewTypingNotify")
 	CWmNewTypingNotify(connId, chatId, userId, BoolToInt(isTyping))
 }
 
 func (handler *WmEventHandler) HandleHistorySync(historySync *events.HistorySync) {
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil || client.Store.ID == nil {
-		LOG_WARNING("client or Store.ID is nil")
+		LOG_WARNING(handler.connId, "client or Store.ID is nil")
 		return
 	}
 	selfJid := *client.Store.ID
 
-	LOG_TRACE(fmt.Sprintf("HandleHistorySync SyncType %s Progress %d",
+	LOG_TRACE(handler.connId, fmt.Sprintf("HandleHistorySync SyncType %s Progress %d",
 		historySync.Data.GetSyncType().String(), historySync.Data.GetProgress()))
 
 	if historySync.Data.GetProgress() < 98 {
-		LOG_TRACE("Set Syncing")
+		LOG_TRACE(handler.connId, "Set Syncing")
 		CWmSetStatus(handler.connId, FlagSyncing)
 	}
 
 	conversations := historySync.Data.GetConversations()
 	for _, conversation := range conversations {
-		LOG_TRACE(fmt.Sprintf("HandleHistorySync Conversation %#v", conversation))
+		LOG_TRACE(handler.connId, fmt.Sprintf("HandleHistorySync Conversation %s", DumpValue(conversation)))
 
 		chatJid, _ := types.ParseJID(conversation.GetID())
 
-		isUnread := 0
+		unreadCount := int(conversation.GetUnreadCount())
+		isUnread := BoolToInt((unreadCount > 0) || conversation.GetMarkedAsUnread())
 		lastMessageTime := 0
 
 		isSyncRead := (conversation.GetUnreadCount() == 0)
@@ -1023,161 +1325,192 @@ func (handler *WmEventHandler) HandleHistorySync(historySync *events.HistorySync
 		chatId := GetChatId(client, &chatJid, nil)
 		if hasMessages {
 			isMuted := false
-			isPinned := false
+			mutedUntil := 0 // -1 = forever
+			isPinned := conversation.GetPinned() > 0
 			isArchived := conversation.GetArchived()
+			name := conversation.GetName()
+			ephemeralTimer := int(conversation.GetEphemeralExpiration())
+			SetExpiration(handler.connId, chatId, uint32(ephemeralTimer))
 			ctx := context.TODO()
 			settings, setErr := client.Store.ChatSettings.GetChatSettings(ctx, chatJid)
 			if setErr != nil {
-				LOG_WARNING(fmt.Sprintf("Get chat settings failed %#v", setErr))
+				LOG_WARNING(handler.connId, fmt.Sprintf("Get chat settings failed %#v", setErr))
 			}
 			// App state stores settings under LID JID, try that if phone JID lookup missed
 			if !settings.Found && chatJid.Server == types.DefaultUserServer {
 				if lidJid, err := client.Store.LIDs.GetLIDForPN(ctx, chatJid); err == nil && !lidJid.IsEmpty() {
 					settings, setErr = client.Store.ChatSettings.GetChatSettings(ctx, lidJid)
 					if setErr != nil {
-						LOG_WARNING(fmt.Sprintf("Get chat settings by LID failed %#v", setErr))
+						LOG_WARNING(handler.connId, fmt.Sprintf("Get chat settings by LID failed %#v", setErr))
 					}
 				}
 			}
 			if settings.Found {
-				mutedUntil := settings.MutedUntil.Unix()
-				isMuted = (mutedUntil == -1) || (mutedUntil > time.Now().Unix())
+				mutedUntilUnix := settings.MutedUntil.Unix()
+				isMuted = (mutedUntilUnix == -1) || (mutedUntilUnix > time.Now().Unix())
+				if isMuted {
+					mutedUntil = int(mutedUntilUnix)
+				}
 				isPinned = settings.Pinned
 				isArchived = isArchived || settings.Archived
 			} else {
-				LOG_DEBUG(fmt.Sprintf("Chat settings not found %s", chatId))
+				LOG_DEBUG(handler.connId, fmt.Sprintf("Chat settings not found %s", chatId))
 			}
 
-			LOG_TRACE(fmt.Sprintf("Call CWmNewChatsNotify %s muted=%t pinned=%t archived=%t",
-				chatId, isMuted, isPinned, isArchived))
-			CWmNewChatsNotify(handler.connId, chatId, isUnread, BoolToInt(isMuted), BoolToInt(isPinned), BoolToInt(isArchived), lastMessageTime)
+			LOG_TRACE(handler.connId, fmt.Sprintf("Call CWmNewChatsNotify %s unread=%d muted=%t pinned=%t archived=%t ephemeral=%d",
+				chatId, unreadCount, isMuted, isPinned, isArchived, ephemeralTimer))
+			CWmNewChatsNotify(handler.connId, chatId, name, isUnread, unreadCount, BoolToInt(isMuted), mutedUntil, BoolToInt(isPinned), BoolToInt(isArchived), ephemeralTimer, lastMessageTime)
 		} else {
-			LOG_TRACE(fmt.Sprintf("Skip CWmNewChatsNotify %s %d", chatId, len(syncMessages)))
+			LOG_TRACE(handler.connId, fmt.Sprintf("Skip CWmNewChatsNotify %s %d", chatId, len(syncMessages)))
 		}
 
 	}
 
 	if historySync.Data.GetProgress() == 100 {
-		LOG_TRACE("Clear Syncing")
+		LOG_TRACE(handler.connId, "Clear Syncing")
 		CWmClearStatus(handler.connId, FlagSyncing)
 	}
 }
 
+// keep in sync with struct RawGroupChange in whatsmeow-nchat-sys/src/handlers.rs
+type GroupChangeJson struct {
+	Type    string   `json:"type"`
+	Users   []string `json:"users,omitempty"`
+	Text    string   `json:"text,omitempty"`
+	Setting string   `json:"setting,omitempty"`
+	Enabled bool     `json:"enabled,omitempty"`
+	Invite  bool     `json:"invite,omitempty"`
+}
+
+func NotifyGroupChange(connId int, chatId string, actorId string, timeSent int, change GroupChangeJson) {
+	changeJsonBytes, jsonErr := json.Marshal(change)
+	if jsonErr != nil {
+		LOG_WARNING(connId, fmt.Sprintf("marshal group change err %#v", jsonErr))
+		return
+	}
+
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewGroupChangeNotify %s %s %s", chatId, actorId, change.Type))
+	CWmNewGroupChangeNotify(connId, chatId, actorId, timeSent, string(changeJsonBytes))
+}
+
 func (handler *WmEventHandler) HandleGroupInfo(groupInfo *events.GroupInfo) {
 	connId := handler.connId
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 	chatId := GetChatId(client, &groupInfo.JID, nil)
-	userId := GetUserId(client, &groupInfo.JID, groupInfo.Sender)
 
-	senderJidStr := ""
-	if userId != chatId {
-		senderJidStr = userId
+	// who made the change, unknown for some changes
+	actorId := ""
+	if groupInfo.SenderPN != nil && !groupInfo.SenderPN.IsEmpty() {
+		actorId = StrFromJid(*groupInfo.SenderPN)
+	} else if groupInfo.Sender != nil && !groupInfo.Sender.IsEmpty() {
+		actorId = GetUserId(client, nil, groupInfo.Sender)
 	}
 
-	// text
-	text := ""
-	if groupInfo.Name != nil {
-		// Group name change
-		if senderJidStr == "" {
-			senderJidStr = chatId
-		}
-
-		groupName := *groupInfo.Name
-		text = "[Changed group name to " + groupName.Name + "]"
-	} else if len(groupInfo.Join) > 0 {
-		// Group member joined
-		if (len(groupInfo.Join) == 1) && ((senderJidStr == "") || (senderJidStr == GetUserId(client, &groupInfo.JID, &groupInfo.Join[0]))) {
-			senderJidStr = GetUserId(client, &groupInfo.JID, &groupInfo.Join[0])
-			text = "[Joined]"
-		} else {
-			if senderJidStr == "" {
-				senderJidStr = GetChatId(client, &groupInfo.JID, nil)
-			}
+	timeSent := int(groupInfo.Timestamp.Unix())
 
-			joined := ""
-			for _, jid := range groupInfo.Join {
-				if joined != "" {
-					joined += ", "
-				}
+	userIds := func(jids []types.JID) []string {
+		ids := []string{}
+		for _, jid := range jids {
+			ids = append(ids, GetUserId(client, nil, &jid))
+		}
+		return ids
+	}
 
-				joined += GetContactName(connId, GetUserId(client, nil, &jid))
-			}
+	// disappearing messages timer change
+	if groupInfo.Ephemeral != nil {
+		timer := uint32(0)
+		if groupInfo.Ephemeral.IsEphemeral {
+			timer = groupInfo.Ephemeral.DisappearingTimer
+		}
+		SetExpiration(connId, chatId, timer)
+		LOG_TRACE(connId, fmt.Sprintf("Call CWmNewEphemeralSettingNotify %s %d", chatId, timer))
+		CWmNewEphemeralSettingNotify(connId, chatId, int(timer))
+	}
+
+	if groupInfo.Name != nil {
+		AddContactName(connId, chatId, groupInfo.Name.Name)
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "NameChanged", Text: groupInfo.Name.Name})
+	}
 
-			text = "[Added " + joined + "]"
+	if groupInfo.Topic != nil {
+		topic := groupInfo.Topic.Topic
+		if groupInfo.Topic.TopicDeleted {
+			topic = ""
 		}
-	} else if len(groupInfo.Leave) > 0 {
-		// Group member left
-		if (len(groupInfo.Leave) == 1) && ((senderJidStr == "") || (senderJidStr == GetUserId(client, &groupInfo.JID, &groupInfo.Leave[0]))) {
-			senderJidStr = GetUserId(client, &groupInfo.JID, &groupInfo.Leave[0])
-			fromMe := IsSelfUser(client, senderJidStr)
-			if !fromMe {
-				text = "[Left]"
-			}
-		} else {
-			if senderJidStr == "" {
-				senderJidStr = GetChatId(client, &groupInfo.JID, nil)
-			}
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "TopicChanged", Text: topic})
+	}
 
-			left := ""
-			for _, jid := range groupInfo.Leave {
-				if left != "" {
-					left += ", "
-				}
+	if len(groupInfo.Join) > 0 {
+		// joining with an invite link is reported as being added
+		invite := groupInfo.JoinReason == "invite"
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "ParticipantsAdded", Users: userIds(groupInfo.Join), Invite: invite})
+	}
 
-				left += GetContactName(connId, GetUserId(client, nil, &jid))
-			}
+	if len(groupInfo.Leave) > 0 {
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "ParticipantsRemoved", Users: userIds(groupInfo.Leave)})
+	}
 
-			text = "[Removed " + left + "]"
-		}
+	if len(groupInfo.Promote) > 0 {
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "Promoted", Users: userIds(groupInfo.Promote)})
 	}
 
-	if text == "" {
-		LOG_TRACE("HandleGroupInfo ignore")
-		return
-	} else {
-		LOG_TRACE("HandleGroupInfo notify")
+	if len(groupInfo.Demote) > 0 {
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "Demoted", Users: userIds(groupInfo.Demote)})
 	}
 
-	// context
-	quotedId := ""
+	if groupInfo.Announce != nil {
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "SettingsChanged", Setting: "announce", Enabled: groupInfo.Announce.IsAnnounce})
+	}
 
-	// file id, path and status
-	fileId := ""
-	filePath := ""
-	fileStatus := FileStatusNone
+	if groupInfo.Locked != nil {
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "SettingsChanged", Setting: "locked", Enabled: groupInfo.Locked.IsLocked})
+	}
 
-	// general
-	timeSent := int(groupInfo.Timestamp.Unix())
-	msgId := strconv.Itoa(timeSent) // group info updates do not have msg id
-	fromMe := IsSelfUser(client, senderJidStr)
-	senderId := senderJidStr
-	isSelfChat := IsSelfChat(client, chatId)
-	isSyncRead := false
-	isRead := IsRead(isSyncRead, isSelfChat, fromMe, groupInfo.Timestamp, GetTimeRead(connId, chatId))
-	isEdited := false
+	if groupInfo.MembershipApprovalMode != nil {
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "SettingsChanged", Setting: "joinApproval", Enabled: groupInfo.MembershipApprovalMode.IsJoinApprovalRequired})
+	}
 
-	// reset typing if needed
-	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
+	if groupInfo.NewInviteLink != nil {
+		NotifyGroupChange(connId, chatId, actorId, timeSent, GroupChangeJson{Type: "InviteLinkReset"})
+	}
+}
+
+func (handler *WmEventHandler) HandlePicture(picture *events.Picture) {
+	connId := handler.connId
+	if picture.JID.Server != types.GroupServer {
+		LOG_TRACE(connId, "HandlePicture ignore")
+		return
+	}
+
+	client := GetClient(connId)
+	if client == nil {
+		LOG_WARNING(connId, "client is nil")
+		return
+	}
+	chatId := GetChatId(client, &picture.JID, nil)
+
+	actorId := ""
+	if !picture.Author.IsEmpty() {
+		actorId = GetUserId(client, nil, &picture.Author)
+	}
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: %s", chatId, text))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	NotifyGroupChange(connId, chatId, actorId, int(picture.Timestamp.Unix()), GroupChangeJson{Type: "PhotoChanged", Enabled: !picture.Remove})
 }
 
 func (handler *WmEventHandler) HandleDeleteChat(deleteChat *events.DeleteChat) {
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 	chatId := GetChatId(client, &deleteChat.JID, nil)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmDeleteChatNotify %s", chatId))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmDeleteChatNotify %s", chatId))
 	CWmDeleteChatNotify(connId, chatId)
 }
 
@@ -1185,19 +1518,19 @@ func (handler *WmEventHandler) HandleMute(mute *events.Mute) {
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 	chatId := GetChatId(client, &mute.JID, nil)
 	muteAction := mute.Action
 	if muteAction == nil {
-		LOG_WARNING("mute event missing mute action")
+		LOG_WARNING(connId, "mute event missing mute action")
 		return
 	}
 
 	isMuted := muteAction.GetMuted()
 
-	LOG_TRACE(fmt.Sprintf("Call CWmUpdateMuteNotify %s %s", chatId, strconv.FormatBool(isMuted)))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmUpdateMuteNotify %s %s", chatId, strconv.FormatBool(isMuted)))
 	CWmUpdateMuteNotify(connId, chatId, BoolToInt(isMuted))
 }
 
@@ -1205,19 +1538,19 @@ func (handler *WmEventHandler) HandleArchive(archive *events.Archive) {
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 	chatId := GetChatId(client, &archive.JID, nil)
 	archiveAction := archive.Action
 	if archiveAction == nil {
-		LOG_WARNING("archive event missing archive action")
+		LOG_WARNING(connId, "archive event missing archive action")
 		return
 	}
 
 	isArchived := archiveAction.GetArchived()
 
-	LOG_TRACE(fmt.Sprintf("Call CWmUpdateArchivedNotify %s %t", chatId, isArchived))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmUpdateArchivedNotify %s %t", chatId, isArchived))
 	CWmUpdateArchivedNotify(connId, chatId, BoolToInt(isArchived))
 }
 
@@ -1225,26 +1558,26 @@ func (handler *WmEventHandler) HandlePin(pin *events.Pin) {
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 	chatId := GetChatId(client, &pin.JID, nil)
 	pinAction := pin.Action
 	if pinAction == nil {
-		LOG_WARNING("pin event missing pin action")
+		LOG_WARNING(connId, "pin event missing pin action")
 		return
 	}
 
 	isPinned := pinAction.GetPinned()
 	timePinned := int(pin.Timestamp.Unix())
 
-	LOG_TRACE(fmt.Sprintf("Call CWmUpdatePinNotify %s %s %d", chatId, strconv.FormatBool(isPinned), timePinned))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmUpdatePinNotify %s %s %d", chatId, strconv.FormatBool(isPinned), timePinned))
 	CWmUpdatePinNotify(connId, chatId, BoolToInt(isPinned), timePinned)
 }
 
 func (handler *WmEventHandler) HandleClientOutdated() {
 	connId := handler.connId
-	LOG_WARNING("Client Outdated")
+	LOG_WARNING(connId, "Client Outdated")
 	SetState(connId, Outdated)
 }
 
@@ -1252,20 +1585,20 @@ func (handler *WmEventHandler) HandleDeleteForMe(deleteForMe *events.DeleteForMe
 	connId := handler.connId
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 	chatId := GetChatId(client, &deleteForMe.ChatJID, nil)
 	msgId := deleteForMe.MessageID
-	LOG_TRACE(fmt.Sprintf("Call CWmDeleteMessageNotify %s %s", chatId, msgId))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmDeleteMessageNotify %s %s", chatId, msgId))
 	CWmDeleteMessageNotify(connId, chatId, msgId)
 }
 
 func (handler *WmEventHandler) HandleLoggedOut() {
-	LOG_INFO("logged out by server, reinit")
+	LOG_INFO(handler.connId, "logged out by server, reinit")
 	connId := handler.connId
 
-	LOG_TRACE("Call CWmReinit")
+	LOG_TRACE(connId, "Call CWmReinit")
 	CWmReinit(connId)
 }
 
@@ -1345,22 +1678,22 @@ func PhoneFromUserId(userId string) string {
 		phone = strings.Replace(userId, "@s.whatsapp.net", "", 1)
 	}
 
-	LOG_TRACE(fmt.Sprintf("user %s phone %s", userId, phone))
+	LOG_TRACE(-1, fmt.Sprintf("user %s phone %s", userId, RedactPhone(phone)))
 	return phone
 }
 
 func (handler *WmEventHandler) HandleSyncContacts() {
-	LOG_TRACE("HandleSyncContacts")
+	LOG_TRACE(handler.connId, "HandleSyncContacts")
 	GetContacts(handler.connId)
 }
 
 func GetContacts(connId int) {
-	LOG_TRACE("GetContacts")
+	LOG_TRACE(connId, "GetContacts")
 	CWmSetStatus(connId, FlagFetching)
 
 	var client *whatsmeow.Client = GetClient(connId)
 	if client == nil || client.Store.ID == nil {
-		LOG_WARNING("client or Store.ID is nil")
+		LOG_WARNING(connId, "client or Store.ID is nil")
 		CWmClearStatus(connId, FlagFetching)
 		return
 	}
@@ -1376,13 +1709,13 @@ func GetContacts(connId int) {
 		isSelf := BoolToInt(true) // self
 		isAlias := BoolToInt(false)
 
-		LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify %s %s", selfId, selfName))
+		LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify %s %s", selfId, RedactText(selfName)))
 		CWmNewContactsNotify(connId, selfId, selfName, selfPhone, isSelf, isAlias, notify)
 		AddContactName(connId, selfId, selfName)
 
 		selfLid := StrFromJid(client.Store.LID)
 		isAlias = BoolToInt(true)
-		LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify %s %s", selfLid, selfName))
+		LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify %s %s", selfLid, RedactText(selfName)))
 		CWmNewContactsNotify(connId, selfLid, selfName, selfPhone, isSelf, isAlias, notify)
 		AddContactName(connId, selfLid, selfName)
 	}
@@ -1391,9 +1724,9 @@ func GetContacts(connId int) {
 	ctx := context.TODO()
 	contacts, contErr := client.Store.Contacts.GetAllContacts(ctx)
 	if contErr != nil {
-		LOG_WARNING(fmt.Sprintf("get all contacts failed %#v", contErr))
+		LOG_WARNING(connId, fmt.Sprintf("get all contacts failed %#v", contErr))
 	} else {
-		LOG_TRACE(fmt.Sprintf("contacts %#v", contacts))
+		LOG_TRACE(connId, fmt.Sprintf("contacts %s", DumpValue(contacts)))
 		var userIdPhones map[string]string = make(map[string]string)      // phone
 		var userIdNames map[string]string = make(map[string]string)       // contacts
 		var aliasUserIdNames map[string]string = make(map[string]string)  // public
@@ -1415,7 +1748,7 @@ func GetContacts(connId int) {
 						userIdPhones[userLid] = userIdPhones[userId]
 					}
 				} else {
-					LOG_WARNING(fmt.Sprintf("Skip empty name %s %#v", userId, contactInfo))
+					LOG_WARNING(connId, fmt.Sprintf("Skip empty name %s %s", userId, DumpValue(contactInfo)))
 				}
 			}
 		}
@@ -1447,7 +1780,7 @@ func GetContacts(connId int) {
 					userIdNames[userId] = name
 					userIdPhones[userId] = ""
 				} else {
-					LOG_WARNING(fmt.Sprintf("Skip empty name %s %#v", userId, contactInfo))
+					LOG_WARNING(connId, fmt.Sprintf("Skip empty name %s %s", userId, DumpValue(contactInfo)))
 				}
 			}
 		}
@@ -1471,7 +1804,7 @@ func GetContacts(connId int) {
 			if !isSelf {
 				phone := userIdPhones[userId]
 				isAlias := BoolToInt(false)
-				LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify regular %s %s", userId, name))
+				LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify regular %s %s", userId, RedactText(name)))
 				CWmNewContactsNotify(connId, userId, name, phone, BoolToInt(isSelf), isAlias, notify)
 				AddContactName(connId, userId, name)
 			}
@@ -1483,7 +1816,7 @@ func GetContacts(connId int) {
 			if !isSelf {
 				phone := userIdPhones[userId]
 				isAlias := BoolToInt(true)
-				LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify alias %s %s", userId, name))
+				LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify alias %s %s", userId, RedactText(name)))
 				CWmNewContactsNotify(connId, userId, name, phone, BoolToInt(isSelf), isAlias, notify)
 				AddContactName(connId, userId, name)
 			}
@@ -1495,7 +1828,7 @@ func GetContacts(connId int) {
 			if !isSelf {
 				phone := userIdPhones[userId]
 				isAlias := BoolToInt(true)
-				LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify sender %s %s", userId, name))
+				LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify sender %s %s", userId, RedactText(name)))
 				CWmNewContactsNotify(connId, userId, name, phone, BoolToInt(isSelf), isAlias, notify)
 				AddContactName(connId, userId, name)
 			}
@@ -1505,9 +1838,9 @@ func GetContacts(connId int) {
 	// groups
 	groups, groupErr := client.GetJoinedGroups(ctx)
 	if groupErr != nil {
-		LOG_WARNING(fmt.Sprintf("get joined groups failed %#v", groupErr))
+		LOG_WARNING(connId, fmt.Sprintf("get joined groups failed %#v", groupErr))
 	} else {
-		LOG_TRACE(fmt.Sprintf("groups %#v", groups))
+		LOG_TRACE(connId, fmt.Sprintf("groups %s", DumpValue(groups)))
 		for _, group := range groups {
 			if group == nil {
 				continue
@@ -1518,7 +1851,7 @@ func GetContacts(connId int) {
 			groupPhone := ""
 			isSelf := BoolToInt(false)
 			isAlias := BoolToInt(false)
-			LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify %s %s", groupId, groupName))
+			LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify %s %s", groupId, RedactText(groupName)))
 			CWmNewContactsNotify(connId, groupId, groupName, groupPhone, isSelf, isAlias, notify)
 			AddContactName(connId, groupId, groupName)
 
@@ -1535,7 +1868,7 @@ func GetContacts(connId int) {
 		whatsappPhone := ""
 		isSelf := BoolToInt(false)
 		isAlias := BoolToInt(false)
-		LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify %s %s", whatsappId, whatsappName))
+		LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify %s %s", whatsappId, RedactText(whatsappName)))
 		CWmNewContactsNotify(connId, whatsappId, whatsappName, whatsappPhone, isSelf, isAlias, notify)
 		AddContactName(connId, whatsappId, whatsappName)
 	}
@@ -1548,7 +1881,7 @@ func GetContacts(connId int) {
 		isSelf := BoolToInt(false)
 		isAlias := BoolToInt(false)
 		notify = NotifySendCached // perform notification upon last contact
-		LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify %s %s", statusId, statusName))
+		LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify %s %s", statusId, RedactText(statusName)))
 		CWmNewContactsNotify(connId, statusId, statusName, statusPhone, isSelf, isAlias, notify)
 		AddContactName(connId, statusId, statusName)
 	}
@@ -1583,6 +1916,9 @@ func (handler *WmEventHandler) HandleMessage(messageInfo types.MessageInfo, msg
 	case msg.ReactionMessage != nil:
 		handler.HandleReactionMessage(messageInfo, msg, isSyncRead)
 
+	case msg.PollUpdateMessage != nil:
+		handler.HandlePollUpdateMessage(messageInfo, msg, isSyncRead)
+
 	case msg.ProtocolMessage != nil:
 		handler.HandleProtocolMessage(messageInfo, msg, isSyncRead)
 
@@ -1605,7 +1941,7 @@ func (handler *WmEventHandler) ProcessContextInfo(contextInfo *waE2E.ContextInfo
 				mentionedId := StrFromJid(mentionedJid)              // ex: 121874109111111@lid (skip phone mapping, whatsapp mentions only in groups)
 				mentionedName := GetContactName(connId, mentionedId) // ex: Michael Scott
 				mentionedOrigText := "@" + mentionedStrParts[0]      // ex: @121874109111111
-				mentionedQuoted := CWmAppConfigGetNum("mentions_quoted") != 0
+				mentionedQuoted := CWmAppConfigGetNum(connId, "mentions_quoted") != 0
 				mentionedHasSpace := strings.Contains(mentionedName, " ")
 				var mentionedNewText string
 				if mentionedQuoted && mentionedHasSpace {
@@ -1613,7 +1949,7 @@ func (handler *WmEventHandler) ProcessContextInfo(contextInfo *waE2E.ContextInfo
 				} else {
 					mentionedNewText = "@" + mentionedName // ex: @Michael Scott
 				}
-				LOG_TRACE(fmt.Sprintf("mention jid %s id %s name %s", StrFromJid(mentionedJid), mentionedId, mentionedName)) // @todo: remove
+				LOG_TRACE(connId, fmt.Sprintf("mention jid %s id %s name %s", StrFromJid(mentionedJid), mentionedId, RedactText(mentionedName))) // @todo: remove
 				*text = strings.ReplaceAll(*text, mentionedOrigText, mentionedNewText)
 			}
 		}
@@ -1640,7 +1976,7 @@ func (handler *WmEventHandler) ProcessMessageInfo(messageInfo types.MessageInfo)
 	if pid, _ := client.Store.LIDs.GetPNForLID(ctx, messageInfo.Sender); !pid.IsEmpty() {
 		userPid := StrFromJid(pid)
 		name := PhoneFromUserId(userPid)
-		LOG_TRACE(fmt.Sprintf("add sender %s %s", userId, name))
+		LOG_TRACE(connId, fmt.Sprintf("add sender %s %s", userId, RedactText(name)))
 		AddSender(connId, userId, name)
 
 		if GetNamesSynced(connId) {
@@ -1648,7 +1984,7 @@ func (handler *WmEventHandler) ProcessMessageInfo(messageInfo types.MessageInfo)
 			isSelf := BoolToInt(false)
 			isAlias := BoolToInt(true)
 			var notify int = NotifyDirect // notify without cache
-			LOG_TRACE(fmt.Sprintf("Call CWmNewContactsNotify sender %s %s", userId, name))
+			LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify sender %s %s", userId, RedactText(name)))
 			CWmNewContactsNotify(connId, userId, name, phone, isSelf, isAlias, notify)
 			AddContactName(connId, userId, name)
 		}
@@ -1656,12 +1992,12 @@ func (handler *WmEventHandler) ProcessMessageInfo(messageInfo types.MessageInfo)
 }
 
 func (handler *WmEventHandler) HandleTextMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("TextMessage")
+	LOG_TRACE(handler.connId, "TextMessage")
 
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 	text := ""
@@ -1676,6 +2012,9 @@ func (handler *WmEventHandler) HandleTextMessage(messageInfo types.MessageInfo,
 		handler.ProcessContextInfo(ci, &quotedId, &text)
 	}
 
+	// content
+	contentJson := ContentJson("Text", nil)
+
 	// file id, path and status
 	fileId := ""
 	filePath := ""
@@ -1694,25 +2033,25 @@ func (handler *WmEventHandler) HandleTextMessage(messageInfo types.MessageInfo,
 	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
 	handler.ProcessMessageInfo(messageInfo)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: %s", chatId, text))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewMessagesNotify %s: %s", chatId, RedactText(text)))
+	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, contentJson, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
+		ExpiresAt(messageInfo, msg), BoolToInt(isRead), BoolToInt(isEdited))
 }
 
 func (handler *WmEventHandler) HandleImageMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("ImageMessage")
+	LOG_TRACE(handler.connId, "ImageMessage")
 
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 
 	// get image part
 	img := msg.GetImageMessage()
 	if img == nil {
-		LOG_WARNING("get image message failed")
+		LOG_WARNING(connId, "get image message failed")
 		return
 	}
 
@@ -1728,6 +2067,9 @@ func (handler *WmEventHandler) HandleImageMessage(messageInfo types.MessageInfo,
 	ci := img.GetContextInfo()
 	handler.ProcessContextInfo(ci, &quotedId, &text)
 
+	// content
+	contentJson := ContentJson("Image", nil)
+
 	// file path, id and status
 	filePath := ""
 	fileId := ""
@@ -1750,25 +2092,25 @@ func (handler *WmEventHandler) HandleImageMessage(messageInfo types.MessageInfo,
 	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
 	handler.ProcessMessageInfo(messageInfo)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: image", chatId))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewMessagesNotify %s: image", chatId))
+	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, contentJson, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
+		ExpiresAt(messageInfo, msg), BoolToInt(isRead), BoolToInt(isEdited))
 }
 
 func (handler *WmEventHandler) HandleVideoMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("VideoMessage")
+	LOG_TRACE(handler.connId, "VideoMessage")
 
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 
 	// get video part
 	vid := msg.GetVideoMessage()
 	if vid == nil {
-		LOG_WARNING("get video message failed")
+		LOG_WARNING(connId, "get video message failed")
 		return
 	}
 
@@ -1784,6 +2126,9 @@ func (handler *WmEventHandler) HandleVideoMessage(messageInfo types.MessageInfo,
 	ci := vid.GetContextInfo()
 	handler.ProcessContextInfo(ci, &quotedId, &text)
 
+	// content
+	contentJson := ContentJson("Video", nil)
+
 	// file path, id and status
 	filePath := ""
 	fileId := ""
@@ -1806,25 +2151,25 @@ func (handler *WmEventHandler) HandleVideoMessage(messageInfo types.MessageInfo,
 	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
 	handler.ProcessMessageInfo(messageInfo)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: video", chatId))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewMessagesNotify %s: video", chatId))
+	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, contentJson, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
+		ExpiresAt(messageInfo, msg), BoolToInt(isRead), BoolToInt(isEdited))
 }
 
 func (handler *WmEventHandler) HandleAudioMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("AudioMessage")
+	LOG_TRACE(handler.connId, "AudioMessage")
 
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 
 	// get audio part
 	aud := msg.GetAudioMessage()
 	if aud == nil {
-		LOG_WARNING("get audio message failed")
+		LOG_WARNING(connId, "get audio message failed")
 		return
 	}
 
@@ -1839,6 +2184,9 @@ func (handler *WmEventHandler) HandleAudioMessage(messageInfo types.MessageInfo,
 	ci := aud.GetContextInfo()
 	handler.ProcessContextInfo(ci, &quotedId, &text)
 
+	// content
+	contentJson := ContentJson("Audio", map[string]interface{}{"is_voice_note": aud.GetPTT(), "seconds": aud.GetSeconds()})
+
 	// file id, path and status
 	var tmpPath string = GetPath(connId) + "/tmp"
 	filePath := fmt.Sprintf("%s/%s%s", tmpPath, messageInfo.ID, ext)
@@ -1858,25 +2206,25 @@ func (handler *WmEventHandler) HandleAudioMessage(messageInfo types.MessageInfo,
 	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
 	handler.ProcessMessageInfo(messageInfo)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: audio", chatId))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewMessagesNotify %s: audio", chatId))
+	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, contentJson, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
+		ExpiresAt(messageInfo, msg), BoolToInt(isRead), BoolToInt(isEdited))
 }
 
 func (handler *WmEventHandler) HandleDocumentMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("DocumentMessage")
+	LOG_TRACE(handler.connId, "DocumentMessage")
 
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 
 	// get doc part
 	doc := msg.GetDocumentMessage()
 	if doc == nil {
-		LOG_WARNING("get document message failed")
+		LOG_WARNING(connId, "get document message failed")
 		return
 	}
 
@@ -1889,6 +2237,9 @@ func (handler *WmEventHandler) HandleDocumentMessage(messageInfo types.MessageIn
 	ci := doc.GetContextInfo()
 	handler.ProcessContextInfo(ci, &quotedId, &text)
 
+	// content
+	contentJson := ContentJson("Document", map[string]interface{}{"file_name": doc.GetFileName()})
+
 	// file path, id and status
 	filePath := ""
 	fileId := ""
@@ -1911,25 +2262,25 @@ func (handler *WmEventHandler) HandleDocumentMessage(messageInfo types.MessageIn
 	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
 	handler.ProcessMessageInfo(messageInfo)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: document", chatId))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewMessagesNotify %s: document", chatId))
+	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, contentJson, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
+		ExpiresAt(messageInfo, msg), BoolToInt(isRead), BoolToInt(isEdited))
 }
 
 func (handler *WmEventHandler) HandleStickerMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("StickerMessage")
+	LOG_TRACE(handler.connId, "StickerMessage")
 
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 
 	// get sticker part
 	sticker := msg.GetStickerMessage()
 	if sticker == nil {
-		LOG_WARNING("get sticker message failed")
+		LOG_WARNING(connId, "get sticker message failed")
 		return
 	}
 
@@ -1944,7 +2295,10 @@ func (handler *WmEventHandler) HandleStickerMessage(messageInfo types.MessageInf
 	ci := sticker.GetContextInfo()
 	handler.ProcessContextInfo(ci, &quotedId, &text)
 
-	// file id, path and status
+	// content
+	contentJson := ContentJson("Sticker", map[string]interface{}{"animated": sticker.GetIsAnimated()})
+
+	// file id, path and status
 	var tmpPath string = GetPath(connId) + "/tmp"
 	filePath := fmt.Sprintf("%s/%s%s", tmpPath, messageInfo.ID, ext)
 	fileId := DownloadableMessageToFileId(client, sticker, filePath)
@@ -1963,79 +2317,63 @@ func (handler *WmEventHandler) HandleStickerMessage(messageInfo types.MessageInf
 	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
 	handler.ProcessMessageInfo(messageInfo)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: sticker", chatId))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewMessagesNotify %s: sticker", chatId))
+	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, contentJson, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
+		ExpiresAt(messageInfo, msg), BoolToInt(isRead), BoolToInt(isEdited))
 }
 
 func (handler *WmEventHandler) HandleTemplateMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("TemplateMessage")
+	LOG_TRACE(handler.connId, "TemplateMessage")
 
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 
 	// get template part
 	tpl := msg.GetTemplateMessage()
 	if tpl == nil {
-		LOG_WARNING("get template message failed")
+		LOG_WARNING(connId, "get template message failed")
 		return
 	}
 
 	// handle hydrated template
 	hydtpl := tpl.GetHydratedTemplate()
 	if hydtpl == nil {
-		LOG_TRACE("unhandled template type")
+		LOG_TRACE(connId, "unhandled template type")
 		return
 	}
 
-	// text slice
-	var texts []string
-
-	// title
+	// title (media titles are not downloaded)
+	title := ""
 	switch hydtitle := hydtpl.GetTitle().(type) {
-	case *waE2E.TemplateMessage_HydratedFourRowTemplate_DocumentMessage:
-		texts = append(texts, "[Document]")
-	case *waE2E.TemplateMessage_HydratedFourRowTemplate_ImageMessage:
-		texts = append(texts, "[Image]")
-	case *waE2E.TemplateMessage_HydratedFourRowTemplate_VideoMessage:
-		texts = append(texts, "[Video]")
-	case *waE2E.TemplateMessage_HydratedFourRowTemplate_LocationMessage:
-		texts = append(texts, "[Location]")
 	case *waE2E.TemplateMessage_HydratedFourRowTemplate_HydratedTitleText:
-		if hydtitle.HydratedTitleText != "" {
-			texts = append(texts, hydtitle.HydratedTitleText)
-		}
-	}
-
-	// content
-	content := hydtpl.GetHydratedContentText()
-	if content != "" {
-		texts = append(texts, content)
+		title = hydtitle.HydratedTitleText
 	}
 
 	// buttons
-	buttons := hydtpl.GetHydratedButtons()
-	for _, button := range buttons {
+	buttons := []map[string]interface{}{}
+	for _, button := range hydtpl.GetHydratedButtons() {
 		switch hydbutton := button.GetHydratedButton().(type) {
 		case *waE2E.HydratedTemplateButton_QuickReplyButton:
-			texts = append(texts, fmt.Sprintf("%s", hydbutton.QuickReplyButton.GetDisplayText()))
+			buttons = append(buttons, map[string]interface{}{"type": "QuickReply",
+				"text": hydbutton.QuickReplyButton.GetDisplayText(), "id": hydbutton.QuickReplyButton.GetID()})
+		case *waE2E.HydratedTemplateButton_UrlButton:
+			buttons = append(buttons, map[string]interface{}{"type": "Url",
+				"text": hydbutton.UrlButton.GetDisplayText(), "url": hydbutton.UrlButton.GetURL()})
 		case *waE2E.HydratedTemplateButton_CallButton:
-			texts = append(texts, fmt.Sprintf("%s: %s", hydbutton.CallButton.GetDisplayText(), hydbutton.CallButton.GetPhoneNumber()))
+			buttons = append(buttons, map[string]interface{}{"type": "Call",
+				"text": hydbutton.CallButton.GetDisplayText(), "phone": hydbutton.CallButton.GetPhoneNumber()})
 		}
 	}
 
-	// footer
-	footer := hydtpl.GetHydratedFooterText()
-	if footer != "" {
-		texts = append(texts, footer)
-	}
-
 	// text
-	text := strings.Join(texts, "\n")
+	text := hydtpl.GetHydratedContentText()
+
+	// content
+	contentJson := ContentJson("Template", map[string]interface{}{"title": title, "footer": hydtpl.GetHydratedFooterText(), "buttons": buttons})
 
 	// context
 	quotedId := ""
@@ -2060,25 +2398,25 @@ func (handler *WmEventHandler) HandleTemplateMessage(messageInfo types.MessageIn
 	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
 	handler.ProcessMessageInfo(messageInfo)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: template", chatId))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewMessagesNotify %s: template", chatId))
+	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, contentJson, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
+		ExpiresAt(messageInfo, msg), BoolToInt(isRead), BoolToInt(isEdited))
 }
 
 func (handler *WmEventHandler) HandleReactionMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("ReactionMessage")
+	LOG_TRACE(handler.connId, "ReactionMessage")
 
 	connId := handler.connId
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 
 	// get reaction part
 	reaction := msg.GetReactionMessage()
 	if reaction == nil {
-		LOG_WARNING("get reaction message failed")
+		LOG_WARNING(connId, "get reaction message failed")
 		return
 	}
 
@@ -2097,12 +2435,12 @@ func (handler *WmEventHandler) HandleReactionMessage(messageInfo types.MessageIn
 }
 
 func (handler *WmEventHandler) HandleProtocolMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
-	LOG_TRACE("ProtocolMessage")
+	LOG_TRACE(handler.connId, "ProtocolMessage")
 
 	// get protocol part
 	protocol := msg.GetProtocolMessage()
 	if protocol == nil {
-		LOG_WARNING("get protocol message failed")
+		LOG_WARNING(handler.connId, "get protocol message failed")
 		return
 	}
 
@@ -2114,23 +2452,69 @@ func (handler *WmEventHandler) HandleProtocolMessage(messageInfo types.MessageIn
 			newMessageInfo.ID = protocol.GetKey().GetID()
 			handler.HandleMessage(newMessageInfo, editedMsg, isSyncRead)
 		} else {
-			LOG_WARNING("get edited message failed")
+			LOG_WARNING(handler.connId, "get edited message failed")
 		}
 	} else if protocol.GetType() == waE2E.ProtocolMessage_REVOKE {
 		// handle message revoke
 		connId := handler.connId
 		var client *whatsmeow.Client = GetClient(connId)
 		if client == nil {
-			LOG_WARNING("client is nil")
+			LOG_WARNING(connId, "client is nil")
 			return
 		}
 		chatId := GetChatId(client, &messageInfo.Chat, &messageInfo.Sender)
 		msgId := protocol.GetKey().GetID()
-		LOG_TRACE(fmt.Sprintf("Call CWmDeleteMessageNotify %s %s", chatId, msgId))
+		LOG_TRACE(connId, fmt.Sprintf("Call CWmDeleteMessageNotify %s %s", chatId, msgId))
 		CWmDeleteMessageNotify(connId, chatId, msgId)
+	} else if protocol.GetType() == waE2E.ProtocolMessage_EPHEMERAL_SETTING {
+		// handle disappearing messages timer change
+		connId := handler.connId
+		var client *whatsmeow.Client = GetClient(connId)
+		if client == nil {
+			LOG_WARNING(connId, "client is nil")
+			return
+		}
+		chatId := GetChatId(client, &messageInfo.Chat, &messageInfo.Sender)
+		timer := protocol.GetEphemeralExpiration()
+		SetExpiration(connId, chatId, timer)
+		LOG_TRACE(connId, fmt.Sprintf("Call CWmNewEphemeralSettingNotify %s %d", chatId, timer))
+		CWmNewEphemeralSettingNotify(connId, chatId, int(timer))
 	} else {
-		LOG_TRACE(fmt.Sprintf("ProtocolMessage %#v ignore", protocol.GetType()))
+		LOG_TRACE(handler.connId, fmt.Sprintf("ProtocolMessage %#v ignore", protocol.GetType()))
+	}
+}
+
+func (handler *WmEventHandler) HandlePollUpdateMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
+	LOG_TRACE(handler.connId, "PollUpdateMessage")
+
+	connId := handler.connId
+	client := GetClient(connId)
+	if client == nil {
+		LOG_WARNING(connId, "client is nil")
+		return
+	}
+
+	// decrypt vote, it only holds hashes of the selected options
+	pollUpdate := msg.GetPollUpdateMessage()
+	vote, decryptErr := client.DecryptPollVote(context.TODO(), &events.Message{Info: messageInfo, Message: msg})
+	if decryptErr != nil {
+		LOG_WARNING(connId, fmt.Sprintf("decrypt poll vote failed %#v", decryptErr))
+		return
+	}
+
+	// general
+	chatId := GetChatId(client, &messageInfo.Chat, &messageInfo.Sender)
+	voterId := GetUserId(client, &messageInfo.Chat, &messageInfo.Sender)
+	pollId := pollUpdate.GetPollCreationMessageKey().GetID()
+
+	resultsJson, ok := SetPollVote(connId, pollId, voterId, vote.GetSelectedOptions())
+	if !ok {
+		LOG_DEBUG(connId, "poll vote for unknown poll " + pollId)
+		return
 	}
+
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewPollUpdateNotify %s %s", chatId, pollId))
+	CWmNewPollUpdateNotify(connId, chatId, pollId, resultsJson)
 }
 
 func (handler *WmEventHandler) HandleUnsupportedMessage(messageInfo types.MessageInfo, msg *waE2E.Message, isSyncRead bool) {
@@ -2175,7 +2559,8 @@ func (handler *WmEventHandler) HandleUnsupportedMessage(messageInfo types.Messag
 		msgType = "Chat"
 
 	case msg.ContactsArrayMessage != nil:
-		msgType = "ContactsArrayMessage"
+		msgType = "ContactsArray"
+		msgNotify = true
 
 	case msg.HighlyStructuredMessage != nil:
 		msgType = "HighlyStructuredMessage"
@@ -2201,6 +2586,7 @@ func (handler *WmEventHandler) HandleUnsupportedMessage(messageInfo types.Messag
 
 	case msg.GroupInviteMessage != nil:
 		msgType = "GroupInviteMessage"
+		msgNotify = true
 
 	case msg.TemplateButtonReplyMessage != nil:
 		msgType = "TemplateButtonReplyMessage"
@@ -2255,6 +2641,7 @@ func (handler *WmEventHandler) HandleUnsupportedMessage(messageInfo types.Messag
 
 	case msg.PollCreationMessage != nil:
 		msgType = "PollCreationMessage"
+		msgNotify = true
 
 	case msg.PollUpdateMessage != nil:
 		msgType = "PollUpdateMessage"
@@ -2282,6 +2669,7 @@ func (handler *WmEventHandler) HandleUnsupportedMessage(messageInfo types.Messag
 
 	case msg.PollCreationMessageV2 != nil:
 		msgType = "PollCreationMessageV2"
+		msgNotify = true
 
 	case msg.ScheduledCallCreationMessage != nil:
 		msgType = "ScheduledCallCreationMessage"
@@ -2291,6 +2679,7 @@ func (handler *WmEventHandler) HandleUnsupportedMessage(messageInfo types.Messag
 
 	case msg.PollCreationMessageV3 != nil:
 		msgType = "PollCreationMessageV3"
+		msgNotify = true
 
 	case msg.ScheduledCallEditMessage != nil:
 		msgType = "ScheduledCallEditMessage"
@@ -2300,21 +2689,69 @@ func (handler *WmEventHandler) HandleUnsupportedMessage(messageInfo types.Messag
 	}
 
 	if !msgNotify {
-		LOG_TRACE(fmt.Sprintf("%s ignore", msgType))
+		LOG_TRACE(handler.connId, fmt.Sprintf("%s ignore", msgType))
 		return
 	} else {
-		LOG_TRACE(fmt.Sprintf("%s notify", msgType))
+		LOG_TRACE(handler.connId, fmt.Sprintf("%s notify", msgType))
 	}
 
 	connId := handler.connId
 	var client *whatsmeow.Client = GetClient(handler.connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
+		LOG_WARNING(connId, "client is nil")
 		return
 	}
 
-	// text
-	text := "[" + msgType + "]"
+	// text and content
+	text := ""
+	contentJson := ContentJson("Unsupported", map[string]interface{}{"kind": msgType})
+	switch {
+	case msg.ContactMessage != nil:
+		contact := msg.GetContactMessage()
+		contentJson = ContentJson("Contact", map[string]interface{}{"display_name": contact.GetDisplayName(), "cards": []string{contact.GetVcard()}})
+
+	case msg.ContactsArrayMessage != nil:
+		contacts := msg.GetContactsArrayMessage()
+		cards := []string{}
+		for _, contact := range contacts.GetContacts() {
+			cards = append(cards, contact.GetVcard())
+		}
+		contentJson = ContentJson("Contact", map[string]interface{}{"display_name": contacts.GetDisplayName(), "cards": cards})
+
+	case msg.GroupInviteMessage != nil:
+		invite := msg.GetGroupInviteMessage()
+		contentJson = ContentJson("GroupInvite", map[string]interface{}{"group_id": invite.GetGroupJID(), "group_name": invite.GetGroupName(),
+			"code": invite.GetInviteCode(), "expiration": invite.GetInviteExpiration()})
+		text = invite.GetCaption()
+
+	case msg.LocationMessage != nil:
+		loc := msg.GetLocationMessage()
+		contentJson = ContentJson("Location", map[string]interface{}{"lat": loc.GetDegreesLatitude(), "lon": loc.GetDegreesLongitude(),
+			"name": loc.GetName(), "address": loc.GetAddress(), "is_live": loc.GetIsLive()})
+		text = loc.GetComment()
+
+	case msg.LiveLocationMessage != nil:
+		loc := msg.GetLiveLocationMessage()
+		contentJson = ContentJson("Location", map[string]interface{}{"lat": loc.GetDegreesLatitude(), "lon": loc.GetDegreesLongitude(),
+			"is_live": true})
+		text = loc.GetCaption()
+
+	case (msg.PollCreationMessage != nil) || (msg.PollCreationMessageV2 != nil) || (msg.PollCreationMessageV3 != nil):
+		poll := msg.GetPollCreationMessage()
+		if poll == nil {
+			poll = msg.GetPollCreationMessageV2()
+		}
+		if poll == nil {
+			poll = msg.GetPollCreationMessageV3()
+		}
+		options := []string{}
+		for _, option := range poll.GetOptions() {
+			options = append(options, option.GetOptionName())
+		}
+		AddPoll(connId, messageInfo.ID, options, messageInfo.Timestamp)
+		contentJson = ContentJson("Poll", map[string]interface{}{"name": poll.GetName(), "options": options,
+			"selectable_count": poll.GetSelectableOptionsCount()})
+	}
 
 	// context
 	quotedId := ""
@@ -2337,9 +2774,30 @@ func (handler *WmEventHandler) HandleUnsupportedMessage(messageInfo types.Messag
 	ResetTypingStatus(connId, chatId, senderId, fromMe, isSyncRead)
 	handler.ProcessMessageInfo(messageInfo)
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewMessagesNotify %s: %s", chatId, text))
-	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
-		BoolToInt(isRead), BoolToInt(isEdited))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewMessagesNotify %s: %s", chatId, RedactText(text)))
+	CWmNewMessagesNotify(connId, chatId, msgId, senderId, text, contentJson, BoolToInt(fromMe), quotedId, fileId, filePath, fileStatus, timeSent,
+		ExpiresAt(messageInfo, msg), BoolToInt(isRead), BoolToInt(isEdited))
+}
+
+// unix time a disappearing message expires, 0 if it doesn't
+func ExpiresAt(messageInfo types.MessageInfo, msg *waE2E.Message) int {
+	// the timer is in the ContextInfo of whichever message type this is
+	var expiration uint32
+	msg.ProtoReflect().Range(func(fd protoreflect.FieldDescriptor, v protoreflect.Value) bool {
+		if fd.Kind() != protoreflect.MessageKind {
+			return true
+		}
+		if part, ok := v.Message().Interface().(interface{ GetContextInfo() *waE2E.ContextInfo }); ok {
+			expiration = part.GetContextInfo().GetExpiration()
+		}
+		return expiration == 0
+	})
+
+	if expiration == 0 {
+		return 0
+	}
+
+	return int(messageInfo.Timestamp.Unix()) + int(expiration)
 }
 
 func ResetTypingStatus(connId int, chatId string, userId string, fromMe bool, isSyncRead bool) {
@@ -2349,25 +2807,24 @@ func ResetTypingStatus(connId int, chatId string, userId string, fromMe bool, is
 		return
 	}
 
-	LOG_TRACE("reset typing status " + strconv.Itoa(connId) + ", " + chatId + ", " + userId)
+	LOG_TRACE(connId, "reset typing status " + strconv.Itoa(connId) + ", " + chatId + ", " + userId)
 
 	// update
 	isTyping := false
 
-	LOG_TRACE(fmt.Sprintf("Call CWmNewTypingNotify %t %s %s", isTyping, chatId, userId))
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewTypingNotify %t %s %s", isTyping, chatId, userId))
 	CWmNewTypingNotify(connId, chatId, userId, BoolToInt(isTyping))
 }
 
-func WmInit(path string, proxy string, sendType int) int {
+func WmInit(path string, proxy string, sendType int) (int, error) {
 
-	LOG_DEBUG("init " + filepath.Base(path))
+	LOG_DEBUG(-1, "init " + filepath.Base(path))
 
 	// create tmp dir
 	var tmpPath string = path + "/tmp"
 	tmpErr := os.MkdirAll(tmpPath, os.ModePerm)
 	if tmpErr != nil {
-		LOG_WARNING(fmt.Sprintf("mkdir error %#v", tmpErr))
-		return -1
+		return -1, NewWmError(-1, ErrFileRead, fmt.Sprintf("mkdir error %#v", tmpErr))
 	}
 
 	var ncLogger logger.Loggable = &ncSignalLogger{}
@@ -2379,14 +2836,12 @@ func WmInit(path string, proxy string, sendType int) int {
 	sqlAddress := fmt.Sprintf("file:%s?_foreign_keys=on", sessionPath)
 	container, sqlErr := sqlstore.New(ctx, "sqlite3", sqlAddress, dbLog)
 	if sqlErr != nil {
-		LOG_WARNING(fmt.Sprintf("sqlite error %#v", sqlErr))
-		return -1
+		return -1, NewWmError(-1, ErrUnknown, fmt.Sprintf("sqlite error %#v", sqlErr))
 	}
 
 	deviceStore, devErr := container.GetFirstDevice(ctx)
 	if devErr != nil {
-		LOG_WARNING(fmt.Sprintf("dev store error %#v", devErr))
-		return -1
+		return -1, NewWmError(-1, ErrUnknown, fmt.Sprintf("dev store error %#v", devErr))
 	}
 
 	store.DeviceProps.RequireFullSync = proto.Bool(true)
@@ -2403,8 +2858,7 @@ func WmInit(path string, proxy string, sendType int) int {
 	clientLog := NcLogger()
 	client := whatsmeow.NewClient(deviceStore, clientLog)
 	if client == nil {
-		LOG_WARNING("client error")
-		return -1
+		return -1, NewWmError(-1, ErrClientMissing, "client error")
 	}
 
 	// set proxy details
@@ -2414,208 +2868,330 @@ func WmInit(path string, proxy string, sendType int) int {
 
 	// store connection and get id
 	var connId int = AddConn(client, path, sendType)
+	dbLog.connId = connId
+	clientLog.connId = connId
 
-	LOG_DEBUG("connId " + strconv.Itoa(connId))
+	LOG_DEBUG(connId, "connId " + strconv.Itoa(connId))
 
-	return connId
+	return connId, nil
 }
 
-func WmLogin(connId int) int {
+func WmLogin(connId int, mode int, phone string) error {
 
-	LOG_DEBUG("login " + strconv.Itoa(connId) + " whatsmeow " + strconv.Itoa(whatsmeowDate))
+	LOG_DEBUG(connId, "login " + strconv.Itoa(connId) + " whatsmeow " + strconv.Itoa(whatsmeowDate))
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	// get path and conn
 	var path string = GetPath(connId)
 	var cli *whatsmeow.Client = GetClient(connId)
 	if cli == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	// only one login at a time per connection
+	ctx, cancel := context.WithCancel(context.Background())
+	if !SetLoginCancel(connId, cancel) {
+		cancel()
+		return NewWmError(connId, ErrInvalidArgument, "login already in progress")
 	}
 
-	// authenticate if needed, otherwise just connect
 	SetState(connId, Connecting)
+	CWmLoginProgressNotify(connId, LoginConnecting)
+
+	// connect and wait for result in the background, outcome is reported through progress notify
+	go func() {
+		progress := DoLogin(ctx, connId, path, cli, mode, phone)
+		RemoveLoginCancel(connId)
+		cancel()
+
+		LOG_DEBUG(connId, "login done " + strconv.Itoa(progress))
+		CWmLoginProgressNotify(connId, progress)
+	}()
+
+	return nil
+}
+
+func DoLogin(ctx context.Context, connId int, path string, cli *whatsmeow.Client, mode int, phone string) int {
+
+	// authenticate if needed, otherwise just connect
 	var timeoutMs int = 10000 // 10 sec timeout by default (regular connect)
 
-	ch, err := cli.GetQRChannel(context.Background())
+	ch, err := cli.GetQRChannel(ctx)
 	if err != nil {
 		if errors.Is(err, whatsmeow.ErrQRStoreContainsID) {
 			// This error means that we're already logged in, so ignore it.
 		} else {
-			LOG_WARNING(fmt.Sprintf("failed to get qr channel %#v", err))
+			LOG_WARNING(connId, fmt.Sprintf("failed to get qr channel %#v", err))
 			SetState(connId, Disconnected)
 		}
 	} else {
 		timeoutMs = 60000 // 60 sec timeout during setup / qr code scan
 		go func() {
-			usePairingCode := GetConfigOrEnvFlag("USE_PAIRING_CODE")
-
-			LOG_TRACE("acquire console")
-			CWmSetProtocolUiControl(connId, 1)
+			var usePairingCode bool
+			switch mode {
+			case LoginModeQr:
+				usePairingCode = false
+			case LoginModePairingCode:
+				usePairingCode = true
+			default:
+				usePairingCode = GetConfigOrEnvFlag(connId, "USE_PAIRING_CODE")
+			}
 
+			var instructions string
 			if usePairingCode {
-				fmt.Printf("\n")
-				fmt.Printf("Open the WhatsApp notification \"Enter code to link new device\" on your phone,\n")
-				fmt.Printf("click \"Confirm\" and enter below pairing code on your phone, or press CTRL-C\n")
-				fmt.Printf("to abort.\n")
-				fmt.Printf("\n")
+				instructions = "Open the WhatsApp notification \"Enter code to link new device\" on your phone,\n" +
+					"click \"Confirm\" and enter below pairing code on your phone, or press CTRL-C\n" +
+					"to abort.\n"
+			} else {
+				instructions = "Open WhatsApp on your phone, click the menu bar and select \"Linked devices\".\n" +
+					"Click on \"Link a device\", unlock the phone and aim its camera at the\n" +
+					"Qr code displayed on the computer screen.\n" +
+					"\n" +
+					"Scan the Qr code to authenticate, or press CTRL-C to abort.\n"
+			}
+
+			// in headless mode the app shows the instructions
+			isHeadless := IsHeadless()
+			if isHeadless {
+				CWmLoginInstructions(connId, instructions)
 			} else {
-				fmt.Printf("\n")
-				fmt.Printf("Open WhatsApp on your phone, click the menu bar and select \"Linked devices\".\n")
-				fmt.Printf("Click on \"Link a device\", unlock the phone and aim its camera at the\n")
-				fmt.Printf("Qr code displayed on the computer screen.\n")
-				fmt.Printf("\n")
-				fmt.Printf("Scan the Qr code to authenticate, or press CTRL-C to abort.\n")
+				LOG_TRACE(connId, "acquire console")
+				CWmSetProtocolUiControl(connId, 1)
+
+				fmt.Printf("\n%s\n", instructions)
 			}
 
+			isFirstCode := true
 			for evt := range ch {
 				if evt.Event == whatsmeow.QRChannelEventCode {
 					if usePairingCode {
-						ctx := context.TODO()
-						phoneNumber := GetPhoneNumberFromPath(path)
+						// explicit phone number, or legacy profile dir name
+						phoneNumber := phone
+						if len(phoneNumber) == 0 {
+							phoneNumber = GetPhoneNumberFromPath(path)
+						}
 						showPushNotification := true
 						pairCode, pairErr := cli.PairPhone(ctx, phoneNumber, showPushNotification, whatsmeow.PairClientFirefox, GetClientDisplayName())
 						if pairErr != nil {
-							LOG_WARNING(fmt.Sprintf("pair phone error %#v", pairErr))
+							LOG_WARNING(connId, fmt.Sprintf("pair phone error %#v", pairErr))
+							CWmLoginProgressNotify(connId, LoginPairError)
 							SetState(connId, Disconnected)
 						} else {
-							CWmExtLoginPairingCode(pairCode)
+							CWmExtLoginPairingCode(connId, pairCode)
+							CWmLoginProgressNotify(connId, LoginQrAvailable)
 						}
+					} else if isFirstCode {
+						CWmExtQrCode(connId, evt.Code)
+						CWmLoginProgressNotify(connId, LoginQrAvailable)
 					} else {
-						CWmExtQrCode(evt.Code)
+						// previous code expired, a new one replaces it
+						CWmExtQrCodeRefresh(connId, evt.Code)
 					}
+					isFirstCode = false
 				} else if evt == whatsmeow.QRChannelSuccess {
-					LOG_DEBUG("qr channel event success")
+					LOG_DEBUG(connId, "qr channel event success")
+				} else if evt == whatsmeow.QRChannelTimeout {
+					LOG_WARNING(connId, fmt.Sprintf("qr channel result %#v", evt.Event))
+					if !usePairingCode {
+						CWmExtQrCodeExpired(connId)
+					}
+					SetState(connId, Disconnected)
 				} else if evt == whatsmeow.QRChannelClientOutdated {
-					LOG_WARNING(fmt.Sprintf("qr channel result %#v", evt.Event))
+					LOG_WARNING(connId, fmt.Sprintf("qr channel result %#v", evt.Event))
 					SetState(connId, Outdated)
 				} else {
-					LOG_WARNING(fmt.Sprintf("qr channel result %#v", evt.Event))
+					LOG_WARNING(connId, fmt.Sprintf("qr channel result %#v", evt.Event))
 					SetState(connId, Disconnected)
 				}
 			}
 
-			LOG_TRACE("release console")
-			CWmSetProtocolUiControl(connId, 0)
+			if !isHeadless {
+				LOG_TRACE(connId, "release console")
+				CWmSetProtocolUiControl(connId, 0)
+			}
 		}()
 	}
 
-	eventHandler := GetHandler(connId)
-	cli.AddEventHandler(eventHandler.HandleEvent)
 	err = cli.Connect()
 	if err != nil {
-		LOG_WARNING(fmt.Sprintf("failed to connect %#v", err))
+		LOG_WARNING(connId, fmt.Sprintf("failed to connect %#v", err))
+		SetState(connId, Disconnected)
 		CWmClearStatus(connId, FlagConnecting)
-		return -1
+		if ErrorCodeFor(err, ErrRequestFailed) == ErrTimeout {
+			return LoginTimeout
+		}
+		return LoginFailed
+	}
+
+	LOG_DEBUG(connId, "connect ok")
+
+	// wait for state change (up to timeout), or cancellation
+	LOG_DEBUG(connId, "wait start")
+	timer := time.NewTimer(time.Duration(timeoutMs) * time.Millisecond)
+	defer timer.Stop()
+	signal := GetStateSignal(connId)
+	isCancelled := false
+	isTimedOut := false
+	for !isCancelled && !isTimedOut && (GetState(connId) == Connecting) {
+		select {
+		case <-ctx.Done():
+			isCancelled = true
+		case <-timer.C:
+			isTimedOut = true
+		case <-signal:
+		}
 	}
+	LOG_DEBUG(connId, "wait done")
 
-	LOG_DEBUG("connect ok")
-
-	// wait for result (up to timeout, 100 ms at a time)
-	LOG_DEBUG("wait start")
-	waitedMs := 0
-	for (waitedMs < timeoutMs) && (GetState(connId) == Connecting) {
-		time.Sleep(100 * time.Millisecond)
-		waitedMs += 100
+	if isCancelled {
+		LOG_DEBUG(connId, "login cancelled")
+		cli.Disconnect()
+		SetState(connId, Disconnected)
+		CWmClearStatus(connId, FlagConnecting)
+		return LoginCancelled
 	}
-	LOG_DEBUG("wait done")
 
-	// delete temporary image file
-	_ = os.Remove(path + "/tmp/qr.png")
+	// log error on stdout, or let the app show it in headless mode
+	state := GetState(connId)
+	if state != Connected {
 
-	// log error on stdout
-	if GetState(connId) != Connected {
-		LOG_WARNING(fmt.Sprintf("state not connected %#v", GetState(connId)))
-
-		LOG_TRACE("acquire console")
-		CWmSetProtocolUiControl(connId, 1)
-
-		fmt.Printf("\n")
-		if GetState(connId) == Outdated {
-			fmt.Printf("ERROR:\n")
-			fmt.Printf("WhatsApp client is outdated, please update nchat to a newer version. See:\n")
-			fmt.Printf("https://github.com/d99kris/nchat/blob/master/doc/WMOUTDATED.md\n")
+		var reason string
+		if state == Outdated {
+			reason = "WhatsApp client is outdated, please update nchat to a newer version. See:\n" +
+				"https://github.com/d99kris/nchat/blob/master/doc/WMOUTDATED.md\n"
 		} else {
-			fmt.Printf("ERROR:\n")
-			fmt.Printf("Please see the log for details.\n")
+			reason = "Please see the log for details.\n"
 		}
-		fmt.Printf("\n")
 
-		LOG_TRACE("release console")
-		CWmSetProtocolUiControl(connId, 0)
+		if IsHeadless() {
+			CWmFatalLoginError(connId, reason)
+		} else {
+			LOG_TRACE(connId, "acquire console")
+			CWmSetProtocolUiControl(connId, 1)
+
+			fmt.Printf("\nERROR:\n%s\n", reason)
+
+			LOG_TRACE(connId, "release console")
+			CWmSetProtocolUiControl(connId, 0)
+		}
 
 		CWmClearStatus(connId, FlagConnecting)
-		return -1
+		switch state {
+		case Outdated:
+			LOG_WARNING(connId, "client outdated")
+			return LoginOutdated
+		case Connecting:
+			LOG_WARNING(connId, "timed out waiting for connection")
+			cli.Disconnect()
+			SetState(connId, Disconnected)
+			return LoginTimeout
+		default:
+			LOG_WARNING(connId, fmt.Sprintf("state not connected %#v", state))
+			return LoginFailed
+		}
 	}
 
-	LOG_DEBUG("login ok")
-	return 0
+	LOG_DEBUG(connId, "login ok")
+	return LoginConnected
+}
+
+func WmSetLogRedaction(level int) {
+	LOG_DEBUG(-1, "set log redaction " + strconv.Itoa(level))
+	SetRedaction(level)
+}
+
+func WmSetHeadless(isHeadless int) {
+	LOG_DEBUG(-1, "set headless " + strconv.Itoa(isHeadless))
+	SetHeadless(IntToBool(isHeadless))
 }
 
-func WmLogout(connId int) int {
+func WmLoginCancel(connId int) error {
 
-	LOG_DEBUG("logout " + strconv.Itoa(connId))
+	LOG_DEBUG(connId, "login cancel " + strconv.Itoa(connId))
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	// no-op if no login is in progress
+	CancelLogin(connId)
+
+	return nil
+}
+
+func WmLogout(connId int) error {
+
+	LOG_DEBUG(connId, "logout " + strconv.Itoa(connId))
+
+	// sanity check arg
+	if connId == -1 {
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	// get client
 	var client *whatsmeow.Client = GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
+	// stop login in progress, if any
+	CancelLogin(connId)
+
 	// disconnect
 	client.Disconnect()
 
 	// set state
 	SetState(connId, Disconnected)
 
-	LOG_DEBUG("logout ok")
+	LOG_DEBUG(connId, "logout ok")
 
-	return 0
+	return nil
 }
 
-func WmCleanup(connId int) int {
+func WmCleanup(connId int) error {
 
-	LOG_DEBUG("cleanup " + strconv.Itoa(connId))
+	LOG_DEBUG(connId, "cleanup " + strconv.Itoa(connId))
+	CancelLogin(connId)
 	RemoveConn(connId)
-	return 0
+	return nil
 }
 
 func WmGetVersion() int {
 	return whatsmeowDate
 }
 
-func WmGetMessages(connId int, chatId string, limit int, fromMsgId string, owner int) int {
+func WmGetMessages(connId int, chatId string, limit int, fromMsgId string, owner int) error {
 	// not supported in multi-device
-	return -1
+	return NewWmError(connId, ErrNotSupported, "get messages not supported in multi-device")
 }
 
-func WmSendMessage(connId int, chatId string, text string, quotedId string, quotedText string, quotedSender string, filePath string, fileType string, editMsgId string, editMsgSent int, mentionsJson string) int {
+// per-message attachment options, overriding the connection send type
+type FileInfo struct {
+	Sticker  bool  `json:"sticker"`
+	Animated bool  `json:"animated"`
+	Ptt      bool  `json:"ptt"`
+	Seconds  int   `json:"seconds"`
+	Waveform []int `json:"waveform"`
+}
+
+func WmSendMessage(connId int, chatId string, text string, quotedId string, quotedText string, quotedSender string, filePath string, fileType string, fileInfoJson string, editMsgId string, editMsgSent int, mentionsJson string) error {
 
-	LOG_TRACE("send message " + strconv.Itoa(connId) + ", " + chatId + ", " + text + ", " + quotedId + ", " + filePath + ", " + editMsgId)
+	LOG_TRACE(connId, "send message " + strconv.Itoa(connId) + ", " + chatId + ", " + RedactText(text) + ", " + quotedId + ", " + filePath + ", " + editMsgId)
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	// get conn
 	var client *whatsmeow.Client = GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
 	// local vars
@@ -2626,8 +3202,7 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 	// recipient
 	chatJid, jidErr := types.ParseJID(chatId)
 	if jidErr != nil {
-		LOG_WARNING(fmt.Sprintf("jid err %#v", jidErr))
-		return -1
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
 	}
 
 	isSend := false
@@ -2642,7 +3217,7 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 		quotedSender = strings.Replace(quotedSender, "@c.us", "@s.whatsapp.net", 1)
 
-		LOG_TRACE("send quoted " + quotedId + ", " + quotedText + ", " + quotedSender)
+		LOG_TRACE(connId, "send quoted " + quotedId + ", " + RedactText(quotedText) + ", " + quotedSender)
 		contextInfo = waE2E.ContextInfo{
 			QuotedMessage: &quotedMessage,
 			StanzaID:      &quotedId,
@@ -2652,7 +3227,7 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 	expiration := GetExpiration(connId, chatId)
 	if expiration != 0 {
-		LOG_TRACE("send expiration " + strconv.FormatUint(uint64(expiration), 10))
+		LOG_TRACE(connId, "send expiration " + strconv.FormatUint(uint64(expiration), 10))
 		contextInfo.Expiration = &expiration
 	}
 
@@ -2665,7 +3240,7 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 				// Replace @Name or @[Name] with @<user-local-part> in text
 				userJid, parseErr := types.ParseJID(userId)
 				if parseErr != nil {
-					LOG_WARNING(fmt.Sprintf("mention jid parse err %#v", parseErr))
+					LOG_WARNING(connId, fmt.Sprintf("mention jid parse err %#v", parseErr))
 					continue
 				}
 				userLocal := userJid.User
@@ -2699,6 +3274,13 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 	} else {
 
 		var sendType int = GetSendType(connId)
+		var fileInfo FileInfo
+		if len(fileInfoJson) > 0 {
+			if jsonErr := json.Unmarshal([]byte(fileInfoJson), &fileInfo); jsonErr != nil {
+				return NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("file info json err %#v", jsonErr))
+			}
+		}
+
 		mimeParts := strings.Split(fileType, "/")
 		mimeType := mimeParts[0] // image, text, application, video, etc.
 		mimeSubType := ""
@@ -2711,19 +3293,17 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 		hasText := (len(text) > 0)
 		isSendAsSpecial := (sendType == AttachmentSendAsSticker) && !hasText && !hasQuote
 
-		if isSendAsSpecial && (mimeSubType == "webp") {
+		if fileInfo.Sticker || (isSendAsSpecial && (mimeSubType == "webp")) {
 
-			LOG_TRACE("send sticker " + fileType)
+			LOG_TRACE(connId, "send sticker " + fileType)
 			data, err := os.ReadFile(filePath)
 			if err != nil {
-				LOG_WARNING(fmt.Sprintf("read file %s err %#v", filePath, err))
-				return -1
+				return NewWmError(connId, ErrFileRead, fmt.Sprintf("read file %s err %#v", filePath, err))
 			}
 
 			uploaded, upErr := client.Upload(context.Background(), data, whatsmeow.MediaImage)
 			if upErr != nil {
-				LOG_WARNING(fmt.Sprintf("upload error %#v", upErr))
-				return -1
+				return NewWmError(connId, ErrorCodeFor(upErr, ErrUploadFailed), fmt.Sprintf("upload error %#v", upErr))
 			}
 
 			stickerMessage := waE2E.StickerMessage{
@@ -2734,6 +3314,7 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 				FileEncSHA256: uploaded.FileEncSHA256,
 				FileSHA256:    uploaded.FileSHA256,
 				FileLength:    proto.Uint64(uint64(len(data))),
+				IsAnimated:    proto.Bool(fileInfo.Animated),
 				ContextInfo:   &contextInfo,
 			}
 
@@ -2742,17 +3323,15 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 		} else if isSendAsSpecial && (mimeSubType == "mp4" || mimeSubType == "x-m4v") {
 
-			LOG_TRACE("send gif " + fileType)
+			LOG_TRACE(connId, "send gif " + fileType)
 			data, err := os.ReadFile(filePath)
 			if err != nil {
-				LOG_WARNING(fmt.Sprintf("read file %s err %#v", filePath, err))
-				return -1
+				return NewWmError(connId, ErrFileRead, fmt.Sprintf("read file %s err %#v", filePath, err))
 			}
 
 			uploaded, upErr := client.Upload(context.Background(), data, whatsmeow.MediaVideo)
 			if upErr != nil {
-				LOG_WARNING(fmt.Sprintf("upload error %#v", upErr))
-				return -1
+				return NewWmError(connId, ErrorCodeFor(upErr, ErrUploadFailed), fmt.Sprintf("upload error %#v", upErr))
 			}
 
 			videoMessage := waE2E.VideoMessage{
@@ -2770,19 +3349,17 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 			message.VideoMessage = &videoMessage
 			isSend = true
 
-		} else if (sendType >= AttachmentSendAsType) && (mimeType == "audio") {
+		} else if ((sendType >= AttachmentSendAsType) || fileInfo.Ptt) && (mimeType == "audio") {
 
-			LOG_TRACE("send audio " + fileType)
+			LOG_TRACE(connId, "send audio " + fileType)
 			data, err := os.ReadFile(filePath)
 			if err != nil {
-				LOG_WARNING(fmt.Sprintf("read file %s err %#v", filePath, err))
-				return -1
+				return NewWmError(connId, ErrFileRead, fmt.Sprintf("read file %s err %#v", filePath, err))
 			}
 
 			uploaded, upErr := client.Upload(context.Background(), data, whatsmeow.MediaAudio)
 			if upErr != nil {
-				LOG_WARNING(fmt.Sprintf("upload error %#v", upErr))
-				return -1
+				return NewWmError(connId, ErrorCodeFor(upErr, ErrUploadFailed), fmt.Sprintf("upload error %#v", upErr))
 			}
 
 			audioMime := fileType
@@ -2801,6 +3378,17 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 				ContextInfo:   &contextInfo,
 			}
 
+			// voice note, shown as a playable bubble with waveform
+			if fileInfo.Ptt {
+				waveform := make([]byte, len(fileInfo.Waveform))
+				for i, level := range fileInfo.Waveform {
+					waveform[i] = byte(level)
+				}
+				audioMessage.PTT = proto.Bool(true)
+				audioMessage.Seconds = proto.Uint32(uint32(fileInfo.Seconds))
+				audioMessage.Waveform = waveform
+			}
+
 			message.AudioMessage = &audioMessage
 			isSend = true
 
@@ -2810,7 +3398,7 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 			if len(editMsgId) > 0 {
 
-				LOG_TRACE("edit video caption " + fileType)
+				LOG_TRACE(connId, "edit video caption " + fileType)
 				videoMessage = waE2E.VideoMessage{
 					Caption: proto.String(text),
 				}
@@ -2818,17 +3406,15 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 			} else {
 
-				LOG_TRACE("send video " + fileType)
+				LOG_TRACE(connId, "send video " + fileType)
 				data, err := os.ReadFile(filePath)
 				if err != nil {
-					LOG_WARNING(fmt.Sprintf("read file %s err %#v", filePath, err))
-					return -1
+					return NewWmError(connId, ErrFileRead, fmt.Sprintf("read file %s err %#v", filePath, err))
 				}
 
 				uploaded, upErr := client.Upload(context.Background(), data, whatsmeow.MediaVideo)
 				if upErr != nil {
-					LOG_WARNING(fmt.Sprintf("upload error %#v", upErr))
-					return -1
+					return NewWmError(connId, ErrorCodeFor(upErr, ErrUploadFailed), fmt.Sprintf("upload error %#v", upErr))
 				}
 
 				videoMessage = waE2E.VideoMessage{
@@ -2853,7 +3439,7 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 			if len(editMsgId) > 0 {
 
-				LOG_TRACE("edit image caption " + fileType)
+				LOG_TRACE(connId, "edit image caption " + fileType)
 				imageMessage = waE2E.ImageMessage{
 					Caption: proto.String(text),
 				}
@@ -2861,17 +3447,15 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 			} else {
 
-				LOG_TRACE("send image " + fileType)
+				LOG_TRACE(connId, "send image " + fileType)
 				data, err := os.ReadFile(filePath)
 				if err != nil {
-					LOG_WARNING(fmt.Sprintf("read file %s err %#v", filePath, err))
-					return -1
+					return NewWmError(connId, ErrFileRead, fmt.Sprintf("read file %s err %#v", filePath, err))
 				}
 
 				uploaded, upErr := client.Upload(context.Background(), data, whatsmeow.MediaImage)
 				if upErr != nil {
-					LOG_WARNING(fmt.Sprintf("upload error %#v", upErr))
-					return -1
+					return NewWmError(connId, ErrorCodeFor(upErr, ErrUploadFailed), fmt.Sprintf("upload error %#v", upErr))
 				}
 
 				imageMessage = waE2E.ImageMessage{
@@ -2896,7 +3480,7 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 			if len(editMsgId) > 0 {
 
-				LOG_TRACE("edit document caption " + fileType)
+				LOG_TRACE(connId, "edit document caption " + fileType)
 				documentMessage = waE2E.DocumentMessage{
 					Caption: proto.String(text),
 				}
@@ -2904,17 +3488,15 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 			} else {
 
-				LOG_TRACE("send document " + fileType)
+				LOG_TRACE(connId, "send document " + fileType)
 				data, err := os.ReadFile(filePath)
 				if err != nil {
-					LOG_WARNING(fmt.Sprintf("read file %s err %#v", filePath, err))
-					return -1
+					return NewWmError(connId, ErrFileRead, fmt.Sprintf("read file %s err %#v", filePath, err))
 				}
 
 				uploaded, upErr := client.Upload(context.Background(), data, whatsmeow.MediaDocument)
 				if upErr != nil {
-					LOG_WARNING(fmt.Sprintf("upload error %#v", upErr))
-					return -1
+					return NewWmError(connId, ErrorCodeFor(upErr, ErrUploadFailed), fmt.Sprintf("upload error %#v", upErr))
 				}
 
 				fileName := filepath.Base(filePath)
@@ -2954,10 +3536,9 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 
 	// log any error
 	if sendErr != nil {
-		LOG_WARNING(fmt.Sprintf("send message error %#v", sendErr))
-		return -1
+		return NewWmError(connId, ErrorCodeFor(sendErr, ErrSendFailed), fmt.Sprintf("send message error %#v", sendErr))
 	} else {
-		LOG_TRACE("send message ok")
+		LOG_TRACE(connId, "send message ok")
 
 		// messageInfo
 		var messageInfo types.MessageInfo
@@ -2983,212 +3564,1250 @@ func WmSendMessage(connId int, chatId string, text string, quotedId string, quot
 		handler.HandleMessage(messageInfo, &message, isSyncRead)
 	}
 
-	return 0
+	return nil
+}
+
+func CheckCoordinates(connId int, lat float64, lon float64) error {
+	if (lat < -90) || (lat > 90) || (lon < -180) || (lon > 180) || math.IsNaN(lat) || math.IsNaN(lon) {
+		return NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("invalid coordinates %f, %f", lat, lon))
+	}
+
+	return nil
+}
+
+// notify sent own message, like received ones
+func NotifySentMessage(connId int, client *whatsmeow.Client, chatJid types.JID, msgId string, timestamp time.Time, isEdited bool, message *waE2E.Message) {
+	var messageInfo types.MessageInfo
+	messageInfo.Chat = chatJid
+	messageInfo.IsFromMe = true
+	if client.Store.ID != nil {
+		messageInfo.Sender = *client.Store.ID
+	}
+	if isEdited {
+		messageInfo.Edit = "1"
+	}
+	messageInfo.ID = msgId
+	messageInfo.Timestamp = timestamp
+
+	isSyncRead := false
+	handler := GetHandler(connId)
+	handler.HandleMessage(messageInfo, message, isSyncRead)
 }
 
-func WmGetGroupMembers(connId int, chatId string) int {
+func WmSendLocation(connId int, chatId string, lat float64, lon float64, name string, address string) error {
 
-	LOG_TRACE("get group members " + strconv.Itoa(connId) + ", " + chatId)
+	LOG_TRACE(connId, "send location " + strconv.Itoa(connId) + ", " + chatId)
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	if err := CheckCoordinates(connId, lat, lon); err != nil {
+		return err
 	}
 
 	// get client
-	var client *whatsmeow.Client = GetClient(connId)
+	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
-	// parse chat JID
 	chatJid, jidErr := types.ParseJID(chatId)
 	if jidErr != nil {
-		LOG_WARNING(fmt.Sprintf("jid err %#v", jidErr))
-		return -1
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
 	}
 
-	if chatJid.Server != types.GroupServer {
-		LOG_WARNING("not a group chat")
-		return -1
+	contextInfo := waE2E.ContextInfo{}
+	expiration := GetExpiration(connId, chatId)
+	if expiration != 0 {
+		contextInfo.Expiration = &expiration
 	}
 
-	// get group info
-	ctx := context.TODO()
-	groupInfo, groupErr := client.GetGroupInfo(ctx, chatJid)
-	if groupErr != nil {
-		LOG_WARNING(fmt.Sprintf("get group info failed %#v", groupErr))
-		return -1
+	message := waE2E.Message{
+		LocationMessage: &waE2E.LocationMessage{
+			DegreesLatitude:  proto.Float64(lat),
+			DegreesLongitude: proto.Float64(lon),
+			Name:             proto.String(name),
+			Address:          proto.String(address),
+			ContextInfo:      &contextInfo,
+		},
 	}
 
-	type MemberInfo struct {
-		Id   string `json:"id"`
-		Name string `json:"name"`
-	}
-	var members []MemberInfo
-	for _, participant := range groupInfo.Participants {
-		memberId := StrFromJid(participant.JID)
-		memberName := GetContactName(connId, memberId)
-		members = append(members, MemberInfo{Id: memberId, Name: memberName})
-	}
-	membersJsonBytes, jsonErr := json.Marshal(members)
-	if jsonErr != nil {
-		LOG_WARNING(fmt.Sprintf("marshal group members err %#v", jsonErr))
-		return -1
+	sendResponse, sendErr := client.SendMessage(context.Background(), chatJid, &message)
+	if sendErr != nil {
+		return NewWmError(connId, ErrorCodeFor(sendErr, ErrSendFailed), fmt.Sprintf("send location error %#v", sendErr))
 	}
-	CWmNewGroupMembersNotify(connId, chatId, string(membersJsonBytes))
 
-	return 0
+	LOG_TRACE(connId, "send location ok")
+	NotifySentMessage(connId, client, chatJid, sendResponse.ID, sendResponse.Timestamp, false, &message)
+
+	return nil
 }
 
-func WmGetContacts(connId int) int {
+// starts sharing live location if liveMsgId is empty, otherwise updates or
+// stops (replaced by a static location) the live location message liveMsgId
+func WmSendLiveLocation(connId int, chatId string, liveMsgId string, lat float64, lon float64, accuracy int, caption string, sequence int, isStop bool) (string, error) {
 
-	LOG_TRACE("get contacts " + strconv.Itoa(connId))
+	LOG_TRACE(connId, "send live location " + strconv.Itoa(connId) + ", " + chatId + ", " + liveMsgId + ", " + strconv.Itoa(sequence))
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return "", NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	if err := CheckCoordinates(connId, lat, lon); err != nil {
+		return "", err
 	}
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return "", NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
-	// sync contacts
-	err := client.FetchAppState(context.TODO(), appstate.WAPatchCriticalUnblockLow, true, false)
-	if err != nil {
-		LOG_WARNING(fmt.Sprintf("fetch contacts app state failed %#v", err))
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return "", NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
 	}
 
-	// get contacts
-	GetContacts(connId)
+	contextInfo := waE2E.ContextInfo{}
+	expiration := GetExpiration(connId, chatId)
+	if expiration != 0 {
+		contextInfo.Expiration = &expiration
+	}
 
-	return 0
+	var message waE2E.Message
+	if isStop {
+		message.LocationMessage = &waE2E.LocationMessage{
+			DegreesLatitude:  proto.Float64(lat),
+			DegreesLongitude: proto.Float64(lon),
+			IsLive:           proto.Bool(false),
+			Comment:          proto.String(caption),
+			ContextInfo:      &contextInfo,
+		}
+	} else {
+		message.LiveLocationMessage = &waE2E.LiveLocationMessage{
+			DegreesLatitude:  proto.Float64(lat),
+			DegreesLongitude: proto.Float64(lon),
+			AccuracyInMeters: proto.Uint32(uint32(accuracy)),
+			Caption:          proto.String(caption),
+			SequenceNumber:   proto.Int64(int64(sequence)),
+			ContextInfo:      &contextInfo,
+		}
+	}
+
+	isStart := (len(liveMsgId) == 0)
+	var sendResponse whatsmeow.SendResponse
+	var sendErr error
+	if isStart {
+		sendResponse, sendErr = client.SendMessage(context.Background(), chatJid, &message)
+	} else {
+		sendResponse, sendErr = client.SendMessage(context.Background(), chatJid, client.BuildEdit(chatJid, liveMsgId, &message))
+	}
+
+	if sendErr != nil {
+		return "", NewWmError(connId, ErrorCodeFor(sendErr, ErrSendFailed), fmt.Sprintf("send live location error %#v", sendErr))
+	}
+
+	LOG_TRACE(connId, "send live location ok")
+	msgId := liveMsgId
+	if isStart {
+		msgId = sendResponse.ID
+	}
+	NotifySentMessage(connId, client, chatJid, msgId, sendResponse.Timestamp, !isStart, &message)
+
+	return msgId, nil
+}
+
+type ContactCard struct {
+	DisplayName string `json:"display_name"`
+	Vcard       string `json:"vcard"`
 }
 
-func WmGetStatus(connId int, userId string) int {
+// sends a single contact, or a contacts array if more than one
+func WmSendContacts(connId int, chatId string, contactsJson string) error {
 
-	LOG_TRACE("get status " + strconv.Itoa(connId) + ", " + userId)
+	LOG_TRACE(connId, "send contacts " + strconv.Itoa(connId) + ", " + chatId)
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	var contacts []ContactCard
+	if jsonErr := json.Unmarshal([]byte(contactsJson), &contacts); jsonErr != nil {
+		return NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("contacts json err %#v", jsonErr))
+	}
+
+	if len(contacts) == 0 {
+		return NewWmError(connId, ErrInvalidArgument, "no contacts")
 	}
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
-	// ignore presence requests before connected
-	if GetState(connId) != Connected {
-		return -1
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
 	}
 
-	// ignore presence requests for groups
-	userJid, _ := types.ParseJID(userId)
-	if userJid.Server == types.GroupServer {
-		return -1
+	contextInfo := waE2E.ContextInfo{}
+	expiration := GetExpiration(connId, chatId)
+	if expiration != 0 {
+		contextInfo.Expiration = &expiration
 	}
 
-	// ignore presence requests for self
-	isSelfUser := IsSelfUser(client, userId)
-	if isSelfUser {
-		return -1
-	}
+	var message waE2E.Message
+	if len(contacts) == 1 {
+		message.ContactMessage = &waE2E.ContactMessage{
+			DisplayName: proto.String(contacts[0].DisplayName),
+			Vcard:       proto.String(contacts[0].Vcard),
+			ContextInfo: &contextInfo,
+		}
+	} else {
+		contactMessages := []*waE2E.ContactMessage{}
+		for _, contact := range contacts {
+			contactMessages = append(contactMessages, &waE2E.ContactMessage{
+				DisplayName: proto.String(contact.DisplayName),
+				Vcard:       proto.String(contact.Vcard),
+			})
+		}
 
-	// subscribe user presence
-	ctx := context.TODO()
-	err := client.SubscribePresence(ctx, userJid)
+		message.ContactsArrayMessage = &waE2E.ContactsArrayMessage{
+			DisplayName: proto.String(fmt.Sprintf("%d contacts", len(contacts))),
+			Contacts:    contactMessages,
+			ContextInfo: &contextInfo,
+		}
+	}
 
-	// log any error
-	if err != nil {
-		LOG_WARNING(fmt.Sprintf("get user status error %#v", err))
-		return -1
-	} else {
-		LOG_TRACE("get user status ok")
+	sendResponse, sendErr := client.SendMessage(context.Background(), chatJid, &message)
+	if sendErr != nil {
+		return NewWmError(connId, ErrorCodeFor(sendErr, ErrSendFailed), fmt.Sprintf("send contacts error %#v", sendErr))
 	}
 
-	return 0
+	LOG_TRACE(connId, "send contacts ok")
+	NotifySentMessage(connId, client, chatJid, sendResponse.ID, sendResponse.Timestamp, false, &message)
+
+	return nil
 }
 
-func WmMarkMessageRead(connId int, chatId string, senderId string, msgId string) int {
+func WmSetDisappearingTimer(connId int, chatId string, seconds int) error {
 
-	LOG_TRACE("mark message read " + strconv.Itoa(connId) + ", " + chatId + ", " + senderId + ", " + msgId)
+	LOG_TRACE(connId, "set disappearing timer " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(seconds))
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	if seconds < 0 {
+		return NewWmError(connId, ErrInvalidArgument, "negative timer")
 	}
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
-	// mark read
-	msgIds := []types.MessageID{
-		msgId,
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
 	}
-	timeRead := time.Now()
-	chatJid, _ := types.ParseJID(chatId)
-	senderJid, _ := types.ParseJID(senderId)
-	ctx := context.TODO()
-	err := client.MarkRead(ctx, msgIds, timeRead, chatJid, senderJid)
-
-	// store time
-	SetTimeRead(connId, chatId, timeRead)
 
-	// log any error
-	if err != nil {
-		LOG_WARNING(fmt.Sprintf("mark message read error %#v", err))
-		return -1
-	} else {
-		LOG_TRACE(fmt.Sprintf("mark message read ok %#v", msgId))
+	timer := time.Duration(seconds) * time.Second
+	setErr := client.SetDisappearingTimer(context.Background(), chatJid, timer, time.Time{})
+	if setErr != nil {
+		return NewWmError(connId, ErrorCodeFor(setErr, ErrRequestFailed), fmt.Sprintf("set disappearing timer error %#v", setErr))
 	}
 
-	return 0
+	LOG_TRACE(connId, "set disappearing timer ok")
+	SetExpiration(connId, chatId, uint32(seconds))
+	CWmNewEphemeralSettingNotify(connId, chatId, seconds)
+
+	return nil
 }
 
-func WmDeleteMessage(connId int, chatId string, senderId string, msgId string) int {
+func WmSetDefaultDisappearingTimer(connId int, seconds int) error {
 
-	LOG_TRACE("delete message " + strconv.Itoa(connId) + ", " + chatId + ", " + msgId)
+	LOG_TRACE(connId, "set default disappearing timer " + strconv.Itoa(connId) + ", " + strconv.Itoa(seconds))
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	if seconds < 0 {
+		return NewWmError(connId, ErrInvalidArgument, "negative timer")
 	}
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
-	chatJid, _ := types.ParseJID(chatId)
-	senderJid, _ := types.ParseJID(senderId)
+	timer := time.Duration(seconds) * time.Second
+	setErr := client.SetDefaultDisappearingTimer(context.Background(), timer)
+	if setErr != nil {
+		return NewWmError(connId, ErrorCodeFor(setErr, ErrRequestFailed), fmt.Sprintf("set default disappearing timer error %#v", setErr))
+	}
 
-	// skip deleting messages sent by others in private chat
+	LOG_TRACE(connId, "set default disappearing timer ok")
+
+	return nil
+}
+
+func WmCreatePoll(connId int, chatId string, name string, optionsJson string, selectableCount int) (string, error) {
+
+	LOG_TRACE(connId, "create poll " + strconv.Itoa(connId) + ", " + chatId + ", \"" + RedactText(name) + "\"")
+
+	// sanity check arg
+	if connId == -1 {
+		return "", NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	var options []string
+	if jsonErr := json.Unmarshal([]byte(optionsJson), &options); jsonErr != nil {
+		return "", NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("options json err %#v", jsonErr))
+	}
+
+	// votes refer to options by hash, so they must be unique
+	uniqueOptions := make(map[string]bool)
+	for _, option := range options {
+		uniqueOptions[option] = true
+	}
+
+	if (len(options) < 2) || (len(uniqueOptions) != len(options)) {
+		return "", NewWmError(connId, ErrInvalidArgument, "poll needs at least two unique options")
+	}
+
+	// get client
+	client := GetClient(connId)
+	if client == nil {
+		return "", NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return "", NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
+
+	message := client.BuildPollCreation(name, options, selectableCount)
+	expiration := GetExpiration(connId, chatId)
+	if expiration != 0 {
+		message.PollCreationMessage.ContextInfo = &waE2E.ContextInfo{Expiration: &expiration}
+	}
+
+	sendResponse, sendErr := client.SendMessage(context.Background(), chatJid, message)
+	if sendErr != nil {
+		return "", NewWmError(connId, ErrorCodeFor(sendErr, ErrSendFailed), fmt.Sprintf("create poll error %#v", sendErr))
+	}
+
+	LOG_TRACE(connId, "create poll ok")
+	NotifySentMessage(connId, client, chatJid, sendResponse.ID, sendResponse.Timestamp, false, message)
+
+	return sendResponse.ID, nil
+}
+
+func WmVotePoll(connId int, chatId string, senderId string, pollId string, optionsJson string) error {
+
+	LOG_TRACE(connId, "vote poll " + strconv.Itoa(connId) + ", " + chatId + ", " + pollId)
+
+	// sanity check arg
+	if connId == -1 {
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	var options []string
+	if jsonErr := json.Unmarshal([]byte(optionsJson), &options); jsonErr != nil {
+		return NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("options json err %#v", jsonErr))
+	}
+
+	// get client
+	client := GetClient(connId)
+	if client == nil {
+		return NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
+
+	senderJid, senderErr := types.ParseJID(senderId)
+	if senderErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", senderErr))
+	}
+
+	// info of the poll message, to find its secret
+	var pollInfo types.MessageInfo
+	pollInfo.Chat = chatJid
+	pollInfo.Sender = senderJid
+	pollInfo.ID = pollId
+	pollInfo.IsGroup = (chatJid.Server == types.GroupServer)
+	pollInfo.IsFromMe = IsSelfUser(client, StrFromJid(senderJid))
+
+	message, buildErr := client.BuildPollVote(context.Background(), &pollInfo, options)
+	if buildErr != nil {
+		return NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("build poll vote error %#v", buildErr))
+	}
+
+	_, sendErr := client.SendMessage(context.Background(), chatJid, message)
+	if sendErr != nil {
+		return NewWmError(connId, ErrorCodeFor(sendErr, ErrSendFailed), fmt.Sprintf("vote poll error %#v", sendErr))
+	}
+
+	LOG_TRACE(connId, "vote poll ok")
+
+	// own votes aren't echoed back, tally locally
+	if client.Store.ID != nil {
+		selfId := GetUserId(client, &chatJid, client.Store.ID)
+		if resultsJson, ok := SetPollVote(connId, pollId, selfId, whatsmeow.HashPollOptions(options)); ok {
+			LOG_TRACE(connId, fmt.Sprintf("Call CWmNewPollUpdateNotify %s %s", chatId, pollId))
+			CWmNewPollUpdateNotify(connId, chatId, pollId, resultsJson)
+		}
+	}
+
+	return nil
+}
+
+// gets the client and parsed JID of a group, for the group functions below
+func GetGroupClient(connId int, chatId string) (*whatsmeow.Client, types.JID, error) {
+	// sanity check arg
+	if connId == -1 {
+		return nil, types.EmptyJID, NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	// get client
+	var client *whatsmeow.Client = GetClient(connId)
+	if client == nil {
+		return nil, types.EmptyJID, NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	// parse chat JID
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return nil, types.EmptyJID, NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
+
+	if chatJid.Server != types.GroupServer {
+		return nil, types.EmptyJID, NewWmError(connId, ErrInvalidJid, "not a group chat")
+	}
+
+	return client, chatJid, nil
+}
+
+func WmCreateGroup(connId int, name string, userIdsJson string) (string, error) {
+
+	LOG_TRACE(connId, "create group " + strconv.Itoa(connId) + ", \"" + RedactText(name) + "\"")
+
+	// sanity check arg
+	if connId == -1 {
+		return "", NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	if len(name) == 0 {
+		return "", NewWmError(connId, ErrInvalidArgument, "empty group name")
+	}
+
+	var userIds []string
+	jsonErr := json.Unmarshal([]byte(userIdsJson), &userIds)
+	if jsonErr != nil {
+		return "", NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("user ids json err %#v", jsonErr))
+	}
+
+	// get client
+	var client *whatsmeow.Client = GetClient(connId)
+	if client == nil {
+		return "", NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	var userJids []types.JID
+	for _, userId := range userIds {
+		userJid, jidErr := types.ParseJID(userId)
+		if jidErr != nil {
+			return "", NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+		}
+
+		userJids = append(userJids, userJid)
+	}
+
+	req := whatsmeow.ReqCreateGroup{
+		Name:         name,
+		Participants: userJids,
+	}
+	groupInfo, createErr := client.CreateGroup(context.TODO(), req)
+	if createErr != nil {
+		return "", NewWmError(connId, ErrorCodeFor(createErr, ErrRequestFailed), fmt.Sprintf("create group error %#v", createErr))
+	}
+
+	// add it to the chat list right away, JoinedGroup isn't handled
+	groupId := StrFromJid(groupInfo.JID)
+	groupName := GetGroupDisplayName(connId, groupInfo)
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify %s %s", groupId, RedactText(groupName)))
+	CWmNewContactsNotify(connId, groupId, groupName, "", BoolToInt(false), BoolToInt(false), NotifyDirect)
+	AddContactName(connId, groupId, groupName)
+
+	LOG_TRACE(connId, "create group ok " + groupId)
+
+	return groupId, nil
+}
+
+func WmSetGroupName(connId int, chatId string, name string) error {
+
+	LOG_TRACE(connId, "set group name " + strconv.Itoa(connId) + ", " + chatId + ", \"" + RedactText(name) + "\"")
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	setErr := client.SetGroupName(context.TODO(), groupJid, name)
+	if setErr != nil {
+		return NewWmError(connId, ErrorCodeFor(setErr, ErrRequestFailed), fmt.Sprintf("set group name error %#v", setErr))
+	}
+
+	LOG_TRACE(connId, "set group name ok")
+
+	return nil
+}
+
+func WmSetGroupDescription(connId int, chatId string, description string) error {
+
+	LOG_TRACE(connId, "set group description " + strconv.Itoa(connId) + ", " + chatId + ", \"" + RedactText(description) + "\"")
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	setErr := client.SetGroupDescription(context.TODO(), groupJid, description)
+	if setErr != nil {
+		return NewWmError(connId, ErrorCodeFor(setErr, ErrRequestFailed), fmt.Sprintf("set group description error %#v", setErr))
+	}
+
+	LOG_TRACE(connId, "set group description ok")
+
+	return nil
+}
+
+func WmSetGroupPhoto(connId int, chatId string, filePath string) error {
+
+	LOG_TRACE(connId, "set group photo " + strconv.Itoa(connId) + ", " + chatId + ", " + filePath)
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	// no file removes the photo
+	var data []byte
+	if len(filePath) > 0 {
+		var err error
+		data, err = os.ReadFile(filePath)
+		if err != nil {
+			return NewWmError(connId, ErrFileRead, fmt.Sprintf("read file %s err %#v", filePath, err))
+		}
+	}
+
+	_, setErr := client.SetGroupPhoto(context.TODO(), groupJid, data)
+	if setErr != nil {
+		code := ErrorCodeFor(setErr, ErrRequestFailed)
+		if errors.Is(setErr, whatsmeow.ErrInvalidImageFormat) {
+			code = ErrInvalidArgument
+		}
+		return NewWmError(connId, code, fmt.Sprintf("set group photo error %#v", setErr))
+	}
+
+	LOG_TRACE(connId, "set group photo ok")
+
+	return nil
+}
+
+func WmSetGroupAnnounce(connId int, chatId string, announce int) error {
+
+	LOG_TRACE(connId, "set group announce " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(announce))
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	setErr := client.SetGroupAnnounce(context.TODO(), groupJid, announce != 0)
+	if setErr != nil {
+		return NewWmError(connId, ErrorCodeFor(setErr, ErrRequestFailed), fmt.Sprintf("set group announce error %#v", setErr))
+	}
+
+	LOG_TRACE(connId, "set group announce ok")
+
+	return nil
+}
+
+func WmSetGroupLocked(connId int, chatId string, locked int) error {
+
+	LOG_TRACE(connId, "set group locked " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(locked))
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	setErr := client.SetGroupLocked(context.TODO(), groupJid, locked != 0)
+	if setErr != nil {
+		return NewWmError(connId, ErrorCodeFor(setErr, ErrRequestFailed), fmt.Sprintf("set group locked error %#v", setErr))
+	}
+
+	LOG_TRACE(connId, "set group locked ok")
+
+	return nil
+}
+
+func WmSetGroupMemberAddMode(connId int, chatId string, adminsOnly int) error {
+
+	LOG_TRACE(connId, "set group member add mode " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(adminsOnly))
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	mode := types.GroupMemberAddModeAllMember
+	if adminsOnly != 0 {
+		mode = types.GroupMemberAddModeAdmin
+	}
+
+	setErr := client.SetGroupMemberAddMode(context.TODO(), groupJid, mode)
+	if setErr != nil {
+		return NewWmError(connId, ErrorCodeFor(setErr, ErrRequestFailed), fmt.Sprintf("set group member add mode error %#v", setErr))
+	}
+
+	LOG_TRACE(connId, "set group member add mode ok")
+
+	return nil
+}
+
+func WmSetGroupJoinApprovalMode(connId int, chatId string, approval int) error {
+
+	LOG_TRACE(connId, "set group join approval mode " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(approval))
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	setErr := client.SetGroupJoinApprovalMode(context.TODO(), groupJid, approval != 0)
+	if setErr != nil {
+		return NewWmError(connId, ErrorCodeFor(setErr, ErrRequestFailed), fmt.Sprintf("set group join approval mode error %#v", setErr))
+	}
+
+	LOG_TRACE(connId, "set group join approval mode ok")
+
+	return nil
+}
+
+func InviteErrorCode(err error) int {
+	if errors.Is(err, whatsmeow.ErrInviteLinkRevoked) || errors.Is(err, whatsmeow.ErrInviteLinkInvalid) {
+		return ErrInvalidArgument
+	}
+
+	return ErrorCodeFor(err, ErrRequestFailed)
+}
+
+func WmGetGroupInviteLink(connId int, chatId string, reset int) (string, error) {
+
+	LOG_TRACE(connId, "get group invite link " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(reset))
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return "", groupErr
+	}
+
+	link, linkErr := client.GetGroupInviteLink(context.TODO(), groupJid, reset != 0)
+	if linkErr != nil {
+		return "", NewWmError(connId, ErrorCodeFor(linkErr, ErrRequestFailed), fmt.Sprintf("get group invite link error %#v", linkErr))
+	}
+
+	LOG_TRACE(connId, "get group invite link ok")
+
+	return link, nil
+}
+
+func WmPreviewGroupInvite(connId int, link string) (string, error) {
+
+	LOG_TRACE(connId, "preview group invite " + strconv.Itoa(connId))
+
+	// sanity check arg
+	if connId == -1 {
+		return "", NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	// get client
+	var client *whatsmeow.Client = GetClient(connId)
+	if client == nil {
+		return "", NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	groupInfo, infoErr := client.GetGroupInfoFromLink(context.TODO(), link)
+	if infoErr != nil {
+		return "", NewWmError(connId, InviteErrorCode(infoErr), fmt.Sprintf("preview group invite error %#v", infoErr))
+	}
+
+	infoJson, jsonErr := GetGroupInfoJson(connId, groupInfo)
+	if jsonErr != nil {
+		return "", NewWmError(connId, ErrUnknown, fmt.Sprintf("marshal group info err %#v", jsonErr))
+	}
+
+	LOG_TRACE(connId, "preview group invite ok")
+
+	return infoJson, nil
+}
+
+func WmJoinGroupWithLink(connId int, link string) (string, error) {
+
+	LOG_TRACE(connId, "join group with link " + strconv.Itoa(connId))
+
+	// sanity check arg
+	if connId == -1 {
+		return "", NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	// get client
+	var client *whatsmeow.Client = GetClient(connId)
+	if client == nil {
+		return "", NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	groupJid, joinErr := client.JoinGroupWithLink(context.TODO(), link)
+	if joinErr != nil {
+		return "", NewWmError(connId, InviteErrorCode(joinErr), fmt.Sprintf("join group with link error %#v", joinErr))
+	}
+
+	groupId := StrFromJid(groupJid)
+	LOG_TRACE(connId, "join group with link ok " + groupId)
+	NotifyJoinedGroup(connId, client, groupJid)
+
+	return groupId, nil
+}
+
+func WmAcceptGroupInvite(connId int, chatId string, inviterId string, code string, expiration int) error {
+
+	LOG_TRACE(connId, "accept group invite " + strconv.Itoa(connId) + ", " + chatId + ", " + inviterId)
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	inviterJid, jidErr := types.ParseJID(inviterId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
+
+	if expiration != 0 && time.Now().Unix() > int64(expiration) {
+		return NewWmError(connId, ErrInvalidArgument, "invite expired")
+	}
+
+	joinErr := client.JoinGroupWithInvite(context.TODO(), groupJid, inviterJid, code, int64(expiration))
+	if joinErr != nil {
+		return NewWmError(connId, InviteErrorCode(joinErr), fmt.Sprintf("accept group invite error %#v", joinErr))
+	}
+
+	LOG_TRACE(connId, "accept group invite ok")
+	NotifyJoinedGroup(connId, client, groupJid)
+
+	return nil
+}
+
+// adds a group we just joined to the chat list, JoinedGroup isn't handled
+func NotifyJoinedGroup(connId int, client *whatsmeow.Client, groupJid types.JID) {
+	groupInfo, groupErr := client.GetGroupInfo(context.TODO(), groupJid)
+	if groupErr != nil {
+		// pending admin approval, or the info isn't available yet
+		LOG_WARNING(connId, fmt.Sprintf("get group info failed %#v", groupErr))
+		return
+	}
+
+	groupId := StrFromJid(groupJid)
+	groupName := GetGroupDisplayName(connId, groupInfo)
+	LOG_TRACE(connId, fmt.Sprintf("Call CWmNewContactsNotify %s %s", groupId, RedactText(groupName)))
+	CWmNewContactsNotify(connId, groupId, groupName, "", BoolToInt(false), BoolToInt(false), NotifyDirect)
+	AddContactName(connId, groupId, groupName)
+
+	if groupInfo.IsEphemeral {
+		SetExpiration(connId, groupId, groupInfo.DisappearingTimer)
+	}
+}
+
+// result of changing one participant, serialized as a (id, status) pair
+type ParticipantStatus struct {
+	Type string `json:"type"`
+	Code int    `json:"code,omitempty"`
+}
+
+func WmUpdateGroupParticipants(connId int, chatId string, userIdsJson string, action string) (string, error) {
+
+	LOG_TRACE(connId, "update group participants " + strconv.Itoa(connId) + ", " + chatId + ", " + action)
+
+	// sanity check arg
+	if connId == -1 {
+		return "", NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	var change whatsmeow.ParticipantChange
+	switch action {
+	case "add":
+		change = whatsmeow.ParticipantChangeAdd
+	case "remove":
+		change = whatsmeow.ParticipantChangeRemove
+	case "promote":
+		change = whatsmeow.ParticipantChangePromote
+	case "demote":
+		change = whatsmeow.ParticipantChangeDemote
+	default:
+		return "", NewWmError(connId, ErrInvalidArgument, "unknown action " + action)
+	}
+
+	var userIds []string
+	jsonErr := json.Unmarshal([]byte(userIdsJson), &userIds)
+	if jsonErr != nil {
+		return "", NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("user ids json err %#v", jsonErr))
+	}
+
+	if len(userIds) == 0 {
+		return "", NewWmError(connId, ErrInvalidArgument, "no participants")
+	}
+
+	// get client
+	var client *whatsmeow.Client = GetClient(connId)
+	if client == nil {
+		return "", NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	// parse chat JID
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return "", NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
+
+	if chatJid.Server != types.GroupServer {
+		return "", NewWmError(connId, ErrInvalidJid, "not a group chat")
+	}
+
+	results := map[string]ParticipantStatus{}
+	var userJids []types.JID
+	var phones []string
+	for _, userId := range userIds {
+		userJid, userJidErr := types.ParseJID(userId)
+		if userJidErr != nil {
+			return "", NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", userJidErr))
+		}
+
+		userJids = append(userJids, userJid)
+		if userJid.Server == types.DefaultUserServer {
+			phones = append(phones, "+" + userJid.User)
+		}
+	}
+
+	// numbers not on whatsapp aren't reported well by the group request, check them first
+	ctx := context.TODO()
+	if change == whatsmeow.ParticipantChangeAdd && len(phones) > 0 {
+		registered, isOnErr := client.IsOnWhatsApp(ctx, phones)
+		if isOnErr != nil {
+			return "", NewWmError(connId, ErrorCodeFor(isOnErr, ErrRequestFailed), fmt.Sprintf("is on whatsapp failed %#v", isOnErr))
+		}
+
+		var checkedJids []types.JID
+		for _, userJid := range userJids {
+			isOn := true
+			for _, info := range registered {
+				if info.Query == "+" + userJid.User && !info.IsIn {
+					isOn = false
+				}
+			}
+
+			if isOn {
+				checkedJids = append(checkedJids, userJid)
+			} else {
+				results[StrFromJid(userJid)] = ParticipantStatus{Type: "NotOnWhatsApp"}
+			}
+		}
+
+		userJids = checkedJids
+	}
+
+	if len(userJids) > 0 {
+		participants, updateErr := client.UpdateGroupParticipants(ctx, chatJid, userJids, change)
+		if updateErr != nil {
+			return "", NewWmError(connId, ErrorCodeFor(updateErr, ErrRequestFailed), fmt.Sprintf("update group participants failed %#v", updateErr))
+		}
+
+		for _, participant := range participants {
+			// report under the id it was requested as
+			userId := StrFromJid(participant.JID)
+			for _, userJid := range userJids {
+				if userJid.ToNonAD() == participant.JID || userJid.ToNonAD() == participant.PhoneNumber || userJid.ToNonAD() == participant.LID {
+					userId = StrFromJid(userJid)
+				}
+			}
+
+			switch {
+			case participant.Error == 0:
+				results[userId] = ParticipantStatus{Type: "Ok"}
+			case participant.Error == 403 && participant.AddRequest != nil:
+				// privacy settings only allow an invite, send it like the phone app does
+				if SendGroupAddRequest(connId, client, chatJid, participant) {
+					results[userId] = ParticipantStatus{Type: "InviteSent"}
+				} else {
+					results[userId] = ParticipantStatus{Type: "PrivacyBlocked"}
+				}
+			case participant.Error == 403:
+				results[userId] = ParticipantStatus{Type: "PrivacyBlocked"}
+			case participant.Error == 404:
+				results[userId] = ParticipantStatus{Type: "NotOnWhatsApp"}
+			default:
+				results[userId] = ParticipantStatus{Type: "Failed", Code: participant.Error}
+			}
+		}
+	}
+
+	// keep the order of the request
+	var resultList [][]interface{}
+	for _, userId := range userIds {
+		userJid, _ := types.ParseJID(userId)
+		result, ok := results[StrFromJid(userJid)]
+		if !ok {
+			// missing from the response, count it as a server error
+			LOG_WARNING(connId, "no result for participant " + userId)
+			result = ParticipantStatus{Type: "Failed", Code: 500}
+		}
+		resultList = append(resultList, []interface{}{StrFromJid(userJid), result})
+	}
+
+	resultsJsonBytes, jsonErr := json.Marshal(resultList)
+	if jsonErr != nil {
+		return "", NewWmError(connId, ErrUnknown, fmt.Sprintf("marshal participant results err %#v", jsonErr))
+	}
+
+	LOG_TRACE(connId, "update group participants ok")
+
+	return string(resultsJsonBytes), nil
+}
+
+// sends a participant we couldn't add an invite to join, returns false on failure
+func SendGroupAddRequest(connId int, client *whatsmeow.Client, chatJid types.JID, participant types.GroupParticipant) bool {
+	groupName := ""
+	groupInfo, groupErr := client.GetGroupInfo(context.TODO(), chatJid)
+	if groupErr != nil {
+		LOG_WARNING(connId, fmt.Sprintf("get group info failed %#v", groupErr))
+	} else {
+		groupName = groupInfo.Name
+	}
+
+	userJid := participant.JID
+	if !participant.PhoneNumber.IsEmpty() {
+		userJid = participant.PhoneNumber
+	}
+
+	message := waE2E.Message{
+		GroupInviteMessage: &waE2E.GroupInviteMessage{
+			GroupJID:         proto.String(chatJid.String()),
+			InviteCode:       proto.String(participant.AddRequest.Code),
+			InviteExpiration: proto.Int64(participant.AddRequest.Expiration.Unix()),
+			GroupName:        proto.String(groupName),
+		},
+	}
+
+	sendResponse, sendErr := client.SendMessage(context.Background(), userJid, &message)
+	if sendErr != nil {
+		LOG_WARNING(connId, fmt.Sprintf("send group invite error %#v", sendErr))
+		return false
+	}
+
+	NotifySentMessage(connId, client, userJid, sendResponse.ID, sendResponse.Timestamp, false, &message)
+
+	return true
+}
+
+// keep in sync with struct RawGroupInfo in whatsmeow-nchat-sys/src/types.rs
+type GroupParticipantJson struct {
+	Id           string `json:"id"`
+	Name         string `json:"name"`
+	Lid          string `json:"lid"`
+	IsAdmin      bool   `json:"isAdmin"`
+	IsSuperAdmin bool   `json:"isSuperAdmin"`
+}
+
+type GroupInfoJson struct {
+	Id             string                 `json:"id"`
+	Name           string                 `json:"name"`
+	Description    string                 `json:"description"`
+	OwnerId        string                 `json:"ownerId"`
+	Created        int                    `json:"created"`
+	Participants   []GroupParticipantJson `json:"participants"`
+	Size           int                    `json:"size"`
+	IsAnnounce     bool                   `json:"isAnnounce"`
+	IsLocked       bool                   `json:"isLocked"`
+	AdminsOnlyAdd  bool                   `json:"adminsOnlyAdd"`
+	JoinApproval   bool                   `json:"joinApproval"`
+	EphemeralTimer uint32                 `json:"ephemeralTimer"`
+	IsCommunity    bool                   `json:"isCommunity"`
+	ParentId       string                 `json:"parentId"`
+}
+
+func GetGroupInfoJson(connId int, groupInfo *types.GroupInfo) (string, error) {
+	// prefer phone numbers, like the rest of the chats
+	ownerJid := groupInfo.OwnerJID
+	if !groupInfo.OwnerPN.IsEmpty() {
+		ownerJid = groupInfo.OwnerPN
+	}
+
+	info := GroupInfoJson{
+		Id:            StrFromJid(groupInfo.JID),
+		Name:          groupInfo.Name,
+		Description:   groupInfo.Topic,
+		Created:       int(groupInfo.GroupCreated.Unix()),
+		Participants:  []GroupParticipantJson{},
+		IsAnnounce:    groupInfo.IsAnnounce,
+		IsLocked:      groupInfo.IsLocked,
+		AdminsOnlyAdd: groupInfo.MemberAddMode == types.GroupMemberAddModeAdmin,
+		JoinApproval:  groupInfo.IsJoinApprovalRequired,
+		IsCommunity:   groupInfo.IsParent,
+	}
+
+	if !ownerJid.IsEmpty() {
+		info.OwnerId = StrFromJid(ownerJid)
+	}
+
+	if groupInfo.IsEphemeral {
+		info.EphemeralTimer = groupInfo.DisappearingTimer
+	}
+
+	if !groupInfo.LinkedParentJID.IsEmpty() {
+		info.ParentId = StrFromJid(groupInfo.LinkedParentJID)
+	}
+
+	for _, participant := range groupInfo.Participants {
+		memberJid := participant.JID
+		if !participant.PhoneNumber.IsEmpty() {
+			memberJid = participant.PhoneNumber
+		}
+
+		memberId := StrFromJid(memberJid)
+		memberLid := ""
+		if !participant.LID.IsEmpty() {
+			memberLid = StrFromJid(participant.LID)
+		}
+
+		info.Participants = append(info.Participants, GroupParticipantJson{
+			Id:           memberId,
+			Name:         GetContactName(connId, memberId),
+			Lid:          memberLid,
+			IsAdmin:      participant.IsAdmin,
+			IsSuperAdmin: participant.IsSuperAdmin,
+		})
+	}
+
+	// previews from invite links only list some participants
+	info.Size = groupInfo.ParticipantCount
+	if info.Size < len(info.Participants) {
+		info.Size = len(info.Participants)
+	}
+
+	infoJsonBytes, jsonErr := json.Marshal(info)
+	if jsonErr != nil {
+		return "", jsonErr
+	}
+
+	return string(infoJsonBytes), nil
+}
+
+func WmGetGroupInfo(connId int, chatId string) error {
+
+	LOG_TRACE(connId, "get group info " + strconv.Itoa(connId) + ", " + chatId)
+
+	client, groupJid, groupErr := GetGroupClient(connId, chatId)
+	if groupErr != nil {
+		return groupErr
+	}
+
+	// fetched in the background, the result (or error) is notified
+	go func() {
+		groupInfo, infoErr := client.GetGroupInfo(context.TODO(), groupJid)
+		if infoErr != nil {
+			errMsg := fmt.Sprintf("get group info failed %#v", infoErr)
+			LOG_WARNING(connId, errMsg)
+			CWmNewGroupInfoNotify(connId, chatId, "", ErrorCodeFor(infoErr, ErrRequestFailed), errMsg)
+			return
+		}
+
+		if groupInfo.IsEphemeral {
+			SetExpiration(connId, chatId, groupInfo.DisappearingTimer)
+		}
+
+		infoJson, jsonErr := GetGroupInfoJson(connId, groupInfo)
+		if jsonErr != nil {
+			errMsg := fmt.Sprintf("marshal group info err %#v", jsonErr)
+			LOG_WARNING(connId, errMsg)
+			CWmNewGroupInfoNotify(connId, chatId, "", ErrUnknown, errMsg)
+			return
+		}
+
+		LOG_TRACE(connId, "Call CWmNewGroupInfoNotify " + chatId)
+		CWmNewGroupInfoNotify(connId, chatId, infoJson, ErrNone, "")
+	}()
+
+	return nil
+}
+
+func WmGetContacts(connId int) error {
+
+	LOG_TRACE(connId, "get contacts " + strconv.Itoa(connId))
+
+	// sanity check arg
+	if connId == -1 {
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	// get client
+	client := GetClient(connId)
+	if client == nil {
+		return NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	// sync contacts
+	err := client.FetchAppState(context.TODO(), appstate.WAPatchCriticalUnblockLow, true, false)
+	if err != nil {
+		LOG_WARNING(connId, fmt.Sprintf("fetch contacts app state failed %#v", err))
+	}
+
+	// get contacts
+	GetContacts(connId)
+
+	return nil
+}
+
+func WmGetStatus(connId int, userId string) error {
+
+	LOG_TRACE(connId, "get status " + strconv.Itoa(connId) + ", " + userId)
+
+	// sanity check arg
+	if connId == -1 {
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	// get client
+	client := GetClient(connId)
+	if client == nil {
+		return NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	// ignore presence requests before connected
+	if GetState(connId) != Connected {
+		return NewWmError(connId, ErrNotLoggedIn, "not connected")
+	}
+
+	// ignore presence requests for groups
+	userJid, jidErr := types.ParseJID(userId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
+	if userJid.Server == types.GroupServer {
+		return NewWmError(connId, ErrInvalidArgument, "presence not available for groups")
+	}
+
+	// ignore presence requests for self
+	isSelfUser := IsSelfUser(client, userId)
+	if isSelfUser {
+		return NewWmError(connId, ErrInvalidArgument, "presence not available for self")
+	}
+
+	// subscribe user presence
+	ctx := context.TODO()
+	err := client.SubscribePresence(ctx, userJid)
+
+	// log any error
+	if err != nil {
+		return NewWmError(connId, ErrorCodeFor(err, ErrRequestFailed), fmt.Sprintf("get user status error %#v", err))
+	} else {
+		LOG_TRACE(connId, "get user status ok")
+	}
+
+	return nil
+}
+
+func WmMarkMessageRead(connId int, chatId string, senderId string, msgId string) error {
+
+	LOG_TRACE(connId, "mark message read " + strconv.Itoa(connId) + ", " + chatId + ", " + senderId + ", " + msgId)
+
+	// sanity check arg
+	if connId == -1 {
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	// get client
+	client := GetClient(connId)
+	if client == nil {
+		return NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	// mark read
+	msgIds := []types.MessageID{
+		msgId,
+	}
+	timeRead := time.Now()
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
+	senderJid, _ := types.ParseJID(senderId)
+	ctx := context.TODO()
+	err := client.MarkRead(ctx, msgIds, timeRead, chatJid, senderJid)
+
+	// store time
+	SetTimeRead(connId, chatId, timeRead)
+
+	// log any error
+	if err != nil {
+		return NewWmError(connId, ErrorCodeFor(err, ErrRequestFailed), fmt.Sprintf("mark message read error %#v", err))
+	} else {
+		LOG_TRACE(connId, fmt.Sprintf("mark message read ok %#v", msgId))
+	}
+
+	return nil
+}
+
+func WmDeleteMessage(connId int, chatId string, senderId string, msgId string) error {
+
+	LOG_TRACE(connId, "delete message " + strconv.Itoa(connId) + ", " + chatId + ", " + msgId)
+
+	// sanity check arg
+	if connId == -1 {
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
+	}
+
+	// get client
+	client := GetClient(connId)
+	if client == nil {
+		return NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
+	senderJid, _ := types.ParseJID(senderId)
+
+	// skip deleting messages sent by others in private chat
 	isGroup := (chatJid.Server == types.GroupServer)
 	isFromSelf := IsSelfUser(client, senderId)
 	if !isFromSelf && !isGroup {
-		LOG_TRACE(fmt.Sprintf("delete message isGroup %t isFromSelf %t skip %#v",
+		return NewWmError(connId, ErrInvalidArgument, fmt.Sprintf("delete message isGroup %t isFromSelf %t skip %#v",
 			isGroup, isFromSelf, msgId))
-		return -1
 	}
 
 	// delete message
@@ -3197,34 +4816,34 @@ func WmDeleteMessage(connId int, chatId string, senderId string, msgId string) i
 
 	// log any error
 	if err != nil {
-		LOG_WARNING(fmt.Sprintf("delete message error %#v", err))
-		return -1
+		return NewWmError(connId, ErrorCodeFor(err, ErrSendFailed), fmt.Sprintf("delete message error %#v", err))
 	} else {
-		LOG_TRACE(fmt.Sprintf("delete message ok %#v", msgId))
+		LOG_TRACE(connId, fmt.Sprintf("delete message ok %#v", msgId))
 	}
 
-	return 0
+	return nil
 }
 
-func WmDeleteChat(connId int, chatId string) int {
+func WmDeleteChat(connId int, chatId string) error {
 
-	LOG_TRACE("delete chat " + strconv.Itoa(connId) + ", " + chatId)
+	LOG_TRACE(connId, "delete chat " + strconv.Itoa(connId) + ", " + chatId)
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
 	// get chat jid
-	chatJid, _ := types.ParseJID(chatId)
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
 
 	// leave / delete
 	if chatJid.Server == types.GroupServer {
@@ -3234,96 +4853,93 @@ func WmDeleteChat(connId int, chatId string) int {
 
 		// log any error
 		if err != nil {
-			LOG_WARNING(fmt.Sprintf("leave group error %s %#v", chatId, err))
-			return -1
+			return NewWmError(connId, ErrorCodeFor(err, ErrRequestFailed), fmt.Sprintf("leave group error %s %#v", chatId, err))
 		} else {
-			LOG_TRACE(fmt.Sprintf("leave group ok (but not deleted) %s", chatId))
+			LOG_TRACE(connId, fmt.Sprintf("leave group ok (but not deleted) %s", chatId))
 		}
 	} else {
-		// if private, log warning (function not supported by underlying library)
-		LOG_WARNING(fmt.Sprintf("delete chat not supported %s", chatId))
+		// if private, return error (function not supported by underlying library)
+		return NewWmError(connId, ErrNotSupported, fmt.Sprintf("delete chat not supported %s", chatId))
 	}
 
-	return 0
+	return nil
 }
 
-func WmArchiveChat(connId int, chatId string, isArchived int) int {
+func WmArchiveChat(connId int, chatId string, isArchived int) error {
 
-	LOG_TRACE("archive chat " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(isArchived))
+	LOG_TRACE(connId, "archive chat " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(isArchived))
 
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
 	}
-	chatJid, _ := types.ParseJID(chatId)
 	archived := isArchived != 0
 
 	ctx := context.TODO()
 	err := client.SendAppState(ctx, appstate.BuildArchive(chatJid, archived, time.Time{}, nil))
 	if err != nil {
-		LOG_WARNING(fmt.Sprintf("archive chat error %s %#v", chatId, err))
-		return -1
+		return NewWmError(connId, ErrorCodeFor(err, ErrRequestFailed), fmt.Sprintf("archive chat error %s %#v", chatId, err))
 	}
 
-	LOG_TRACE(fmt.Sprintf("archive chat ok %s %t", chatId, archived))
-	return 0
+	LOG_TRACE(connId, fmt.Sprintf("archive chat ok %s %t", chatId, archived))
+	return nil
 }
 
-func WmPinChat(connId int, chatId string, isPinned int) int {
+func WmPinChat(connId int, chatId string, isPinned int) error {
 
-	LOG_TRACE("pin chat " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(isPinned))
+	LOG_TRACE(connId, "pin chat " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(isPinned))
 
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
+	}
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
 	}
-	chatJid, _ := types.ParseJID(chatId)
 	pin := isPinned != 0
 
 	ctx := context.TODO()
 	err := client.SendAppState(ctx, appstate.BuildPin(chatJid, pin))
 	if err != nil {
-		LOG_WARNING(fmt.Sprintf("pin chat error %s %#v", chatId, err))
-		return -1
+		return NewWmError(connId, ErrorCodeFor(err, ErrRequestFailed), fmt.Sprintf("pin chat error %s %#v", chatId, err))
 	}
 
-	LOG_TRACE(fmt.Sprintf("pin chat ok %s %t", chatId, pin))
-	return 0
+	LOG_TRACE(connId, fmt.Sprintf("pin chat ok %s %t", chatId, pin))
+	return nil
 }
 
-func WmSendTyping(connId int, chatId string, isTyping int) int {
+func WmSendTyping(connId int, chatId string, isTyping int) error {
 
-	LOG_TRACE("send typing " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(isTyping))
+	LOG_TRACE(connId, "send typing " + strconv.Itoa(connId) + ", " + chatId + ", " + strconv.Itoa(isTyping))
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
 	// do not send typing to self chat
 	isSelfChat := IsSelfChat(client, chatId)
 	if isSelfChat {
-		return 0
+		return nil
 	}
 
 	// set presence
@@ -3333,41 +4949,41 @@ func WmSendTyping(connId int, chatId string, isTyping int) int {
 	}
 
 	var chatPresenceMedia types.ChatPresenceMedia = types.ChatPresenceMediaText
-	chatJid, _ := types.ParseJID(chatId)
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
 	ctx := context.TODO()
 	err := client.SendChatPresence(ctx, chatJid, chatPresence, chatPresenceMedia)
 
 	// log any error
 	if err != nil {
-		LOG_WARNING(fmt.Sprintf("send typing error %#v", err))
-		return -1
+		return NewWmError(connId, ErrorCodeFor(err, ErrRequestFailed), fmt.Sprintf("send typing error %#v", err))
 	} else {
-		LOG_TRACE("send typing ok")
+		LOG_TRACE(connId, "send typing ok")
 	}
 
-	return 0
+	return nil
 }
 
-func WmSendStatus(connId int, isOnline int) int {
+func WmSendStatus(connId int, isOnline int) error {
 
-	LOG_TRACE("send status " + strconv.Itoa(connId) + ", " + strconv.Itoa(isOnline))
+	LOG_TRACE(connId, "send status " + strconv.Itoa(connId) + ", " + strconv.Itoa(isOnline))
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
 	// bail out if no push name yet
 	if len(client.Store.PushName) == 0 {
-		return -1
+		return NewWmError(connId, ErrNotLoggedIn, "no push name yet")
 	}
 
 	// set presence
@@ -3379,9 +4995,9 @@ func WmSendStatus(connId int, isOnline int) int {
 	ctx := context.TODO()
 	err := client.SendPresence(ctx, presence)
 	if err != nil {
-		LOG_WARNING("Failed to send presence")
+		return NewWmError(connId, ErrorCodeFor(err, ErrRequestFailed), fmt.Sprintf("send presence error %#v", err))
 	} else {
-		LOG_TRACE("Sent presence ok")
+		LOG_TRACE(connId, "Sent presence ok")
 		if isOnline == 1 {
 			CWmClearStatus(connId, FlagAway)
 		} else {
@@ -3389,17 +5005,16 @@ func WmSendStatus(connId int, isOnline int) int {
 		}
 	}
 
-	return 0
+	return nil
 }
 
-func WmDownloadFile(connId int, chatId string, msgId string, fileId string, action int) int {
+func WmDownloadFile(connId int, chatId string, msgId string, fileId string, action int) error {
 
-	LOG_TRACE("download file " + strconv.Itoa(connId) + ", " + chatId + ", " + msgId + ", " + fileId)
+	LOG_TRACE(connId, "download file " + strconv.Itoa(connId) + ", " + chatId + ", " + msgId + ", " + RedactKey(fileId))
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	// download file
@@ -3408,40 +5023,40 @@ func WmDownloadFile(connId int, chatId string, msgId string, fileId string, acti
 	// notify result
 	CWmNewMessageFileNotify(connId, chatId, msgId, filePath, fileStatus, action)
 
-	return 0
+	return nil
 }
 
-func WmSendReaction(connId int, chatId string, senderId string, msgId string, emoji string) int {
+func WmSendReaction(connId int, chatId string, senderId string, msgId string, emoji string) error {
 
-	LOG_TRACE("send reaction " + strconv.Itoa(connId) + ", " + chatId + ", " + msgId + ", \"" + emoji + "\"")
+	LOG_TRACE(connId, "send reaction " + strconv.Itoa(connId) + ", " + chatId + ", " + msgId + ", \"" + RedactText(emoji) + "\"")
 
 	// sanity check arg
 	if connId == -1 {
-		LOG_WARNING("invalid connId")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "invalid connId")
 	}
 
 	// get client
 	client := GetClient(connId)
 	if client == nil {
-		LOG_WARNING("client is nil")
-		return -1
+		return NewWmError(connId, ErrClientMissing, "client is nil")
 	}
 
 	// send reaction
-	chatJid, _ := types.ParseJID(chatId)
+	chatJid, jidErr := types.ParseJID(chatId)
+	if jidErr != nil {
+		return NewWmError(connId, ErrInvalidJid, fmt.Sprintf("jid err %#v", jidErr))
+	}
 	senderJid, _ := types.ParseJID(senderId)
 	_, sendErr :=
 		client.SendMessage(context.Background(), chatJid, client.BuildReaction(chatJid, senderJid, msgId, emoji))
 
 	if sendErr != nil {
-		LOG_WARNING(fmt.Sprintf("send reaction error %#v", sendErr))
-		return -1
+		return NewWmError(connId, ErrorCodeFor(sendErr, ErrSendFailed), fmt.Sprintf("send reaction error %#v", sendErr))
 	} else {
-		LOG_TRACE("send reaction ok")
+		LOG_TRACE(connId, "send reaction ok")
 		fromMe := true //messageInfo.IsFromMe
 		CWmNewMessageReactionNotify(connId, chatId, msgId, senderId, emoji, BoolToInt(fromMe))
 	}
 
-	return 0
+	return nil
 }
@@ -0,0 +1,254 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::c_int,
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+    sync::{LazyLock, RwLock},
+};
+
+use crate::ConnId;
+
+/// Name of the config file inside the profile directory.
+pub const CONFIG_FILE_NAME: &str = "app.conf";
+
+/// Per-connection settings, read by the Go side
+/// through `WmAppConfigGetNum`.
+///
+/// Stored as `key=value` lines (`0`/`1` for flags)
+/// when persisted, like nchat's `app.conf`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Config {
+    /// Link the device with a pairing code entered on the phone,
+    /// instead of scanning a Qr code.
+    ///
+    /// Unless one is passed to `CWmLogin`, the phone number is taken
+    /// from the profile directory name (eg. `WhatsAppMd_+6511111111`).
+    /// Also enabled by the `USE_PAIRING_CODE` env var.
+    pub use_pairing_code: bool,
+    /// Show the Qr code in the terminal, even if a GUI is available.
+    /// Only stored for the app, which renders the code itself.
+    pub use_qr_terminal: bool,
+    /// Write mentions of names with spaces as `@[First Last]`.
+    pub mentions_quoted: bool,
+    /// Other numeric params the Go side asked for, kept as-is.
+    pub other: BTreeMap<String, c_int>,
+}
+
+impl Config {
+    #[must_use]
+    pub fn get_num(&self, param: &str) -> c_int {
+        match param {
+            "use_pairing_code" => self.use_pairing_code.into(),
+            "use_qr_terminal" => self.use_qr_terminal.into(),
+            "mentions_quoted" => self.mentions_quoted.into(),
+            _ => self.other.get(param).copied().unwrap_or(0),
+        }
+    }
+
+    pub fn set_num(&mut self, param: &str, value: c_int) {
+        match param {
+            "use_pairing_code" => self.use_pairing_code = value != 0,
+            "use_qr_terminal" => self.use_qr_terminal = value != 0,
+            "mentions_quoted" => self.mentions_quoted = value != 0,
+            _ => {
+                self.other.insert(param.to_owned(), value);
+            }
+        }
+    }
+
+    /// Parses `key=value` lines, ignoring blank lines, `#` comments,
+    /// lines without a key and values that aren't numbers.
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        let mut config = Self::default();
+        for line in input.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+            if let Ok(value) = value.trim().parse() {
+                config.set_num(key, value);
+            }
+        }
+        config
+    }
+
+    /// Serializes to `key=value` lines, sorted by key.
+    #[must_use]
+    pub fn to_file_string(&self) -> String {
+        let mut entries: BTreeMap<&str, c_int> =
+            self.other.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        entries.insert("mentions_quoted", self.mentions_quoted.into());
+        entries.insert("use_pairing_code", self.use_pairing_code.into());
+        entries.insert("use_qr_terminal", self.use_qr_terminal.into());
+
+        let mut out = String::new();
+        for (key, value) in entries {
+            _ = writeln!(out, "{key}={value}");
+        }
+        out
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    config: Config,
+    /// Where to save the config on every change, if persisted.
+    file: Option<PathBuf>,
+}
+
+impl Entry {
+    fn save(&self) -> io::Result<()> {
+        match &self.file {
+            Some(file) => std::fs::write(file, self.config.to_file_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+static CONFIGS: LazyLock<RwLock<HashMap<ConnId, Entry>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Returns the config of connection `id`, or the default
+/// if none was set.
+#[must_use]
+pub fn config(id: ConnId) -> Config {
+    CONFIGS
+        .read()
+        .ok()
+        .and_then(|cmap| cmap.get(&id).map(|e| e.config.clone()))
+        .unwrap_or_default()
+}
+
+/// Replaces the config of connection `id`,
+/// saving it if persistence is enabled.
+///
+/// # Errors
+/// If saving the config file failed.
+/// The new config is used regardless.
+pub fn set_config(id: ConnId, config: Config) -> io::Result<()> {
+    let Ok(mut cmap) = CONFIGS.write() else {
+        return Ok(());
+    };
+    let entry = cmap.entry(id).or_default();
+    entry.config = config;
+    entry.save()
+}
+
+/// Persists the config of connection `id` as
+/// [`CONFIG_FILE_NAME`] in `profile_dir`.
+///
+/// If the file already exists it's loaded, replacing the current config,
+/// otherwise the current config is written to it.
+/// From then on every change is saved.
+///
+/// # Errors
+/// If reading or writing the config file failed.
+pub fn persist_config(id: ConnId, profile_dir: &Path) -> io::Result<()> {
+    let file = profile_dir.join(CONFIG_FILE_NAME);
+    let loaded = match std::fs::read_to_string(&file) {
+        Ok(contents) => Some(Config::parse(&contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+
+    let Ok(mut cmap) = CONFIGS.write() else {
+        return Ok(());
+    };
+    let entry = cmap.entry(id).or_default();
+    entry.file = Some(file);
+    match loaded {
+        Some(config) => {
+            entry.config = config;
+            Ok(())
+        }
+        None => entry.save(),
+    }
+}
+
+/// Forgets the config of connection `id`
+/// (the persisted file is kept).
+pub fn remove_config(id: ConnId) {
+    if let Ok(mut cmap) = CONFIGS.write() {
+        cmap.remove(&id);
+    }
+}
+
+pub(crate) fn get_num(id: ConnId, param: &str) -> c_int {
+    CONFIGS
+        .read()
+        .ok()
+        .and_then(|cmap| cmap.get(&id).map(|e| e.config.get_num(param)))
+        .unwrap_or(0)
+}
+
+pub(crate) fn set_num(id: ConnId, param: &str, value: c_int) -> io::Result<()> {
+    let Ok(mut cmap) = CONFIGS.write() else {
+        return Ok(());
+    };
+    let entry = cmap.entry(id).or_default();
+    entry.config.set_num(param, value);
+    entry.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_skips_comments_blank_and_malformed_lines() {
+        let config = Config::parse(
+            "# nchat app config\n\
+             \n\
+             use_pairing_code=1\n\
+             \x20 use_qr_terminal = 0 \n\
+             mentions_quoted\n\
+             attachment_prefetch=not a number\n\
+             =3\n\
+             proxy_port=8080\n",
+        );
+        assert!(config.use_pairing_code);
+        assert!(!config.use_qr_terminal);
+        assert!(!config.mentions_quoted);
+        assert_eq!(
+            config.other,
+            BTreeMap::from([("proxy_port".to_owned(), 8080)])
+        );
+    }
+
+    #[test]
+    fn file_string_round_trips() {
+        let mut config = Config::default();
+        config.set_num("use_qr_terminal", 1);
+        config.set_num("mentions_quoted", 7);
+        config.set_num("proxy_port", 8080);
+        config.set_num("negative", -1);
+
+        let file = config.to_file_string();
+        assert_eq!(
+            file,
+            "mentions_quoted=1\nnegative=-1\nproxy_port=8080\nuse_pairing_code=0\nuse_qr_terminal=1\n"
+        );
+        assert_eq!(Config::parse(&file), config);
+        assert_eq!(config.get_num("proxy_port"), 8080);
+        assert_eq!(config.get_num("missing"), 0);
+    }
+
+    #[test]
+    fn unknown_keys_survive_round_trip() {
+        let config = Config::parse("# kept\nfoo=2\n\nbar=-5\nuse_pairing_code=1\n");
+        let reparsed = Config::parse(&config.to_file_string());
+        assert_eq!(reparsed, config);
+        assert_eq!(reparsed.get_num("foo"), 2);
+        assert_eq!(reparsed.get_num("bar"), -5);
+    }
+}
@@ -1,12 +1,13 @@
 use std::{
     collections::HashMap,
     ffi::{c_char, c_int},
-    sync::{LazyLock, RwLock},
+    sync::{LazyLock, Mutex, RwLock},
 };
-use tokio::sync::mpsc::UnboundedSender as Sender;
+use tokio::sync::{mpsc::UnboundedSender as Sender, oneshot};
 
 use crate::{
-    handlers::cstr, ConnId, DownloadFileAction, DownloadFileStatus, Jid, MsgId, StatusFlags,
+    handlers::cstr, ConnId, DownloadFileAction, DownloadFileStatus, GroupChange, GroupInfo, Jid,
+    LoginProgress, MessageContent, MsgId, PollOption, StatusFlags, WmError,
 };
 
 #[derive(Debug, Clone)]
@@ -18,21 +19,39 @@ pub enum ChatEvent {
         is_group: bool,
         notify: isize,
     },
+    /// A chat from the history sync, with its settings
+    /// from the app state.
     NewChatsNotify {
+        /// Name of the group, or empty if unknown
+        /// (see [`ChatEvent::NewContactsNotify`] for contacts).
+        name: String,
+        /// Has unread messages, or was marked as unread.
         is_unread: bool,
+        unread_count: usize,
         is_muted: bool,
+        /// Unix time (seconds) the chat is muted until,
+        /// `-1` if forever or `0` if not muted.
+        muted_until: isize,
         is_pinned: bool,
+        is_archived: bool,
+        /// Disappearing messages timer in seconds, `0` if off.
+        ephemeral_timer: u32,
         last_message_time: isize,
     },
     NewMessagesNotify {
         msg_id: MsgId,
         sender_id: Jid,
+        /// The body, or the caption of media.
         text: String,
+        content: MessageContent,
         from_me: bool,
         quoted_id: Option<MsgId>,
         file_id_path: Option<(String, String)>,
         file_status: DownloadFileStatus,
         time_sent: isize,
+        /// Unix time when this disappearing message expires,
+        /// `0` if it doesn't.
+        expires_at: isize,
         is_read: bool,
         is_edited: bool,
     },
@@ -57,6 +76,22 @@ pub enum ChatEvent {
         emoji: String,
         from_me: bool,
     },
+    /// New results of a poll (sent as [`MessageContent::Poll`]),
+    /// after someone voted or changed their vote.
+    ///
+    /// Votes can only be tallied for polls this device received,
+    /// and only the 1000 newest of them are kept.
+    PollUpdate {
+        poll_id: MsgId,
+        /// All options, in the poll's order.
+        options: Vec<PollOption>,
+    },
+    /// Disappearing messages were turned on, off or changed,
+    /// by anyone in the chat (including us).
+    EphemeralSettingChanged {
+        /// Seconds messages are kept for, `0` if turned off.
+        timer: u32,
+    },
     DeleteChatNotify,
     DeleteMessageNotify(MsgId),
     UpdateIsMuted(bool),
@@ -65,11 +100,20 @@ pub enum ChatEvent {
         is_pinned: bool,
         time_pinned: isize,
     },
-    /// Updates the group members list,
-    /// with a map of jid to display name.
-    GroupMembers(HashMap<Jid, String>),
+    /// Info and members of a group, fetched with `CWmGetGroupInfo`.
+    GroupInfo(GroupInfo),
+    /// Someone changed a group, or its members.
+    GroupChange {
+        /// Who made the change, if known.
+        actor: Option<Jid>,
+        /// Unix time (seconds) of the change.
+        time: isize,
+        change: GroupChange,
+    },
 }
 
+// Most events are chat events, boxing them isn't worth it
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum Event {
     ChatEvent(Jid, ChatEvent),
@@ -86,11 +130,51 @@ pub enum Event {
     /// Qr code displayed on the computer screen.
     ///
     /// Scan the Qr code to authenticate
-    QrCode(String),
+    ///
+    /// `code` is the raw payload, not an image: render it
+    /// with `whatsmeow_nchat::qr::LoginQr`.
+    QrCode {
+        conn_id: ConnId,
+        code: String,
+    },
+    /// The previous Qr code expired, display this one instead.
+    QrCodeRefreshed {
+        conn_id: ConnId,
+        code: String,
+    },
+    /// All Qr codes expired without being scanned,
+    /// you'll need to log in again.
+    QrCodeExpired {
+        conn_id: ConnId,
+    },
     /// Open the WhatsApp notification "Enter code to link new device" on your phone,
     /// click "Confirm" and enter below pairing code on your phone, or press CTRL-C
     /// to abort.
-    PairingCode(String),
+    PairingCode {
+        conn_id: ConnId,
+        code: String,
+    },
+    /// Progress of a login started with `CWmLogin`,
+    /// the last one has [`LoginProgress::is_finished`] set.
+    LoginProgress {
+        conn_id: ConnId,
+        progress: LoginProgress,
+    },
+    /// Instructions on how to link the device, to show to the user.
+    ///
+    /// Only sent in headless mode, otherwise they're printed to stdout.
+    LoginInstructions {
+        conn_id: ConnId,
+        text: String,
+    },
+    /// The login failed and can't be retried as-is,
+    /// `reason` is meant to be shown to the user.
+    ///
+    /// Only sent in headless mode, otherwise it's printed to stdout.
+    FatalLoginError {
+        conn_id: ConnId,
+        reason: String,
+    },
     /// When it's about to print something, so it's releasing the TUI?
     SetProtocolUiControl {
         is_take_control: bool,
@@ -108,6 +192,72 @@ pub fn add_sender(id: ConnId, sender: Sender<Event>) {
     }
 }
 
+/// Removes the event sender of connection `id`,
+/// closing its `Receiver`.
+pub fn remove_sender(id: ConnId) {
+    if let Ok(mut smap) = SENDERS.write() {
+        smap.remove(&id);
+    }
+}
+
+type LoginWaiterMap = HashMap<ConnId, oneshot::Sender<LoginProgress>>;
+static LOGIN_WAITERS: LazyLock<Mutex<LoginWaiterMap>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Returns a `Receiver` for the outcome of the next login of connection `id`.
+///
+/// Call this before `CWmLogin`, so a quick outcome isn't missed.
+/// Returns `None` if someone is already waiting on a login of this connection.
+pub fn wait_login(id: ConnId) -> Option<oneshot::Receiver<LoginProgress>> {
+    let mut wmap = LOGIN_WAITERS.lock().ok()?;
+    if wmap.get(&id).is_some_and(|s| !s.is_closed()) {
+        return None;
+    }
+    let (sender, receiver) = oneshot::channel();
+    wmap.insert(id, sender);
+    Some(receiver)
+}
+
+pub fn finish_login(id: ConnId, progress: LoginProgress) {
+    if let Ok(mut wmap) = LOGIN_WAITERS.lock() {
+        if let Some(s) = wmap.remove(&id) {
+            _ = s.send(progress);
+        }
+    }
+}
+
+type GroupInfoResult = Result<GroupInfo, WmError>;
+// Keyed by the raw group id, which Go sends back as given,
+// so replies with an unparseable id still reach their waiters
+type GroupInfoWaiterMap = HashMap<(ConnId, String), Vec<oneshot::Sender<GroupInfoResult>>>;
+static GROUP_INFO_WAITERS: LazyLock<Mutex<GroupInfoWaiterMap>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Returns a `Receiver` for the next info of group `group_id`
+/// fetched by connection `id`, or the error fetching it.
+///
+/// Call this before `CWmGetGroupInfo`, so a quick reply isn't missed.
+pub fn wait_group_info(id: ConnId, group_id: Jid) -> oneshot::Receiver<GroupInfoResult> {
+    let (sender, receiver) = oneshot::channel();
+    if let Ok(mut wmap) = GROUP_INFO_WAITERS.lock() {
+        // Drop the waiters that gave up
+        wmap.retain(|_, senders| {
+            senders.retain(|s| !s.is_closed());
+            !senders.is_empty()
+        });
+        wmap.entry((id, group_id.to_id())).or_default().push(sender);
+    }
+    receiver
+}
+
+pub fn finish_group_info(id: ConnId, group_id: &str, result: &GroupInfoResult) {
+    if let Ok(mut wmap) = GROUP_INFO_WAITERS.lock() {
+        for s in wmap.remove(&(id, group_id.to_owned())).unwrap_or_default() {
+            _ = s.send(result.clone());
+        }
+    }
+}
+
 pub fn sendm(id: c_int, event: Event) {
     if let Ok(smap) = SENDERS.read() {
         if let Some(s) = smap.get(&ConnId(id as _)) {
@@ -1,12 +1,12 @@
-use std::{
-    collections::HashMap,
-    ffi::{c_char, c_int, CStr},
-    sync::{LazyLock, Mutex},
-};
+use std::ffi::{c_char, c_int, CStr};
+
+use tracing::Level;
 
 use crate::{
-    events::{sendc, sendm, ChatEvent, Event},
-    DownloadFileAction, DownloadFileStatus, Jid, MsgId, StatusFlags,
+    config,
+    events::{finish_group_info, finish_login, sendc, sendm, ChatEvent, Event},
+    ConnId, DownloadFileAction, DownloadFileStatus, ErrorCode, GroupChange, GroupInfo,
+    GroupSetting, Jid, LoginProgress, MessageContent, MsgId, PollOption, StatusFlags, WmError,
 };
 
 pub fn cstr(ptr: *const c_char) -> String {
@@ -45,18 +45,28 @@ extern "C" fn WmNewContactsNotify(
 extern "C" fn WmNewChatsNotify(
     conn_id: c_int,
     chat_id: *const c_char,
+    name: *const c_char,
     is_unread: c_int,
+    unread_count: c_int,
     is_muted: c_int,
+    muted_until: c_int,
     is_pinned: c_int,
+    is_archived: c_int,
+    ephemeral_timer: c_int,
     last_message_time: c_int,
 ) {
     sendc(
         conn_id,
         chat_id,
         ChatEvent::NewChatsNotify {
+            name: cstr(name),
             is_unread: is_unread != 0,
+            unread_count: unread_count.max(0) as _,
             is_muted: is_muted != 0,
+            muted_until: muted_until as _,
             is_pinned: is_pinned != 0,
+            is_archived: is_archived != 0,
+            ephemeral_timer: ephemeral_timer.max(0) as _,
             last_message_time: last_message_time as _,
         },
     );
@@ -69,12 +79,14 @@ extern "C" fn WmNewMessagesNotify(
     msg_id: *const c_char,
     sender_id: *const c_char,
     text: *const c_char,
+    content_json: *const c_char,
     from_me: c_int,
     quoted_id: *const c_char,
     file_id: *const c_char,
     file_path: *const c_char,
     file_status: c_int,
     time_sent: c_int,
+    expires_at: c_int,
     is_read: c_int,
     is_edited: c_int,
 ) {
@@ -91,12 +103,14 @@ extern "C" fn WmNewMessagesNotify(
             msg_id: MsgId(cstr(msg_id)),
             sender_id,
             text: cstr(text),
+            content: MessageContent::from_json(&cstr(content_json)),
             from_me: from_me != 0,
             quoted_id: (!quoted_id.is_empty()).then_some(MsgId(quoted_id)),
             file_id_path: (!file_id.is_empty() && !file_path.is_empty())
                 .then_some((file_id, file_path)),
             file_status: DownloadFileStatus::from_raw(file_status),
             time_sent: time_sent as _,
+            expires_at: expires_at as _,
             is_read: is_read != 0,
             is_edited: is_edited != 0,
         },
@@ -209,6 +223,52 @@ extern "C" fn WmNewMessageReactionNotify(
     );
 }
 
+#[derive(serde::Deserialize)]
+struct PollResult {
+    name: String,
+    voters: Vec<String>,
+}
+
+#[no_mangle]
+extern "C" fn WmNewPollUpdateNotify(
+    conn_id: c_int,
+    chat_id: *const c_char,
+    poll_id: *const c_char,
+    results_json: *const c_char,
+) {
+    let results: Vec<PollResult> = serde_json::from_str(&cstr(results_json)).unwrap_or_default();
+    let options = results
+        .into_iter()
+        .map(|result| PollOption {
+            name: result.name,
+            voters: result
+                .voters
+                .iter()
+                .filter_map(|voter| Jid::parse(voter))
+                .collect(),
+        })
+        .collect();
+    sendc(
+        conn_id,
+        chat_id,
+        ChatEvent::PollUpdate {
+            poll_id: MsgId(cstr(poll_id)),
+            options,
+        },
+    );
+}
+
+#[no_mangle]
+extern "C" fn WmNewEphemeralSettingNotify(conn_id: c_int, chat_id: *const c_char, timer: c_int) {
+    sendc(
+        conn_id,
+        chat_id,
+        ChatEvent::EphemeralSettingChanged {
+            timer: timer.max(0) as u32,
+        },
+    );
+}
+
 #[no_mangle]
 extern "C" fn WmDeleteChatNotify(conn_id: c_int, chat_id: *const c_char) {
     sendc(conn_id, chat_id, ChatEvent::DeleteChatNotify);
@@ -277,14 +337,116 @@ extern "C" fn WmClearStatus(conn_id: c_int, flags: c_int) {
 }
 
 #[no_mangle]
-extern "C" fn WmNewGroupMembersNotify(
-    conn_id: ::std::os::raw::c_int,
-    chat_id: *mut ::std::os::raw::c_char,
-    members_json: *mut ::std::os::raw::c_char,
+extern "C" fn WmNewGroupInfoNotify(
+    conn_id: c_int,
+    chat_id: *const c_char,
+    info_json: *const c_char,
+    err_code: c_int,
+    err_msg: *const c_char,
 ) {
-    let json = cstr(members_json);
-    let map: HashMap<Jid, String> = serde_json::from_str(&json).unwrap_or_default();
-    sendc(conn_id, chat_id, ChatEvent::GroupMembers(map));
+    let chat_id = cstr(chat_id);
+    let info_json = cstr(info_json);
+    let err_msg = cstr(err_msg);
+    let id = ConnId(conn_id as _);
+    let Some(group_id) = Jid::parse(&chat_id) else {
+        let err = WmError {
+            code: ErrorCode::InvalidJid,
+            message: format!("invalid group id {chat_id:?}"),
+        };
+        finish_group_info(id, &chat_id, &Err(err));
+        return;
+    };
+    let result = match ErrorCode::from_raw(err_code as _) {
+        Some(code) => Err(WmError {
+            code,
+            message: err_msg,
+        }),
+        None => GroupInfo::from_json(&info_json),
+    };
+    finish_group_info(id, &chat_id, &result);
+    if let Ok(info) = result {
+        sendm(
+            conn_id,
+            Event::ChatEvent(group_id, ChatEvent::GroupInfo(info)),
+        );
+    }
+}
+
+// Keep in sync with struct GroupChangeJson in go/gowm.go
+#[derive(serde::Deserialize)]
+struct RawGroupChange {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    users: Vec<String>,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    setting: String,
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    invite: bool,
+}
+
+/// Parses a change sent by Go, `None` for the ones this crate doesn't know.
+fn parse_group_change(json: &str) -> Option<GroupChange> {
+    serde_json::from_str::<RawGroupChange>(json)
+        .ok()
+        .and_then(RawGroupChange::into_change)
+}
+
+impl RawGroupChange {
+    fn into_change(self) -> Option<GroupChange> {
+        let users = || self.users.iter().filter_map(|u| Jid::parse(u)).collect();
+        Some(match self.kind.as_str() {
+            "NameChanged" => GroupChange::NameChanged(self.text),
+            "TopicChanged" => GroupChange::TopicChanged(self.text),
+            "ParticipantsAdded" => GroupChange::ParticipantsAdded {
+                users: users(),
+                by_invite_link: self.invite,
+            },
+            "ParticipantsRemoved" => GroupChange::ParticipantsRemoved(users()),
+            "Promoted" => GroupChange::Promoted(users()),
+            "Demoted" => GroupChange::Demoted(users()),
+            "SettingsChanged" => GroupChange::SettingsChanged(match self.setting.as_str() {
+                "announce" => GroupSetting::Announce(self.enabled),
+                "locked" => GroupSetting::Locked(self.enabled),
+                "joinApproval" => GroupSetting::JoinApproval(self.enabled),
+                _ => return None,
+            }),
+            "PhotoChanged" => GroupChange::PhotoChanged {
+                removed: !self.enabled,
+            },
+            "InviteLinkReset" => GroupChange::InviteLinkReset,
+            _ => return None,
+        })
+    }
+}
+
+#[no_mangle]
+extern "C" fn WmNewGroupChangeNotify(
+    conn_id: c_int,
+    chat_id: *const c_char,
+    actor_id: *const c_char,
+    time_sent: c_int,
+    change_json: *const c_char,
+) {
+    let actor = Jid::parse(&cstr(actor_id));
+    let Some(change) = parse_group_change(&cstr(change_json)) else {
+        // Still free it
+        cstr(chat_id);
+        return;
+    };
+    sendc(
+        conn_id,
+        chat_id,
+        ChatEvent::GroupChange {
+            actor,
+            time: time_sent as _,
+            change,
+        },
+    );
 }
 
 #[no_mangle]
@@ -301,86 +463,301 @@ extern "C" fn WmUpdateArchivedNotify(
 }
 
 #[no_mangle]
-extern "C" fn WmAppConfigGetNum(param: *const c_char) -> c_int {
-    println!("[HOOK] WmAppConfigGetNum param={}", cstr(param));
-    0 // placeholder
+extern "C" fn WmAppConfigGetNum(conn_id: c_int, param: *const c_char) -> c_int {
+    config::get_num(ConnId(conn_id as _), &cstr(param))
 }
 
 #[no_mangle]
-extern "C" fn WmAppConfigSetNum(param: *const c_char, value: c_int) {
-    println!(
-        "[HOOK] WmAppConfigSetNum param={} value={}",
-        cstr(param),
-        value
-    );
+extern "C" fn WmAppConfigSetNum(conn_id: c_int, param: *const c_char, value: c_int) {
+    let param = cstr(param);
+    if let Err(err) = config::set_num(ConnId(conn_id as _), &param, value) {
+        tracing::warn!(target: "whatsmeow", conn_id, "failed to save config param {param}: {err}");
+    }
 }
 
-const ENV: &str = "WHATSMEOW_LOG";
+/// Emits a `tracing` event for a Go log line, inside a
+/// `connection` span if the connection id is known.
+///
+/// The strings are freed either way.
+macro_rules! go_log {
+    ($level:expr, $conn_id:expr, $filename:expr, $line_no:expr, $message:expr) => {{
+        let (filename, message) = (cstr($filename), cstr($message));
+        if $conn_id >= 0 {
+            let span = tracing::span!(
+                target: "whatsmeow",
+                $level,
+                "connection",
+                conn_id = $conn_id
+            );
+            let _guard = span.enter();
+            tracing::event!(
+                target: "whatsmeow",
+                $level,
+                file = %filename,
+                line = $line_no,
+                "{message}"
+            );
+        } else {
+            tracing::event!(
+                target: "whatsmeow",
+                $level,
+                file = %filename,
+                line = $line_no,
+                "{message}"
+            );
+        }
+    }};
+}
 
+/// Lets Go skip formatting and passing log lines
+/// that no subscriber is interested in.
+///
+/// Keep in sync with the `LogLevel*` constants in `gowm.go`.
 #[no_mangle]
-extern "C" fn WmLogTrace(filename: *const c_char, line_no: c_int, message: *const c_char) {
-    if log_enabled() {
-        println!("TRACE {}:{} {}", cstr(filename), line_no, cstr(message));
-    }
+extern "C" fn WmLogLevelEnabled(level: c_int) -> c_int {
+    let enabled = match level {
+        0 => tracing::enabled!(target: "whatsmeow", Level::TRACE),
+        1 => tracing::enabled!(target: "whatsmeow", Level::DEBUG),
+        2 => tracing::enabled!(target: "whatsmeow", Level::INFO),
+        3 => tracing::enabled!(target: "whatsmeow", Level::WARN),
+        _ => tracing::enabled!(target: "whatsmeow", Level::ERROR),
+    };
+    enabled.into()
 }
 
 #[no_mangle]
-extern "C" fn WmLogDebug(filename: *const c_char, line_no: c_int, message: *const c_char) {
-    if log_enabled() {
-        println!("DEBUG {}:{} {}", cstr(filename), line_no, cstr(message));
-    }
+extern "C" fn WmLogTrace(
+    conn_id: c_int,
+    filename: *const c_char,
+    line_no: c_int,
+    message: *const c_char,
+) {
+    go_log!(Level::TRACE, conn_id, filename, line_no, message);
 }
 
-fn log_enabled() -> bool {
-    let v = std::env::var(ENV).ok();
-    let v = v.as_deref();
-    v == Some("1") || v == Some("true")
+#[no_mangle]
+extern "C" fn WmLogDebug(
+    conn_id: c_int,
+    filename: *const c_char,
+    line_no: c_int,
+    message: *const c_char,
+) {
+    go_log!(Level::DEBUG, conn_id, filename, line_no, message);
 }
 
 #[no_mangle]
-extern "C" fn WmLogInfo(filename: *const c_char, line_no: c_int, message: *const c_char) {
-    println!("INFO {}:{} {}", cstr(filename), line_no, cstr(message));
+extern "C" fn WmLogInfo(
+    conn_id: c_int,
+    filename: *const c_char,
+    line_no: c_int,
+    message: *const c_char,
+) {
+    go_log!(Level::INFO, conn_id, filename, line_no, message);
 }
 
 #[no_mangle]
-extern "C" fn WmLogWarning(filename: *const c_char, line_no: c_int, message: *const c_char) {
-    let message = cstr(message);
-    let filename = cstr(filename);
-    println!("WARN {filename}:{line_no} {message}");
-    WARNINGS.lock().unwrap().push(LogMsg {
-        filename,
-        line_no,
-        message,
-    });
+extern "C" fn WmLogWarning(
+    conn_id: c_int,
+    filename: *const c_char,
+    line_no: c_int,
+    message: *const c_char,
+) {
+    go_log!(Level::WARN, conn_id, filename, line_no, message);
 }
 
 #[no_mangle]
-extern "C" fn WmLogError(filename: *const c_char, line_no: c_int, message: *const c_char) {
-    println!("ERROR {}:{} {}", cstr(filename), line_no, cstr(message));
+extern "C" fn WmLogError(
+    conn_id: c_int,
+    filename: *const c_char,
+    line_no: c_int,
+    message: *const c_char,
+) {
+    go_log!(Level::ERROR, conn_id, filename, line_no, message);
 }
 
-static WARNINGS: LazyLock<Mutex<Vec<LogMsg>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+#[no_mangle]
+extern "C" fn WmExtQrCode(conn_id: c_int, qr_code: *const c_char) {
+    sendm(
+        conn_id,
+        Event::QrCode {
+            conn_id: ConnId(conn_id as _),
+            code: cstr(qr_code),
+        },
+    );
+}
 
-#[derive(Debug)]
-pub struct LogMsg {
-    pub filename: String,
-    pub line_no: c_int,
-    pub message: String,
+#[no_mangle]
+extern "C" fn WmExtQrCodeRefresh(conn_id: c_int, qr_code: *const c_char) {
+    sendm(
+        conn_id,
+        Event::QrCodeRefreshed {
+            conn_id: ConnId(conn_id as _),
+            code: cstr(qr_code),
+        },
+    );
 }
 
-pub fn get_error() -> Option<LogMsg> {
-    let Ok(mut lock) = WARNINGS.lock() else {
-        return None;
-    };
-    lock.pop()
+#[no_mangle]
+extern "C" fn WmExtQrCodeExpired(conn_id: c_int) {
+    sendm(
+        conn_id,
+        Event::QrCodeExpired {
+            conn_id: ConnId(conn_id as _),
+        },
+    );
 }
 
 #[no_mangle]
-extern "C" fn WmExtQrCode(path: *const c_char) {
-    sendm(0, Event::QrCode(cstr(path)));
+extern "C" fn WmExtLoginPairingCode(conn_id: c_int, code: *const c_char) {
+    sendm(
+        conn_id,
+        Event::PairingCode {
+            conn_id: ConnId(conn_id as _),
+            code: cstr(code),
+        },
+    );
+}
+
+#[no_mangle]
+extern "C" fn WmLoginProgressNotify(conn_id: c_int, progress: c_int) {
+    let progress = LoginProgress::from_raw(progress);
+    sendm(
+        conn_id,
+        Event::LoginProgress {
+            conn_id: ConnId(conn_id as _),
+            progress,
+        },
+    );
+    if progress.is_finished() {
+        finish_login(ConnId(conn_id as _), progress);
+    }
+}
+
+#[no_mangle]
+extern "C" fn WmLoginInstructions(conn_id: c_int, text: *const c_char) {
+    sendm(
+        conn_id,
+        Event::LoginInstructions {
+            conn_id: ConnId(conn_id as _),
+            text: cstr(text),
+        },
+    );
 }
 
 #[no_mangle]
-extern "C" fn WmExtLoginPairingCode(code: *const c_char) {
-    sendm(0, Event::PairingCode(cstr(code)));
+extern "C" fn WmFatalLoginError(conn_id: c_int, reason: *const c_char) {
+    sendm(
+        conn_id,
+        Event::FatalLoginError {
+            conn_id: ConnId(conn_id as _),
+            reason: cstr(reason),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jids(ids: &[&str]) -> Vec<Jid> {
+        ids.iter().map(|id| Jid::parse(id).unwrap()).collect()
+    }
+
+    #[test]
+    fn group_changes_from_go() {
+        // As `json.Marshal` writes them, leaving out empty fields
+        let cases = [
+            (
+                r#"{"type":"NameChanged","text":"Friends"}"#,
+                GroupChange::NameChanged("Friends".to_owned()),
+            ),
+            (
+                r#"{"type":"TopicChanged","text":"Weekend plans"}"#,
+                GroupChange::TopicChanged("Weekend plans".to_owned()),
+            ),
+            (
+                r#"{"type":"TopicChanged"}"#,
+                GroupChange::TopicChanged(String::new()),
+            ),
+            (
+                r#"{"type":"ParticipantsAdded","users":["6511111111@s.whatsapp.net"]}"#,
+                GroupChange::ParticipantsAdded {
+                    users: jids(&["6511111111@s.whatsapp.net"]),
+                    by_invite_link: false,
+                },
+            ),
+            (
+                r#"{"type":"ParticipantsAdded","users":["1234@lid","bad"],"invite":true}"#,
+                GroupChange::ParticipantsAdded {
+                    users: jids(&["1234@lid"]),
+                    by_invite_link: true,
+                },
+            ),
+            (
+                r#"{"type":"ParticipantsRemoved","users":["6511111111@s.whatsapp.net","1234@lid"]}"#,
+                GroupChange::ParticipantsRemoved(jids(&["6511111111@s.whatsapp.net", "1234@lid"])),
+            ),
+            (
+                r#"{"type":"Promoted","users":["1234@lid"]}"#,
+                GroupChange::Promoted(jids(&["1234@lid"])),
+            ),
+            (
+                r#"{"type":"Demoted","users":["6511111111@s.whatsapp.net"]}"#,
+                GroupChange::Demoted(jids(&["6511111111@s.whatsapp.net"])),
+            ),
+            (
+                r#"{"type":"SettingsChanged","setting":"announce","enabled":true}"#,
+                GroupChange::SettingsChanged(GroupSetting::Announce(true)),
+            ),
+            (
+                r#"{"type":"SettingsChanged","setting":"announce"}"#,
+                GroupChange::SettingsChanged(GroupSetting::Announce(false)),
+            ),
+            (
+                r#"{"type":"SettingsChanged","setting":"locked","enabled":true}"#,
+                GroupChange::SettingsChanged(GroupSetting::Locked(true)),
+            ),
+            (
+                r#"{"type":"SettingsChanged","setting":"locked"}"#,
+                GroupChange::SettingsChanged(GroupSetting::Locked(false)),
+            ),
+            (
+                r#"{"type":"SettingsChanged","setting":"joinApproval","enabled":true}"#,
+                GroupChange::SettingsChanged(GroupSetting::JoinApproval(true)),
+            ),
+            (
+                r#"{"type":"SettingsChanged","setting":"joinApproval"}"#,
+                GroupChange::SettingsChanged(GroupSetting::JoinApproval(false)),
+            ),
+            // `enabled` is whether there is a photo now
+            (
+                r#"{"type":"PhotoChanged","enabled":true}"#,
+                GroupChange::PhotoChanged { removed: false },
+            ),
+            (
+                r#"{"type":"PhotoChanged"}"#,
+                GroupChange::PhotoChanged { removed: true },
+            ),
+            (
+                r#"{"type":"InviteLinkReset"}"#,
+                GroupChange::InviteLinkReset,
+            ),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(parse_group_change(json), Some(expected), "{json}");
+        }
+    }
+
+    #[test]
+    fn unknown_group_changes_are_dropped() {
+        for json in [
+            r#"{"type":"Renamed","text":"Friends"}"#,
+            r#"{"type":"SettingsChanged","setting":"memberAddMode","enabled":true}"#,
+            r#"{"type":"SettingsChanged"}"#,
+            r#"{"text":"Friends"}"#,
+            "not json",
+        ] {
+            assert_eq!(parse_group_change(json), None, "{json}");
+        }
+    }
 }
@@ -8,33 +8,60 @@ pub use sys::*;
 
 /// # Safety
 /// `path` and `proxy` must be valid C Strings
-#[allow(clippy::result_unit_err)]
 pub unsafe fn create_connection(
     path: *mut ::std::os::raw::c_char,
     proxy: *mut ::std::os::raw::c_char,
     send_type: GoInt,
-) -> Result<(ConnId, Receiver<Event>), ()> {
-    let r = unsafe { CWmInit(path, proxy, send_type) };
-    if r == -1 {
-        Err(())
-    } else {
-        let id = ConnId(r as _);
-        let (sender, receiver) = mpsc::unbounded_channel();
-        add_sender(id, sender);
-        Ok((id, receiver))
-    }
+) -> Result<(ConnId, Receiver<Event>), WmError> {
+    let mut conn_id: ::std::os::raw::c_int = -1;
+    WmError::check(|err| unsafe { CWmInit(path, proxy, send_type, &mut conn_id, err) })?;
+    let id = ConnId(conn_id as _);
+    let (sender, receiver) = mpsc::unbounded_channel();
+    add_sender(id, sender);
+    Ok((id, receiver))
 }
 
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables headless mode, process-wide.
+///
+/// In headless mode the library never prints to stdout,
+/// see [`Event::LoginInstructions`] and [`Event::FatalLoginError`].
+pub fn set_headless(headless: bool) {
+    HEADLESS.store(headless, Ordering::Relaxed);
+    unsafe { CWmSetHeadless(headless.into()) };
+}
+
+#[must_use]
+pub fn is_headless() -> bool {
+    HEADLESS.load(Ordering::Relaxed)
+}
+
+/// Sets how much private data (JIDs, message bodies, media keys)
+/// is redacted from logs, process-wide.
+///
+/// Defaults to [`LogRedaction::StripContent`].
+pub fn set_log_redaction(level: LogRedaction) {
+    unsafe { CWmSetLogRedaction(level as _) };
+}
+
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::mpsc::{self, UnboundedReceiver as Receiver};
 
+mod config;
 mod events;
 mod handlers;
 mod types;
+mod vcard;
 
-pub use events::{ChatEvent, Event};
-pub use handlers::{get_error, LogMsg};
+pub use config::{config, persist_config, remove_config, set_config, Config, CONFIG_FILE_NAME};
+pub use events::{remove_sender, wait_group_info, wait_login, ChatEvent, Event};
 pub use types::{
-    ConnId, DownloadFileAction, DownloadFileStatus, Jid, JidServer, MsgId, StatusFlags,
+    ConnId, DownloadFileAction, DownloadFileStatus, ErrorCode, GroupChange, GroupInfo,
+    GroupSetting, Jid, JidServer, LogRedaction, LoginMode, LoginProgress, MessageContent, MsgId,
+    Participant, ParticipantRole, ParticipantStatus, PollOption, StatusFlags, TemplateButton,
+    WmError,
 };
+pub use vcard::{VCard, VCardPhone};
 
 use crate::events::add_sender;
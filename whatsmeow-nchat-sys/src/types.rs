@@ -1,5 +1,5 @@
 use std::{
-    ffi::{c_int, CString, NulError},
+    ffi::{c_char, c_int, CString, NulError},
     fmt::Display,
     str::FromStr,
     sync::Arc,
@@ -8,7 +8,7 @@ use std::{
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
-use crate::GoInt;
+use crate::{handlers::cstr, GoInt, VCard};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ConnId(pub(crate) isize);
@@ -217,3 +217,618 @@ impl DownloadFileStatus {
         }
     }
 }
+
+/// What a message is, with what's needed to show it.
+/// Captions and message bodies are in the `text` next to it.
+///
+/// Keep in sync with `ContentJson` in `gowm.go`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum MessageContent {
+    Text,
+    Image,
+    Video,
+    Audio {
+        /// Recorded as a voice note (push to talk).
+        #[serde(default)]
+        is_voice_note: bool,
+        #[serde(default)]
+        seconds: u32,
+    },
+    Document {
+        #[serde(default)]
+        file_name: String,
+    },
+    Sticker {
+        #[serde(default)]
+        animated: bool,
+    },
+    Location {
+        lat: f64,
+        lon: f64,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        address: String,
+        /// Shared live location, updated by later messages.
+        #[serde(default)]
+        is_live: bool,
+    },
+    /// One or more shared contacts.
+    Contact {
+        #[serde(default)]
+        display_name: String,
+        /// Cards that couldn't be parsed are left out.
+        #[serde(deserialize_with = "parseable_cards")]
+        cards: Vec<VCard>,
+    },
+    Poll {
+        /// The question.
+        name: String,
+        options: Vec<String>,
+        /// How many options a voter can pick, `0` for any number.
+        /// Results come with [`crate::ChatEvent::PollUpdate`].
+        #[serde(default)]
+        selectable_count: u32,
+    },
+    /// A business message with buttons,
+    /// the content text is in `text`.
+    Template {
+        #[serde(default)]
+        title: String,
+        #[serde(default)]
+        footer: String,
+        buttons: Vec<TemplateButton>,
+    },
+    /// An invite to join a group. The content text is
+    /// the caption, accept it with `CWmAcceptGroupInvite`.
+    GroupInvite {
+        #[serde(with = "jid_id")]
+        group_id: Jid,
+        #[serde(default)]
+        group_name: String,
+        code: String,
+        /// Unix time (seconds) the invite expires, `0` if unknown.
+        #[serde(default)]
+        expiration: isize,
+    },
+    /// A notice from WhatsApp itself. Only found in older
+    /// stored messages, group changes are now sent as
+    /// [`crate::ChatEvent::GroupChange`].
+    System,
+    /// A message type this library can't show yet,
+    /// `kind` is the WhatsApp name for it (eg. `Call`),
+    /// or content that couldn't be parsed, see [`MessageContent::from_json`].
+    Unsupported {
+        kind: String,
+    },
+}
+
+impl MessageContent {
+    /// Parses the content JSON passed by `CWmNewMessagesNotify`,
+    /// falling back to [`MessageContent::Unsupported`], with the
+    /// type of the content that failed (eg. a `GroupInvite` with
+    /// an invalid group id), or `Unknown`.
+    #[must_use]
+    pub fn from_json(json: &str) -> Self {
+        serde_json::from_str(json).unwrap_or_else(|_| {
+            let kind = serde_json::from_str::<serde_json::Value>(json)
+                .ok()
+                .and_then(|content| Some(content.get("type")?.as_str()?.to_owned()))
+                .unwrap_or_else(|| "Unknown".to_owned());
+            MessageContent::Unsupported { kind }
+        })
+    }
+}
+
+/// (De)serializes a [`Jid`] as its id (`user@server`),
+/// which is how the Go side writes them.
+mod jid_id {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::Jid;
+
+    pub fn serialize<S: Serializer>(jid: &Jid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&jid.to_id())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Jid, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        Jid::parse(&id).ok_or_else(|| D::Error::custom(format!("invalid jid {id}")))
+    }
+}
+
+/// Deserializes the vCard texts of a contact message, skipping the
+/// ones that aren't valid instead of failing the whole message.
+fn parseable_cards<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<VCard>, D::Error> {
+    let texts = Vec::<String>::deserialize(deserializer)?;
+    Ok(texts.iter().filter_map(|text| VCard::parse(text)).collect())
+}
+
+/// An option of a poll and who picked it,
+/// see [`crate::ChatEvent::PollUpdate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PollOption {
+    pub name: String,
+    /// Sorted by id.
+    pub voters: Vec<Jid>,
+}
+
+impl PollOption {
+    #[must_use]
+    pub fn votes(&self) -> usize {
+        self.voters.len()
+    }
+}
+
+/// Info and members of a group, see [`crate::ChatEvent::GroupInfo`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GroupInfo {
+    pub id: Jid,
+    pub name: String,
+    /// Empty if none.
+    pub description: String,
+    /// Who created the group, if known.
+    pub owner: Option<Jid>,
+    /// Unix time (seconds) the group was created.
+    pub created: isize,
+    /// May only list some of the members (eg. in a preview
+    /// of an invite link), see [`GroupInfo::size`].
+    pub participants: Vec<Participant>,
+    /// Number of members.
+    pub size: usize,
+    /// Only admins can send messages.
+    pub is_announce: bool,
+    /// Only admins can edit the name, description and photo.
+    pub is_locked: bool,
+    /// Only admins can add members.
+    pub admins_only_add: bool,
+    /// Admins approve users joining with an invite link.
+    pub join_approval: bool,
+    /// Disappearing messages timer in seconds, `0` if off.
+    pub ephemeral_timer: u32,
+    /// The group is a community, grouping other groups.
+    pub is_community: bool,
+    /// The community the group is part of, if any.
+    pub parent_id: Option<Jid>,
+}
+
+// Keep in sync with struct GroupInfoJson in go/gowm.go
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawGroupInfo {
+    id: String,
+    name: String,
+    description: String,
+    owner_id: String,
+    created: isize,
+    participants: Vec<RawParticipant>,
+    size: usize,
+    is_announce: bool,
+    is_locked: bool,
+    admins_only_add: bool,
+    join_approval: bool,
+    ephemeral_timer: u32,
+    is_community: bool,
+    parent_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawParticipant {
+    id: String,
+    name: String,
+    lid: String,
+    is_admin: bool,
+    is_super_admin: bool,
+}
+
+impl GroupInfo {
+    /// Parses the info JSON passed by `CWmNewGroupInfoNotify`
+    /// and returned by `CWmPreviewGroupInvite`.
+    ///
+    /// # Errors
+    /// If the JSON isn't valid group info.
+    pub fn from_json(json: &str) -> Result<Self, WmError> {
+        let invalid = |message: String| WmError {
+            code: ErrorCode::Unknown,
+            message,
+        };
+        let raw: RawGroupInfo =
+            serde_json::from_str(json).map_err(|err| invalid(format!("group info: {err}")))?;
+        let participants = raw
+            .participants
+            .into_iter()
+            .filter_map(|p| {
+                Some(Participant {
+                    jid: Jid::parse(&p.id)?,
+                    display_name: p.name,
+                    role: if p.is_super_admin {
+                        ParticipantRole::SuperAdmin
+                    } else if p.is_admin {
+                        ParticipantRole::Admin
+                    } else {
+                        ParticipantRole::Member
+                    },
+                    lid: Jid::parse(&p.lid),
+                })
+            })
+            .collect();
+        Ok(GroupInfo {
+            id: Jid::parse(&raw.id)
+                .ok_or_else(|| invalid(format!("group info: invalid id {}", raw.id)))?,
+            name: raw.name,
+            description: raw.description,
+            owner: Jid::parse(&raw.owner_id),
+            created: raw.created,
+            participants,
+            size: raw.size,
+            is_announce: raw.is_announce,
+            is_locked: raw.is_locked,
+            admins_only_add: raw.admins_only_add,
+            join_approval: raw.join_approval,
+            ephemeral_timer: raw.ephemeral_timer,
+            is_community: raw.is_community,
+            parent_id: Jid::parse(&raw.parent_id),
+        })
+    }
+}
+
+impl GroupInfo {
+    /// Finds a participant by their phone number or LID.
+    #[must_use]
+    pub fn participant(&self, user_id: &Jid) -> Option<&Participant> {
+        self.participants
+            .iter()
+            .find(|p| p.jid == *user_id || p.lid.as_ref() == Some(user_id))
+    }
+
+    /// `user_id` is an admin (or the super admin) of the group.
+    #[must_use]
+    pub fn is_admin(&self, user_id: &Jid) -> bool {
+        self.participant(user_id)
+            .is_some_and(|p| p.role != ParticipantRole::Member)
+    }
+}
+
+/// A member of a group, see [`GroupInfo`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Participant {
+    /// Their phone number, or LID if it's hidden.
+    pub jid: Jid,
+    /// Empty if unknown.
+    pub display_name: String,
+    pub role: ParticipantRole,
+    /// Their LID (hidden user id), if known.
+    pub lid: Option<Jid>,
+}
+
+impl Participant {
+    /// A regular member, with no known name or LID.
+    #[must_use]
+    pub fn new(jid: Jid) -> Self {
+        Self {
+            jid,
+            display_name: String::new(),
+            role: ParticipantRole::Member,
+            lid: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParticipantRole {
+    Member,
+    Admin,
+    /// The creator of the group, unless they left.
+    SuperAdmin,
+}
+
+/// A change to a group, see [`crate::ChatEvent::GroupChange`].
+///
+/// Disappearing messages changes are sent as
+/// [`crate::ChatEvent::EphemeralSettingChanged`] instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GroupChange {
+    NameChanged(String),
+    /// The new description, empty if it was removed.
+    TopicChanged(String),
+    /// Users joined or were added.
+    /// If they joined by themselves, the actor is one of them.
+    ParticipantsAdded {
+        users: Vec<Jid>,
+        /// Joined with an invite link.
+        by_invite_link: bool,
+    },
+    /// Users left or were removed.
+    /// If they left by themselves, the actor is one of them.
+    ParticipantsRemoved(Vec<Jid>),
+    /// Users were made admins.
+    Promoted(Vec<Jid>),
+    /// Admins were made regular members.
+    Demoted(Vec<Jid>),
+    SettingsChanged(GroupSetting),
+    PhotoChanged {
+        removed: bool,
+    },
+    /// The invite link was reset, the old one no longer works.
+    InviteLinkReset,
+}
+
+/// A group setting and its new value, see [`GroupChange::SettingsChanged`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GroupSetting {
+    /// Only admins can send messages.
+    Announce(bool),
+    /// Only admins can edit the name, description and photo.
+    Locked(bool),
+    /// Admins approve users joining with an invite link.
+    JoinApproval(bool),
+}
+
+/// Result of adding, removing, promoting or demoting
+/// one participant of a group.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", content = "code")]
+#[non_exhaustive]
+pub enum ParticipantStatus {
+    Ok,
+    NotOnWhatsApp,
+    /// Their privacy settings don't let you add them,
+    /// and sending them an invite failed.
+    PrivacyBlocked,
+    /// Their privacy settings don't let you add them,
+    /// so they were sent an invite to join instead.
+    InviteSent,
+    /// Any other error, with WhatsApp's code
+    /// (eg. `409` if they're already in the group).
+    Failed(u16),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum TemplateButton {
+    QuickReply { text: String, id: String },
+    Url { text: String, url: String },
+    Call { text: String, phone: String },
+}
+
+/// How much of potentially private data is kept in logs.
+///
+/// Keep in sync with the `Redact*` constants in `gowm.go`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LogRedaction {
+    /// Log everything, including message bodies and media keys.
+    Off = 0,
+    /// Replace the user part of JIDs and phone numbers with a short hash,
+    /// and only log the type of dumped events.
+    ///
+    /// The hash is keyed per process, so it is only stable within one run.
+    HashJids = 1,
+    /// Like [`LogRedaction::HashJids`], but also strip
+    /// message bodies, names, reactions and media keys.
+    #[default]
+    StripContent = 2,
+}
+
+/// How to link the device in a login started with `CWmLogin`.
+///
+/// Keep in sync with the `LoginMode*` constants in `gowm.go`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LoginMode {
+    /// Pairing code if enabled in the config
+    /// (or with the `USE_PAIRING_CODE` env var), otherwise Qr code.
+    #[default]
+    Default = 0,
+    Qr = 1,
+    PairingCode = 2,
+}
+
+/// Progress of a login started with `CWmLogin`.
+///
+/// Keep in sync with the `Login*` constants in `gowm.go`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LoginProgress {
+    /// Connecting to the WhatsApp servers.
+    Connecting,
+    /// A Qr code or pairing code is ready to be shown to the user.
+    QrAvailable,
+    /// The phone accepted the Qr code or pairing code.
+    PairSuccess,
+    Connected,
+    Timeout,
+    /// The WhatsApp client version is outdated.
+    Outdated,
+    Failed,
+    /// The login was cancelled with `CWmLoginCancel`.
+    Cancelled,
+    /// Pairing with the phone failed (eg. invalid phone number),
+    /// the login ends with [`LoginProgress::Failed`].
+    PairError,
+}
+
+impl LoginProgress {
+    #[must_use]
+    pub fn from_raw(n: c_int) -> Self {
+        match n {
+            0 => LoginProgress::Connecting,
+            1 => LoginProgress::QrAvailable,
+            2 => LoginProgress::PairSuccess,
+            3 => LoginProgress::Connected,
+            4 => LoginProgress::Timeout,
+            5 => LoginProgress::Outdated,
+            7 => LoginProgress::Cancelled,
+            8 => LoginProgress::PairError,
+            _ => LoginProgress::Failed,
+        }
+    }
+
+    /// Whether this is the last progress update of a login.
+    #[must_use]
+    pub fn is_finished(self) -> bool {
+        !matches!(
+            self,
+            LoginProgress::Connecting
+                | LoginProgress::QrAvailable
+                | LoginProgress::PairSuccess
+                | LoginProgress::PairError
+        )
+    }
+}
+
+/// Error code returned by every `CWm*` export.
+///
+/// Keep in sync with the `Err*` constants in `gowm.go`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    Unknown = 1,
+    ClientMissing = 2,
+    NotLoggedIn = 3,
+    InvalidJid = 4,
+    FileRead = 5,
+    UploadFailed = 6,
+    SendFailed = 7,
+    Timeout = 8,
+    Outdated = 9,
+    InvalidArgument = 10,
+    RequestFailed = 11,
+    NotSupported = 12,
+}
+
+impl ErrorCode {
+    /// Returns `None` for `0` (success).
+    #[must_use]
+    pub fn from_raw(n: GoInt) -> Option<Self> {
+        Some(match n {
+            0 => return None,
+            2 => ErrorCode::ClientMissing,
+            3 => ErrorCode::NotLoggedIn,
+            4 => ErrorCode::InvalidJid,
+            5 => ErrorCode::FileRead,
+            6 => ErrorCode::UploadFailed,
+            7 => ErrorCode::SendFailed,
+            8 => ErrorCode::Timeout,
+            9 => ErrorCode::Outdated,
+            10 => ErrorCode::InvalidArgument,
+            11 => ErrorCode::RequestFailed,
+            12 => ErrorCode::NotSupported,
+            _ => ErrorCode::Unknown,
+        })
+    }
+}
+
+/// An error returned by a single `CWm*` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WmError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl WmError {
+    /// Runs `f`, passing it the error message out-parameter
+    /// expected by the `CWm*` exports, and turns the returned
+    /// code into a `Result`.
+    pub fn check(f: impl FnOnce(*mut *mut c_char) -> GoInt) -> Result<(), WmError> {
+        let mut message: *mut c_char = std::ptr::null_mut();
+        let code = f(&mut message);
+        let message = cstr(message);
+        match ErrorCode::from_raw(code) {
+            None => Ok(()),
+            Some(code) => Err(WmError { code, message }),
+        }
+    }
+
+    /// Like [`WmError::check`], for `CWm*` exports that also
+    /// return a string through the out-parameter before it.
+    pub fn check_string(
+        f: impl FnOnce(*mut *mut c_char, *mut *mut c_char) -> GoInt,
+    ) -> Result<String, WmError> {
+        let mut out: *mut c_char = std::ptr::null_mut();
+        Self::check(|err| f(&mut out, err))?;
+        Ok(cstr(out))
+    }
+}
+
+impl Display for WmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for WmError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contact_skips_invalid_cards() {
+        let content = MessageContent::from_json(
+            r#"{"type":"Contact","display_name":"2 contacts",
+                "cards":["", "garbage", "BEGIN:VCARD\nFN:Jane\nEND:VCARD"]}"#,
+        );
+        assert_eq!(
+            content,
+            MessageContent::Contact {
+                display_name: "2 contacts".to_owned(),
+                cards: vec![VCard::new("Jane")],
+            }
+        );
+    }
+
+    #[test]
+    fn group_invite_with_invalid_group_keeps_kind() {
+        let content = MessageContent::from_json(
+            r#"{"type":"GroupInvite","group_id":"120363000000000000@g.us",
+                "group_name":"Friends","code":"AbCd","expiration":1700000000}"#,
+        );
+        assert_eq!(
+            content,
+            MessageContent::GroupInvite {
+                group_id: Jid::parse("120363000000000000@g.us").unwrap(),
+                group_name: "Friends".to_owned(),
+                code: "AbCd".to_owned(),
+                expiration: 1_700_000_000,
+            }
+        );
+
+        let content = MessageContent::from_json(
+            r#"{"type":"GroupInvite","group_id":"not a jid","code":"AbCd"}"#,
+        );
+        assert_eq!(
+            content,
+            MessageContent::Unsupported {
+                kind: "GroupInvite".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn unparseable_content_is_unknown() {
+        for json in ["", "garbage", "{}", r#"{"type":1}"#] {
+            assert_eq!(
+                MessageContent::from_json(json),
+                MessageContent::Unsupported {
+                    kind: "Unknown".to_owned()
+                },
+                "{json:?}"
+            );
+        }
+        assert_eq!(
+            MessageContent::from_json(r#"{"type":"Call"}"#),
+            MessageContent::Unsupported {
+                kind: "Call".to_owned()
+            }
+        );
+    }
+}
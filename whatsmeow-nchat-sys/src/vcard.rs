@@ -0,0 +1,427 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Jid;
+
+/// A contact card, as shared in WhatsApp contact messages
+/// and `.vcf` files.
+///
+/// Only the properties WhatsApp uses are parsed, the others
+/// are kept as-is so the card serializes back unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct VCard {
+    /// Name to show (`FN`), taken from `N` if missing.
+    pub full_name: String,
+    /// Company (`ORG`), empty if none.
+    pub organization: String,
+    pub phones: Vec<VCardPhone>,
+    pub emails: Vec<String>,
+    /// Other properties, as raw (unfolded) lines.
+    pub other: Vec<String>,
+}
+
+/// A phone number (`TEL`) of a [`VCard`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct VCardPhone {
+    /// As written by the sender, eg. `+65 1111 1111`.
+    pub number: String,
+    /// Uppercased types, eg. `CELL`.
+    pub kinds: Vec<String>,
+    /// Number of the WhatsApp account (`waid`), if the number has one.
+    pub wa_id: Option<String>,
+}
+
+impl VCardPhone {
+    /// The number of a WhatsApp user, linked to their account.
+    #[must_use]
+    pub fn whatsapp(user_id: &Jid) -> Self {
+        Self {
+            number: format!("+{}", user_id.number()),
+            kinds: vec!["CELL".to_owned()],
+            wa_id: Some(user_id.number().to_owned()),
+        }
+    }
+
+    /// The WhatsApp account of the number, if it has one.
+    #[must_use]
+    pub fn jid(&self) -> Option<Jid> {
+        self.wa_id
+            .as_deref()
+            .filter(|id| !id.is_empty())
+            .map(|id| Jid::from_phone_no(id.into()))
+    }
+}
+
+impl VCard {
+    #[must_use]
+    pub fn new(full_name: &str) -> Self {
+        Self {
+            full_name: full_name.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    /// Parses all cards in `input` (eg. a `.vcf` file),
+    /// ignoring lines outside of `BEGIN:VCARD`/`END:VCARD`
+    /// and lines that aren't properties.
+    #[must_use]
+    pub fn parse_all(input: &str) -> Vec<Self> {
+        let mut cards = Vec::new();
+        let mut current: Option<Self> = None;
+        for line in unfold(input) {
+            let Some((head, value)) = line.split_once(':') else {
+                continue;
+            };
+            let mut params = head.split(';');
+            let name = params.next().unwrap_or_default();
+            // Drop the group, eg. `item1.TEL`
+            let name = name.rsplit('.').next().unwrap_or(name).to_ascii_uppercase();
+
+            match (name.as_str(), &mut current) {
+                ("BEGIN", _) if value.trim().eq_ignore_ascii_case("VCARD") => {
+                    current = Some(Self::default());
+                }
+                ("END", Some(card)) if value.trim().eq_ignore_ascii_case("VCARD") => {
+                    if card.full_name.is_empty() {
+                        card.full_name = card.name_from_n();
+                    }
+                    // Drop the `N` we'd write anyway, so cards round-trip
+                    let default_n = format!("N:;{};;;", escape(&card.full_name));
+                    card.other.retain(|line| *line != default_n);
+                    cards.extend(current.take());
+                }
+                (_, None) | ("VERSION", _) => {}
+                ("FN", Some(card)) => card.full_name = unescape(value),
+                ("ORG", Some(card)) => {
+                    let units: Vec<String> = split_unescaped(value, ';')
+                        .into_iter()
+                        .filter(|unit| !unit.is_empty())
+                        .collect();
+                    card.organization = units.join(", ");
+                }
+                ("TEL", Some(card)) => {
+                    let mut phone = VCardPhone {
+                        number: unescape(value),
+                        ..VCardPhone::default()
+                    };
+                    for param in params {
+                        match param.split_once('=') {
+                            Some((key, v)) if key.eq_ignore_ascii_case("TYPE") => phone
+                                .kinds
+                                .extend(v.split(',').map(str::to_ascii_uppercase)),
+                            Some((key, v)) if key.eq_ignore_ascii_case("WAID") => {
+                                phone.wa_id = Some(v.to_owned());
+                            }
+                            Some(_) => {}
+                            // vCard 2.1 style, eg. `TEL;CELL:...`
+                            None => phone.kinds.push(param.to_ascii_uppercase()),
+                        }
+                    }
+                    card.phones.push(phone);
+                }
+                ("EMAIL", Some(card)) => card.emails.push(unescape(value)),
+                (_, Some(card)) => card.other.push(line.clone()),
+            }
+        }
+        cards
+    }
+
+    /// Parses the first card in `input`.
+    #[must_use]
+    pub fn parse(input: &str) -> Option<Self> {
+        Self::parse_all(input).into_iter().next()
+    }
+
+    /// Serializes `cards` as the contents of a `.vcf` file.
+    #[must_use]
+    pub fn to_vcf(cards: &[Self]) -> String {
+        cards.iter().map(ToString::to_string).collect()
+    }
+
+    /// Builds a name from the `N` property
+    /// (`Last;First;Middle;Prefix;Suffix`).
+    fn name_from_n(&self) -> String {
+        let Some(value) = self.other.iter().find_map(|line| {
+            let (head, value) = line.split_once(':')?;
+            let name = head.split(';').next()?;
+            name.rsplit('.')
+                .next()?
+                .eq_ignore_ascii_case("N")
+                .then_some(value)
+        }) else {
+            return String::new();
+        };
+        let parts = split_unescaped(value, ';');
+        let part = |i: usize| parts.get(i).map_or("", String::as_str);
+        [part(3), part(1), part(2), part(0), part(4)]
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn has_n(&self) -> bool {
+        self.other.iter().any(|line| {
+            line.split([';', ':'])
+                .next()
+                .and_then(|name| name.rsplit('.').next())
+                .is_some_and(|name| name.eq_ignore_ascii_case("N"))
+        })
+    }
+}
+
+/// Serializes as vCard 3.0, like WhatsApp does.
+impl Display for VCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "BEGIN:VCARD")?;
+        writeln!(f, "VERSION:3.0")?;
+        // Required by the spec, WhatsApp puts the whole name in it
+        if !self.has_n() {
+            writeln!(f, "N:;{};;;", escape(&self.full_name))?;
+        }
+        writeln!(f, "FN:{}", escape(&self.full_name))?;
+        if !self.organization.is_empty() {
+            writeln!(f, "ORG:{};", escape(&self.organization))?;
+        }
+        for phone in &self.phones {
+            write!(f, "TEL")?;
+            for kind in &phone.kinds {
+                write!(f, ";type={kind}")?;
+            }
+            if let Some(wa_id) = &phone.wa_id {
+                write!(f, ";waid={wa_id}")?;
+            }
+            writeln!(f, ":{}", escape(&phone.number))?;
+        }
+        for email in &self.emails {
+            writeln!(f, "EMAIL:{}", escape(email))?;
+        }
+        for line in &self.other {
+            writeln!(f, "{line}")?;
+        }
+        writeln!(f, "END:VCARD")
+    }
+}
+
+// Stored as the vCard text, which is what WhatsApp sends
+impl Serialize for VCard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for VCard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Self::parse(&text).ok_or_else(|| serde::de::Error::custom("no vCard found"))
+    }
+}
+
+/// Joins folded lines (continued by a leading space or tab).
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in input.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(value: &str) -> String {
+    split_unescaped(value, '\0').concat()
+}
+
+/// Splits `value` on unescaped `sep`, unescaping the parts.
+fn split_unescaped(value: &str, sep: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().expect("parts is never empty");
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => part.push('\n'),
+                Some(c) => part.push(c),
+                None => part.push('\\'),
+            },
+            c if c == sep => parts.push(String::new()),
+            c => part.push(c),
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phone(number: &str, kinds: &[&str], wa_id: Option<&str>) -> VCardPhone {
+        VCardPhone {
+            number: number.to_owned(),
+            kinds: kinds.iter().map(|&k| k.to_owned()).collect(),
+            wa_id: wa_id.map(str::to_owned),
+        }
+    }
+
+    fn round_trip(card: &VCard) -> VCard {
+        VCard::parse(&card.to_string()).expect("serialized card parses")
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut card = VCard::new("Jane \"JD\" Doe; Jr,\nthe 2nd \\o/");
+        card.organization = "Acme, Inc".to_owned();
+        card.phones.push(VCardPhone::whatsapp(&Jid::from_phone_no(
+            "6511111111".into(),
+        )));
+        card.phones
+            .push(phone("+65 2222 2222", &["HOME", "VOICE"], None));
+        card.emails.push("jane@example.com".to_owned());
+        card.other.push("BDAY:2000-01-01".to_owned());
+        assert_eq!(round_trip(&card), card);
+
+        let text = card.to_string();
+        assert!(text.starts_with(
+            "BEGIN:VCARD\nVERSION:3.0\nN:;Jane \"JD\" Doe\\; Jr\\,\\nthe 2nd \\\\o/;;;\n"
+        ));
+        assert!(text.contains("\nTEL;type=CELL;waid=6511111111:+6511111111\n"));
+        assert!(text.ends_with("BDAY:2000-01-01\nEND:VCARD\n"));
+        assert_eq!(round_trip(&VCard::default()), VCard::default());
+    }
+
+    #[test]
+    fn parses_whatsapp_card() {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\n\
+             VERSION:3.0\r\n\
+             N:;Jane Doe;;;\r\n\
+             FN:Jane Doe\r\n\
+             item1.TEL;waid=6511111111:+65 1111 1111\r\n\
+             item1.X-ABLabel:Mobile\r\n\
+             END:VCARD\r\n",
+        )
+        .unwrap();
+        assert_eq!(card.full_name, "Jane Doe");
+        assert_eq!(
+            card.phones,
+            [phone("+65 1111 1111", &[], Some("6511111111"))]
+        );
+        assert_eq!(
+            card.phones[0].jid(),
+            Some(Jid::from_phone_no("6511111111".into()))
+        );
+        // The default `N` is dropped, it's written back anyway
+        assert_eq!(card.other, ["item1.X-ABLabel:Mobile"]);
+        assert_eq!(round_trip(&card), card);
+    }
+
+    #[test]
+    fn parses_phone_types() {
+        let card = VCard::parse(
+            "BEGIN:VCARD\n\
+             FN:x\n\
+             TEL;TYPE=CELL,VOICE:1\n\
+             tel;type=work;type=fax:2\n\
+             TEL;CELL:3\n\
+             TEL;WAID=:4\n\
+             END:VCARD",
+        )
+        .unwrap();
+        assert_eq!(
+            card.phones,
+            [
+                phone("1", &["CELL", "VOICE"], None),
+                phone("2", &["WORK", "FAX"], None),
+                phone("3", &["CELL"], None),
+                phone("4", &[], Some("")),
+            ]
+        );
+        assert_eq!(card.phones[3].jid(), None);
+    }
+
+    #[test]
+    fn unfolds_lines() {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\n\
+             FN:Jane\r\n  Doe\r\n\
+             EMAIL:jane@exa\r\n\tmple.com\r\n\
+             NOTE:a long\r\n  note\r\n\
+             END:VCARD",
+        )
+        .unwrap();
+        assert_eq!(card.full_name, "Jane Doe");
+        assert_eq!(card.emails, ["jane@example.com"]);
+        assert_eq!(card.other, ["NOTE:a long note"]);
+    }
+
+    #[test]
+    fn name_from_n_without_fn() {
+        let card = VCard::parse("BEGIN:VCARD\nN:Doe;Jane;Q;Dr.;PhD\nEND:VCARD").unwrap();
+        assert_eq!(card.full_name, "Dr. Jane Q Doe PhD");
+        assert_eq!(card.other, ["N:Doe;Jane;Q;Dr.;PhD"]);
+        // The original `N` is written instead of the default one
+        assert!(!card.to_string().contains("N:;"));
+        assert_eq!(round_trip(&card), card);
+
+        let card = VCard::parse("BEGIN:VCARD\nN:D\\;oe;Jane\nEND:VCARD").unwrap();
+        assert_eq!(card.full_name, "Jane D;oe");
+        let card = VCard::parse("BEGIN:VCARD\nTEL:1\nEND:VCARD").unwrap();
+        assert_eq!(card.full_name, "");
+    }
+
+    #[test]
+    fn parses_multiple_cards() {
+        let vcf = "junk before\n\
+                   BEGIN:VCARD\nFN:One\nTEL:1\nEND:VCARD\n\
+                   between\n\
+                   begin:vcard\nFN:Two\nEMAIL:two@example.com\nend:vcard\n";
+        let cards = VCard::parse_all(vcf);
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].full_name, "One");
+        assert_eq!(cards[1].full_name, "Two");
+        assert_eq!(VCard::parse(vcf), Some(cards[0].clone()));
+        assert_eq!(VCard::parse_all(&VCard::to_vcf(&cards)), cards);
+    }
+
+    #[test]
+    fn garbage_is_none() {
+        for input in [
+            "",
+            "hello",
+            "FN:Jane Doe",
+            "BEGIN:VCARD\nFN:Jane Doe",
+            "END:VCARD",
+            "BEGIN:VCALENDAR\nEND:VCALENDAR",
+        ] {
+            assert_eq!(VCard::parse(input), None, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn serde_uses_vcard_text() {
+        let mut card = VCard::new("Jane");
+        card.phones.push(phone("1", &["CELL"], None));
+        let json = serde_json::to_string(&card).unwrap();
+        assert_eq!(serde_json::from_str::<VCard>(&json).unwrap(), card);
+        assert!(serde_json::from_str::<VCard>("\"garbage\"").is_err());
+    }
+}
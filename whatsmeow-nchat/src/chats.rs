@@ -0,0 +1,970 @@
+//! Current state of chats, contacts and presence,
+//! folded from the event stream.
+//!
+//! Every app needs the same chat list, so instead of matching on
+//! every [`ChatEvent`] yourself, feed events to [`ChatStore::apply`]
+//! and re-render whatever the returned [`Change`]s touch.
+//!
+//! ```no_run
+//! # async fn run() -> whatsmeow_nchat::Result<()> {
+//! use whatsmeow_nchat::chats::ChatStore;
+//!
+//! let (_conn, mut events) = whatsmeow_nchat::create_connection("./profile", "", 0)?;
+//! let mut chats = ChatStore::new();
+//! while let Some(event) = events.recv().await {
+//!     if !chats.apply(&event).is_empty() {
+//!         for chat in chats.chats() {
+//!             println!("{} ({})", chats.display_name(&chat.id), chat.unread_count());
+//!         }
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use whatsmeow_nchat_sys::JidServer;
+
+use crate::{
+    ChatEvent, Event, GroupChange, GroupInfo, GroupSetting, Jid, MessageContent, MsgId,
+    Participant, ParticipantRole, VCard, VCardPhone,
+};
+
+/// A contact or group, from [`ChatEvent::NewContactsNotify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Contact {
+    pub id: Jid,
+    pub name: String,
+    pub phone: String,
+    /// This is the logged in account.
+    pub is_self: bool,
+}
+
+impl Contact {
+    /// A contact card with the name and WhatsApp number,
+    /// or `None` for groups and users without a known number.
+    #[must_use]
+    pub fn to_vcard(&self) -> Option<VCard> {
+        let phone = match self.id.server() {
+            JidServer::DefaultUser | JidServer::LegacyUser => VCardPhone::whatsapp(&self.id),
+            _ if !self.phone.is_empty() => {
+                VCardPhone::whatsapp(&Jid::from_phone_no(self.phone.as_str().into()))
+            }
+            _ => return None,
+        };
+        let name = if self.name.is_empty() {
+            &phone.number
+        } else {
+            &self.name
+        };
+        let mut card = VCard::new(name);
+        card.phones.push(phone);
+        Some(card)
+    }
+}
+
+/// Online status of a user, from [`Event::NewStatusNotify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Presence {
+    pub is_online: bool,
+    /// Unix time (seconds) the user was last seen, `0` if unknown.
+    pub time_seen: isize,
+}
+
+/// The latest message of a chat, for the chat list.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct MessagePreview {
+    pub msg_id: MsgId,
+    pub sender_id: Jid,
+    pub text: String,
+    pub content: MessageContent,
+    pub from_me: bool,
+    pub has_file: bool,
+    pub time_sent: isize,
+}
+
+/// A chat in the chat list.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Chat {
+    pub id: Jid,
+    /// Name of the group from the history sync, or empty,
+    /// see [`ChatStore::display_name`].
+    pub name: String,
+    /// Marked as unread, on this or another device.
+    pub is_marked_unread: bool,
+    pub is_muted: bool,
+    /// Unix time (seconds) the chat is muted until,
+    /// `-1` if forever or `0` if not muted.
+    pub muted_until: isize,
+    pub is_archived: bool,
+    pub is_pinned: bool,
+    /// Unix time (seconds) the chat was pinned, `0` if unknown.
+    pub time_pinned: isize,
+    /// Unix time (seconds) of the last activity in the chat.
+    pub last_message_time: isize,
+    /// Disappearing messages timer in seconds, `0` if off.
+    pub ephemeral_timer: u32,
+    /// `None` until a message is received, or after
+    /// the last message was deleted.
+    pub last_message: Option<MessagePreview>,
+    /// Users currently typing in the chat.
+    pub typing: HashSet<Jid>,
+    /// Info and members of a group, from [`ChatEvent::GroupInfo`].
+    /// `None` until it's fetched, or for other chats.
+    pub group_info: Option<GroupInfo>,
+    /// Unread count from the history sync.
+    synced_unread: usize,
+    /// Incoming messages not read yet, since the history sync.
+    unread: HashSet<MsgId>,
+}
+
+impl Chat {
+    fn new(id: Jid) -> Self {
+        Self {
+            id,
+            name: String::new(),
+            is_marked_unread: false,
+            is_muted: false,
+            muted_until: 0,
+            is_archived: false,
+            is_pinned: false,
+            time_pinned: 0,
+            last_message_time: 0,
+            ephemeral_timer: 0,
+            last_message: None,
+            typing: HashSet::new(),
+            group_info: None,
+            synced_unread: 0,
+            unread: HashSet::new(),
+        }
+    }
+
+    /// Number of incoming messages not read yet,
+    /// or `1` if the chat was only marked as unread.
+    #[must_use]
+    pub fn unread_count(&self) -> usize {
+        match self.synced_unread + self.unread.len() {
+            0 => self.is_marked_unread.into(),
+            n => n,
+        }
+    }
+
+    #[must_use]
+    pub fn is_group(&self) -> bool {
+        self.id.server() == JidServer::Group
+    }
+}
+
+/// What changed after applying an event,
+/// so UIs only re-render that part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Change {
+    ChatAdded(Jid),
+    /// Flags, unread count or last message of the chat changed,
+    /// which may also change its place in [`ChatStore::chats`].
+    ChatUpdated(Jid),
+    ChatRemoved(Jid),
+    /// Name of a contact or group changed,
+    /// see [`ChatStore::display_name`].
+    ContactUpdated(Jid),
+    TypingChanged {
+        chat_id: Jid,
+        user_id: Jid,
+        is_typing: bool,
+    },
+    PresenceChanged(Jid),
+}
+
+/// Chats, contacts and presence of one connection,
+/// kept up to date with [`ChatStore::apply`].
+#[derive(Debug, Clone, Default)]
+pub struct ChatStore {
+    chats: HashMap<Jid, Chat>,
+    contacts: HashMap<Jid, Contact>,
+    presence: HashMap<Jid, Presence>,
+}
+
+impl ChatStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state with `event`, returning what changed
+    /// (empty if the event isn't relevant or changed nothing).
+    pub fn apply(&mut self, event: &Event) -> Vec<Change> {
+        match event {
+            Event::ChatEvent(chat_id, event) => self.apply_chat_event(chat_id, event),
+            Event::NewStatusNotify {
+                user_id,
+                is_online,
+                time_seen,
+            } => {
+                let presence = Presence {
+                    is_online: *is_online,
+                    time_seen: *time_seen,
+                };
+                if self.presence.insert(user_id.clone(), presence) == Some(presence) {
+                    Vec::new()
+                } else {
+                    vec![Change::PresenceChanged(user_id.clone())]
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn apply_chat_event(&mut self, chat_id: &Jid, event: &ChatEvent) -> Vec<Change> {
+        let mut changes = Vec::new();
+        match event {
+            ChatEvent::NewContactsNotify {
+                name,
+                phone,
+                is_self,
+                ..
+            } => {
+                let contact = Contact {
+                    id: chat_id.clone(),
+                    name: name.clone(),
+                    phone: phone.clone(),
+                    is_self: *is_self,
+                };
+                if self.contacts.get(chat_id) != Some(&contact) {
+                    self.contacts.insert(chat_id.clone(), contact);
+                    changes.push(Change::ContactUpdated(chat_id.clone()));
+                }
+            }
+            ChatEvent::NewChatsNotify {
+                name,
+                is_unread,
+                unread_count,
+                is_muted,
+                muted_until,
+                is_pinned,
+                is_archived,
+                ephemeral_timer,
+                last_message_time,
+            } => {
+                self.update_chat(chat_id, &mut changes, |chat| {
+                    if !name.is_empty() {
+                        chat.name.clone_from(name);
+                    }
+                    chat.is_marked_unread = *is_unread && *unread_count == 0;
+                    // The synced messages came just before,
+                    // the count from the server replaces theirs
+                    chat.synced_unread = *unread_count;
+                    chat.unread.clear();
+                    chat.is_muted = *is_muted;
+                    chat.muted_until = *muted_until;
+                    chat.is_pinned = *is_pinned;
+                    chat.is_archived = *is_archived;
+                    chat.ephemeral_timer = *ephemeral_timer;
+                    chat.last_message_time = chat.last_message_time.max(*last_message_time);
+                });
+            }
+            ChatEvent::NewMessagesNotify {
+                msg_id,
+                sender_id,
+                text,
+                content,
+                from_me,
+                file_id_path,
+                time_sent,
+                is_read,
+                is_edited,
+                ..
+            } => {
+                let preview = MessagePreview {
+                    msg_id: msg_id.clone(),
+                    sender_id: sender_id.clone(),
+                    text: text.clone(),
+                    content: content.clone(),
+                    from_me: *from_me,
+                    has_file: file_id_path.is_some(),
+                    time_sent: *time_sent,
+                };
+                if *is_edited {
+                    // Edits keep the time of the original message,
+                    // and can't be shown without it
+                    self.update_existing_chat(chat_id, &mut changes, |chat| {
+                        if let Some(last) = &mut chat.last_message {
+                            if last.msg_id == *msg_id {
+                                last.text = preview.text;
+                                last.content = preview.content;
+                            }
+                        }
+                    });
+                    return changes;
+                }
+                let mut stopped_typing = false;
+                self.update_chat(chat_id, &mut changes, |chat| {
+                    if !from_me && !is_read {
+                        chat.unread.insert(msg_id.clone());
+                    }
+                    if chat
+                        .last_message
+                        .as_ref()
+                        .is_none_or(|last| last.time_sent <= *time_sent)
+                    {
+                        chat.last_message = Some(preview);
+                    }
+                    chat.last_message_time = chat.last_message_time.max(*time_sent);
+                    stopped_typing = chat.typing.remove(sender_id);
+                });
+                if stopped_typing {
+                    changes.push(Change::TypingChanged {
+                        chat_id: chat_id.clone(),
+                        user_id: sender_id.clone(),
+                        is_typing: false,
+                    });
+                }
+            }
+            ChatEvent::NewMessageStatusNotify { msg_id, is_read } => {
+                if *is_read {
+                    self.update_existing_chat(chat_id, &mut changes, |chat| {
+                        // Messages are read in order, so the
+                        // synced ones were read before this one
+                        if chat.unread.remove(msg_id) {
+                            chat.synced_unread = 0;
+                        }
+                    });
+                }
+            }
+            ChatEvent::DeleteMessageNotify(msg_id) => {
+                self.update_existing_chat(chat_id, &mut changes, |chat| {
+                    chat.unread.remove(msg_id);
+                    if chat
+                        .last_message
+                        .as_ref()
+                        .is_some_and(|last| last.msg_id == *msg_id)
+                    {
+                        chat.last_message = None;
+                    }
+                });
+            }
+            ChatEvent::NewTypingNotify { user_id, is_typing } => {
+                let chat = self
+                    .chats
+                    .entry(chat_id.clone())
+                    .or_insert_with(|| Chat::new(chat_id.clone()));
+                let changed = if *is_typing {
+                    chat.typing.insert(user_id.clone())
+                } else {
+                    chat.typing.remove(user_id)
+                };
+                if changed {
+                    changes.push(Change::TypingChanged {
+                        chat_id: chat_id.clone(),
+                        user_id: user_id.clone(),
+                        is_typing: *is_typing,
+                    });
+                }
+            }
+            ChatEvent::UpdateIsMuted(is_muted) => {
+                self.update_chat(chat_id, &mut changes, |chat| {
+                    chat.is_muted = *is_muted;
+                    if !is_muted {
+                        chat.muted_until = 0;
+                    }
+                });
+            }
+            ChatEvent::UpdateIsArchived(is_archived) => {
+                self.update_chat(chat_id, &mut changes, |chat| {
+                    chat.is_archived = *is_archived;
+                });
+            }
+            ChatEvent::UpdatePinNotify {
+                is_pinned,
+                time_pinned,
+            } => {
+                self.update_chat(chat_id, &mut changes, |chat| {
+                    chat.is_pinned = *is_pinned;
+                    chat.time_pinned = *time_pinned;
+                });
+            }
+            ChatEvent::EphemeralSettingChanged { timer } => {
+                self.update_chat(chat_id, &mut changes, |chat| {
+                    chat.ephemeral_timer = *timer;
+                });
+            }
+            ChatEvent::GroupInfo(info) => {
+                self.update_chat(chat_id, &mut changes, |chat| {
+                    if !info.name.is_empty() {
+                        chat.name.clone_from(&info.name);
+                    }
+                    chat.ephemeral_timer = info.ephemeral_timer;
+                    chat.group_info = Some(info.clone());
+                });
+            }
+            ChatEvent::GroupChange { time, change, .. } => {
+                self.update_chat(chat_id, &mut changes, |chat| {
+                    chat.last_message_time = chat.last_message_time.max(*time);
+                    if let GroupChange::NameChanged(name) = change {
+                        chat.name.clone_from(name);
+                    }
+                    if let Some(info) = &mut chat.group_info {
+                        apply_group_change(info, change);
+                    }
+                });
+            }
+            ChatEvent::DeleteChatNotify => {
+                if self.chats.remove(chat_id).is_some() {
+                    changes.push(Change::ChatRemoved(chat_id.clone()));
+                }
+            }
+            ChatEvent::NewMessageFileNotify { .. }
+            | ChatEvent::NewMessageReactionNotify { .. }
+            | ChatEvent::PollUpdate { .. } => {}
+        }
+        changes
+    }
+
+    /// Applies `f` to the chat, adding it if it's new,
+    /// and records the change if there was one.
+    fn update_chat(&mut self, chat_id: &Jid, changes: &mut Vec<Change>, f: impl FnOnce(&mut Chat)) {
+        match self.chats.get_mut(chat_id) {
+            Some(chat) => {
+                let before = chat.clone();
+                f(chat);
+                if *chat != before {
+                    changes.push(Change::ChatUpdated(chat_id.clone()));
+                }
+            }
+            None => {
+                let mut chat = Chat::new(chat_id.clone());
+                f(&mut chat);
+                self.chats.insert(chat_id.clone(), chat);
+                changes.push(Change::ChatAdded(chat_id.clone()));
+            }
+        }
+    }
+
+    /// Like [`ChatStore::update_chat`], but ignores unknown chats.
+    fn update_existing_chat(
+        &mut self,
+        chat_id: &Jid,
+        changes: &mut Vec<Change>,
+        f: impl FnOnce(&mut Chat),
+    ) {
+        if self.chats.contains_key(chat_id) {
+            self.update_chat(chat_id, changes, f);
+        }
+    }
+
+    /// Unarchived chats, pinned first (most recently pinned first),
+    /// then by last activity, newest first.
+    #[must_use]
+    pub fn chats(&self) -> Vec<&Chat> {
+        self.sorted(|chat| !chat.is_archived)
+    }
+
+    /// Archived chats, by last activity, newest first.
+    #[must_use]
+    pub fn archived_chats(&self) -> Vec<&Chat> {
+        self.sorted(|chat| chat.is_archived)
+    }
+
+    fn sorted(&self, filter: impl Fn(&Chat) -> bool) -> Vec<&Chat> {
+        let mut chats: Vec<&Chat> = self.chats.values().filter(|c| filter(c)).collect();
+        chats.sort_by(|a, b| {
+            b.is_pinned
+                .cmp(&a.is_pinned)
+                .then_with(|| {
+                    if a.is_pinned {
+                        b.time_pinned.cmp(&a.time_pinned)
+                    } else {
+                        std::cmp::Ordering::Equal
+                    }
+                })
+                .then_with(|| b.last_message_time.cmp(&a.last_message_time))
+                // Keep the order stable between calls
+                .then_with(|| a.id.to_id().cmp(&b.id.to_id()))
+        });
+        chats
+    }
+
+    #[must_use]
+    pub fn chat(&self, chat_id: &Jid) -> Option<&Chat> {
+        self.chats.get(chat_id)
+    }
+
+    #[must_use]
+    pub fn contact(&self, id: &Jid) -> Option<&Contact> {
+        self.contacts.get(id)
+    }
+
+    /// All known users with a phone number as a `.vcf` file
+    /// (eg. to import into an address book), sorted by name.
+    /// See [`Contact::to_vcard`].
+    #[must_use]
+    pub fn export_vcf(&self) -> String {
+        let mut cards: Vec<VCard> = self
+            .contacts
+            .values()
+            .filter(|contact| !contact.is_self)
+            .filter_map(Contact::to_vcard)
+            .collect();
+        cards.sort_by(|a, b| a.full_name.cmp(&b.full_name));
+        VCard::to_vcf(&cards)
+    }
+
+    /// The contact marked as the logged in account, if known.
+    #[must_use]
+    pub fn self_contact(&self) -> Option<&Contact> {
+        self.contacts.values().find(|c| c.is_self)
+    }
+
+    /// Total unread count of unmuted chats, eg. for a badge.
+    #[must_use]
+    pub fn total_unread(&self) -> usize {
+        self.chats
+            .values()
+            .filter(|chat| !chat.is_muted)
+            .map(Chat::unread_count)
+            .sum()
+    }
+
+    /// Name to show for a user or group: the contact name,
+    /// else the chat name from the history sync, else the name from
+    /// a group member list, else the phone number.
+    #[must_use]
+    pub fn display_name(&self, id: &Jid) -> String {
+        if let Some(contact) = self.contacts.get(id) {
+            if !contact.name.is_empty() {
+                return contact.name.clone();
+            }
+        }
+        if let Some(chat) = self.chats.get(id).filter(|chat| !chat.name.is_empty()) {
+            return chat.name.clone();
+        }
+        if let Some(name) = self
+            .chats
+            .values()
+            .filter_map(|chat| chat.group_info.as_ref()?.participant(id))
+            .find_map(|p| (!p.display_name.is_empty()).then_some(&p.display_name))
+        {
+            return name.clone();
+        }
+        match self.contacts.get(id) {
+            Some(contact) if !contact.phone.is_empty() => contact.phone.clone(),
+            _ => id.number().to_owned(),
+        }
+    }
+
+    /// Users currently typing in `chat_id`, sorted by id.
+    #[must_use]
+    pub fn typing_users(&self, chat_id: &Jid) -> Vec<&Jid> {
+        let mut users: Vec<&Jid> = self
+            .chats
+            .get(chat_id)
+            .map(|chat| chat.typing.iter().collect())
+            .unwrap_or_default();
+        users.sort_by_key(|user| user.to_id());
+        users
+    }
+
+    /// Online status of `user_id`, if it was fetched
+    /// (see [`crate::Connection::fetch_status`]).
+    #[must_use]
+    pub fn presence(&self, user_id: &Jid) -> Option<Presence> {
+        self.presence.get(user_id).copied()
+    }
+}
+
+/// Keeps fetched group info up to date, until it's fetched again.
+fn apply_group_change(info: &mut GroupInfo, change: &GroupChange) {
+    let set_role = |info: &mut GroupInfo, users: &[Jid], role: ParticipantRole| {
+        for p in &mut info.participants {
+            if users.contains(&p.jid) || p.lid.as_ref().is_some_and(|lid| users.contains(lid)) {
+                p.role = role;
+            }
+        }
+    };
+    match change {
+        GroupChange::NameChanged(name) => info.name.clone_from(name),
+        GroupChange::TopicChanged(topic) => info.description.clone_from(topic),
+        GroupChange::ParticipantsAdded { users, .. } => {
+            for user in users {
+                if info.participant(user).is_none() {
+                    info.participants.push(Participant::new(user.clone()));
+                    info.size += 1;
+                }
+            }
+        }
+        GroupChange::ParticipantsRemoved(users) => {
+            let before = info.participants.len();
+            info.participants.retain(|p| {
+                !users.contains(&p.jid) && !p.lid.as_ref().is_some_and(|lid| users.contains(lid))
+            });
+            info.size = info.size.saturating_sub(before - info.participants.len());
+        }
+        GroupChange::Promoted(users) => set_role(info, users, ParticipantRole::Admin),
+        GroupChange::Demoted(users) => set_role(info, users, ParticipantRole::Member),
+        GroupChange::SettingsChanged(GroupSetting::Announce(on)) => info.is_announce = *on,
+        GroupChange::SettingsChanged(GroupSetting::Locked(on)) => info.is_locked = *on,
+        GroupChange::SettingsChanged(GroupSetting::JoinApproval(on)) => info.join_approval = *on,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{edit, jid, new_message, user};
+
+    fn on_chat(chat_id: &Jid, event: ChatEvent) -> Event {
+        Event::ChatEvent(chat_id.clone(), event)
+    }
+
+    fn synced_chat(is_pinned: bool, last_message_time: isize) -> ChatEvent {
+        ChatEvent::NewChatsNotify {
+            name: String::new(),
+            is_unread: false,
+            unread_count: 0,
+            is_muted: false,
+            muted_until: 0,
+            is_pinned,
+            is_archived: false,
+            ephemeral_timer: 0,
+            last_message_time,
+        }
+    }
+
+    fn read(msg_id: &str) -> ChatEvent {
+        ChatEvent::NewMessageStatusNotify {
+            msg_id: MsgId(msg_id.to_owned()),
+            is_read: true,
+        }
+    }
+
+    fn last_message(store: &ChatStore) -> Option<(&str, &str, isize)> {
+        let last = store.chat(&user())?.last_message.as_ref()?;
+        Some((last.msg_id.0.as_str(), last.text.as_str(), last.time_sent))
+    }
+
+    #[test]
+    fn read_receipt_clears_unread() {
+        let mut store = ChatStore::new();
+        let id = user();
+        store.apply(&on_chat(&id, new_message("A", 100, "a")));
+        store.apply(&on_chat(&id, new_message("B", 200, "b")));
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 2);
+
+        assert_eq!(store.apply(&on_chat(&id, read("unknown"))), []);
+        assert_eq!(
+            store.apply(&on_chat(&id, read("A"))),
+            [Change::ChatUpdated(id.clone())]
+        );
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 1);
+        assert_eq!(store.total_unread(), 1);
+
+        // Unknown chats aren't added by receipts
+        let other = jid("6522222222@s.whatsapp.net");
+        assert_eq!(store.apply(&on_chat(&other, read("A"))), []);
+        assert!(store.chat(&other).is_none());
+    }
+
+    #[test]
+    fn read_receipt_resets_synced_unread() {
+        let mut store = ChatStore::new();
+        let id = user();
+        let mut event = synced_chat(false, 100);
+        if let ChatEvent::NewChatsNotify {
+            is_unread,
+            unread_count,
+            ..
+        } = &mut event
+        {
+            *is_unread = true;
+            *unread_count = 3;
+        }
+        store.apply(&on_chat(&id, event));
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 3);
+
+        store.apply(&on_chat(&id, new_message("A", 200, "a")));
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 4);
+
+        // Not one of the unread messages
+        assert_eq!(store.apply(&on_chat(&id, read("old"))), []);
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 4);
+
+        assert_eq!(
+            store.apply(&on_chat(&id, read("A"))),
+            [Change::ChatUpdated(id.clone())]
+        );
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 0);
+    }
+
+    #[test]
+    fn marked_unread_counts_as_one() {
+        let mut store = ChatStore::new();
+        let id = user();
+        let mut event = synced_chat(false, 100);
+        if let ChatEvent::NewChatsNotify { is_unread, .. } = &mut event {
+            *is_unread = true;
+        }
+        assert_eq!(
+            store.apply(&on_chat(&id, event)),
+            [Change::ChatAdded(id.clone())]
+        );
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 1);
+    }
+
+    #[test]
+    fn edits_keep_their_position() {
+        let mut store = ChatStore::new();
+        let id = user();
+        store.apply(&on_chat(&id, new_message("A", 100, "a")));
+        store.apply(&on_chat(&id, new_message("B", 200, "b")));
+
+        // Not the last message, nothing to show
+        assert_eq!(store.apply(&on_chat(&id, edit("A", 300, "a2"))), []);
+        assert_eq!(last_message(&store), Some(("B", "b", 200)));
+
+        assert_eq!(
+            store.apply(&on_chat(&id, edit("B", 300, "b2"))),
+            [Change::ChatUpdated(id.clone())]
+        );
+        assert_eq!(last_message(&store), Some(("B", "b2", 200)));
+        let chat = store.chat(&id).unwrap();
+        assert_eq!(chat.last_message_time, 200);
+        // Edits aren't new unread messages
+        assert_eq!(chat.unread_count(), 2);
+    }
+
+    #[test]
+    fn edit_in_unknown_chat_is_ignored() {
+        let mut store = ChatStore::new();
+        assert_eq!(store.apply(&on_chat(&user(), edit("A", 100, "a"))), []);
+        assert!(store.chat(&user()).is_none());
+    }
+
+    #[test]
+    fn last_message_only_advances() {
+        let mut store = ChatStore::new();
+        let id = user();
+        assert_eq!(
+            store.apply(&on_chat(&id, new_message("B", 200, "b"))),
+            [Change::ChatAdded(id.clone())]
+        );
+        assert_eq!(last_message(&store), Some(("B", "b", 200)));
+
+        // Arrived late, still counts as unread
+        assert_eq!(
+            store.apply(&on_chat(&id, new_message("A", 100, "a"))),
+            [Change::ChatUpdated(id.clone())]
+        );
+        assert_eq!(last_message(&store), Some(("B", "b", 200)));
+        assert_eq!(store.chat(&id).unwrap().last_message_time, 200);
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 2);
+
+        store.apply(&on_chat(&id, new_message("C", 200, "c")));
+        assert_eq!(last_message(&store), Some(("C", "c", 200)));
+
+        store.apply(&on_chat(
+            &id,
+            ChatEvent::DeleteMessageNotify(MsgId("C".to_owned())),
+        ));
+        assert_eq!(last_message(&store), None);
+        assert_eq!(store.chat(&id).unwrap().unread_count(), 2);
+    }
+
+    #[test]
+    fn new_message_clears_typing() {
+        let mut store = ChatStore::new();
+        let id = user();
+        let typing = |is_typing| ChatEvent::NewTypingNotify {
+            user_id: user(),
+            is_typing,
+        };
+        let typing_changed = |is_typing| Change::TypingChanged {
+            chat_id: user(),
+            user_id: user(),
+            is_typing,
+        };
+
+        assert_eq!(
+            store.apply(&on_chat(&id, typing(true))),
+            [typing_changed(true)]
+        );
+        assert_eq!(store.apply(&on_chat(&id, typing(true))), []);
+        assert_eq!(store.typing_users(&id), [&user()]);
+
+        assert_eq!(
+            store.apply(&on_chat(&id, new_message("A", 100, "a"))),
+            [Change::ChatUpdated(id.clone()), typing_changed(false)]
+        );
+        assert!(store.typing_users(&id).is_empty());
+
+        assert_eq!(
+            store.apply(&on_chat(&id, new_message("B", 200, "b"))),
+            [Change::ChatUpdated(id.clone())]
+        );
+        assert_eq!(store.apply(&on_chat(&id, typing(false))), []);
+    }
+
+    #[test]
+    fn chats_sorted_by_pin_then_activity() {
+        let mut store = ChatStore::new();
+        let chat = |n: u8| jid(&format!("65{n}@s.whatsapp.net"));
+        store.apply(&on_chat(&chat(1), synced_chat(true, 100)));
+        store.apply(&on_chat(&chat(2), synced_chat(true, 50)));
+        store.apply(&on_chat(&chat(3), synced_chat(false, 300)));
+        store.apply(&on_chat(&chat(4), synced_chat(false, 200)));
+        store.apply(&on_chat(&chat(5), synced_chat(false, 200)));
+        store.apply(&on_chat(&chat(6), synced_chat(false, 500)));
+        store.apply(&on_chat(&chat(6), ChatEvent::UpdateIsArchived(true)));
+        for (n, time_pinned) in [(1, 10), (2, 20)] {
+            store.apply(&on_chat(
+                &chat(n),
+                ChatEvent::UpdatePinNotify {
+                    is_pinned: true,
+                    time_pinned,
+                },
+            ));
+        }
+
+        let order =
+            |chats: Vec<&Chat>| -> Vec<Jid> { chats.into_iter().map(|c| c.id.clone()).collect() };
+        assert_eq!(
+            order(store.chats()),
+            [chat(2), chat(1), chat(3), chat(4), chat(5)]
+        );
+        assert_eq!(order(store.archived_chats()), [chat(6)]);
+
+        // New activity moves an unpinned chat up, but not above pinned ones
+        assert_eq!(
+            store.apply(&on_chat(&chat(5), new_message("A", 1000, "a"))),
+            [Change::ChatUpdated(chat(5))]
+        );
+        assert_eq!(
+            order(store.chats()),
+            [chat(2), chat(1), chat(5), chat(3), chat(4)]
+        );
+
+        store.apply(&on_chat(
+            &chat(2),
+            ChatEvent::UpdatePinNotify {
+                is_pinned: false,
+                time_pinned: 0,
+            },
+        ));
+        assert_eq!(
+            order(store.chats()),
+            [chat(1), chat(5), chat(3), chat(4), chat(2)]
+        );
+    }
+
+    fn group() -> Jid {
+        jid("120363000000000000@g.us")
+    }
+
+    /// Info of [`group`], with [`user`] as admin
+    /// and a member known by LID.
+    fn group_info() -> ChatEvent {
+        let info = GroupInfo::from_json(
+            r#"{"id":"120363000000000000@g.us","name":"Friends","description":"",
+                "ownerId":"6511111111@s.whatsapp.net","created":100,
+                "participants":[
+                    {"id":"6511111111@s.whatsapp.net","name":"","lid":"",
+                        "isAdmin":true,"isSuperAdmin":false},
+                    {"id":"6522222222@s.whatsapp.net","name":"Bob","lid":"1234@lid",
+                        "isAdmin":false,"isSuperAdmin":false}
+                ],
+                "size":2,"isAnnounce":false,"isLocked":false,"adminsOnlyAdd":false,
+                "joinApproval":false,"ephemeralTimer":0,"isCommunity":false,"parentId":""}"#,
+        )
+        .unwrap();
+        ChatEvent::GroupInfo(info)
+    }
+
+    fn group_change(change: GroupChange) -> ChatEvent {
+        ChatEvent::GroupChange {
+            actor: Some(user()),
+            time: 200,
+            change,
+        }
+    }
+
+    fn stored_group_info(store: &ChatStore) -> &GroupInfo {
+        store
+            .chat(&group())
+            .and_then(|chat| chat.group_info.as_ref())
+            .unwrap()
+    }
+
+    #[test]
+    fn joins_and_leaves_update_size() {
+        let mut store = ChatStore::new();
+        store.apply(&on_chat(&group(), group_info()));
+        assert_eq!(stored_group_info(&store).size, 2);
+
+        let carol = jid("6533333333@s.whatsapp.net");
+        assert_eq!(
+            store.apply(&on_chat(
+                &group(),
+                group_change(GroupChange::ParticipantsAdded {
+                    users: vec![carol.clone(), user()],
+                    by_invite_link: false,
+                }),
+            )),
+            [Change::ChatUpdated(group())]
+        );
+        let info = stored_group_info(&store);
+        assert_eq!(info.size, 3);
+        assert_eq!(info.participants.len(), 3);
+        assert!(info.participant(&carol).is_some());
+
+        // Bob leaves, by LID, and someone not in the group
+        store.apply(&on_chat(
+            &group(),
+            group_change(GroupChange::ParticipantsRemoved(vec![
+                jid("1234@lid"),
+                jid("6544444444@s.whatsapp.net"),
+            ])),
+        ));
+        let info = stored_group_info(&store);
+        assert_eq!(info.size, 2);
+        assert!(info
+            .participant(&jid("6522222222@s.whatsapp.net"))
+            .is_none());
+    }
+
+    #[test]
+    fn promotions_match_members_by_lid_or_phone() {
+        let mut store = ChatStore::new();
+        store.apply(&on_chat(&group(), group_info()));
+        let bob = jid("6522222222@s.whatsapp.net");
+        let role = |store: &ChatStore| stored_group_info(store).participant(&bob).unwrap().role;
+
+        store.apply(&on_chat(
+            &group(),
+            group_change(GroupChange::Promoted(vec![jid("1234@lid")])),
+        ));
+        assert_eq!(role(&store), ParticipantRole::Admin);
+        assert!(stored_group_info(&store).is_admin(&jid("1234@lid")));
+
+        store.apply(&on_chat(
+            &group(),
+            group_change(GroupChange::Demoted(vec![bob.clone()])),
+        ));
+        assert_eq!(role(&store), ParticipantRole::Member);
+
+        store.apply(&on_chat(
+            &group(),
+            group_change(GroupChange::Promoted(vec![bob.clone()])),
+        ));
+        store.apply(&on_chat(
+            &group(),
+            group_change(GroupChange::Demoted(vec![jid("1234@lid")])),
+        ));
+        assert_eq!(role(&store), ParticipantRole::Member);
+        // The others are left alone
+        assert!(stored_group_info(&store).is_admin(&user()));
+    }
+}
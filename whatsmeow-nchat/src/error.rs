@@ -1,14 +1,40 @@
 use std::{ffi::NulError, fmt::Display};
 
 use thiserror::Error;
-use whatsmeow_nchat_sys::LogMsg;
+use whatsmeow_nchat_sys::{ErrorCode, WmError};
 
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum WhatsmeowError {
-    Unknown,
-    Error(LogMsg),
+    Unknown(String),
+    /// The connection id doesn't refer to a live client,
+    /// for example after it was cleaned up.
+    ClientMissing(String),
+    NotLoggedIn(String),
+    InvalidJid(String),
+    FileRead(String),
+    UploadFailed(String),
+    SendFailed(String),
+    Timeout(String),
+    /// The WhatsApp client version is outdated,
+    /// the library needs to be updated.
+    Outdated(String),
+    InvalidArgument(String),
+    /// A request to the WhatsApp servers failed.
+    RequestFailed(String),
+    NotSupported(String),
+    /// The login was cancelled before it completed.
+    Cancelled,
+    /// The phone number passed to
+    /// [`crate::Connection::login_with_pairing_code`] isn't valid.
+    InvalidPhoneNumber(String),
+    /// A Qr login code couldn't be encoded or rendered.
+    Qr(String),
+    /// The local message store failed.
+    #[cfg(feature = "store")]
+    Store(#[from] rusqlite::Error),
     Nul(#[from] NulError),
+    Io(#[from] std::io::Error),
     Poison,
 }
 
@@ -17,28 +43,65 @@ pub type Result<T> = core::result::Result<T, WhatsmeowError>;
 impl Display for WhatsmeowError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            WhatsmeowError::Unknown => write!(f, "whatsmeow unknown error"),
-            WhatsmeowError::Error(msg) => {
-                write!(f, "{}\n{}:{}", msg.message, msg.filename, msg.line_no)
+            WhatsmeowError::Unknown(msg) => write!(f, "whatsmeow unknown error: {msg}"),
+            WhatsmeowError::ClientMissing(msg) => write!(f, "whatsmeow client missing: {msg}"),
+            WhatsmeowError::NotLoggedIn(msg) => write!(f, "whatsmeow not logged in: {msg}"),
+            WhatsmeowError::InvalidJid(msg) => write!(f, "whatsmeow invalid jid: {msg}"),
+            WhatsmeowError::FileRead(msg) => write!(f, "whatsmeow file read: {msg}"),
+            WhatsmeowError::UploadFailed(msg) => write!(f, "whatsmeow upload failed: {msg}"),
+            WhatsmeowError::SendFailed(msg) => write!(f, "whatsmeow send failed: {msg}"),
+            WhatsmeowError::Timeout(msg) => write!(f, "whatsmeow timeout: {msg}"),
+            WhatsmeowError::Outdated(msg) => write!(f, "whatsmeow client outdated: {msg}"),
+            WhatsmeowError::InvalidArgument(msg) => {
+                write!(f, "whatsmeow invalid argument: {msg}")
             }
+            WhatsmeowError::RequestFailed(msg) => write!(f, "whatsmeow request failed: {msg}"),
+            WhatsmeowError::NotSupported(msg) => write!(f, "whatsmeow not supported: {msg}"),
+            WhatsmeowError::Cancelled => write!(f, "whatsmeow: login cancelled"),
+            WhatsmeowError::InvalidPhoneNumber(phone) => {
+                write!(f, "whatsmeow invalid phone number: {phone}")
+            }
+            WhatsmeowError::Qr(msg) => write!(f, "whatsmeow qr code: {msg}"),
+            #[cfg(feature = "store")]
+            WhatsmeowError::Store(err) => write!(f, "whatsmeow message store: {err}"),
             WhatsmeowError::Nul(err) => write!(f, "whatsmeow ffi: {err}"),
+            WhatsmeowError::Io(err) => write!(f, "whatsmeow io: {err}"),
             WhatsmeowError::Poison => write!(f, "whatsmeow: mutex panicked (poison error)"),
         }
     }
 }
 
-pub fn get_error() -> WhatsmeowError {
-    if let Some(err) = whatsmeow_nchat_sys::get_error() {
-        WhatsmeowError::Error(err)
-    } else {
-        WhatsmeowError::Unknown
+impl From<WmError> for WhatsmeowError {
+    fn from(err: WmError) -> Self {
+        let msg = err.message;
+        match err.code {
+            ErrorCode::ClientMissing => WhatsmeowError::ClientMissing(msg),
+            ErrorCode::NotLoggedIn => WhatsmeowError::NotLoggedIn(msg),
+            ErrorCode::InvalidJid => WhatsmeowError::InvalidJid(msg),
+            ErrorCode::FileRead => WhatsmeowError::FileRead(msg),
+            ErrorCode::UploadFailed => WhatsmeowError::UploadFailed(msg),
+            ErrorCode::SendFailed => WhatsmeowError::SendFailed(msg),
+            ErrorCode::Timeout => WhatsmeowError::Timeout(msg),
+            ErrorCode::Outdated => WhatsmeowError::Outdated(msg),
+            ErrorCode::InvalidArgument => WhatsmeowError::InvalidArgument(msg),
+            ErrorCode::RequestFailed => WhatsmeowError::RequestFailed(msg),
+            ErrorCode::NotSupported => WhatsmeowError::NotSupported(msg),
+            _ => WhatsmeowError::Unknown(msg),
+        }
     }
 }
 
-pub fn attempt(r: whatsmeow_nchat_sys::GoInt) -> Result<()> {
-    if r == -1 {
-        Err(get_error())
-    } else {
-        Ok(())
-    }
+/// Calls a `CWm*` export, passing it the error message out-parameter.
+pub fn attempt(
+    f: impl FnOnce(*mut *mut std::ffi::c_char) -> whatsmeow_nchat_sys::GoInt,
+) -> Result<()> {
+    WmError::check(f).map_err(WhatsmeowError::from)
+}
+
+/// Calls a `CWm*` export that returns a string,
+/// passing it the string and error message out-parameters.
+pub fn attempt_string(
+    f: impl FnOnce(*mut *mut std::ffi::c_char, *mut *mut std::ffi::c_char) -> whatsmeow_nchat_sys::GoInt,
+) -> Result<String> {
+    WmError::check_string(f).map_err(WhatsmeowError::from)
 }
@@ -4,55 +4,104 @@
 //!
 //! - You create connections using [`create_connection`],
 //!   each representing  a different WhatsApp profile or account.
-//!   - You can have multiple running concurrently, just pass the right id.
-//! - Then you operate on them using the other functions (eg. to send messages).
+//!   - You can have multiple running concurrently, each with its own [`Connection`].
+//! - Then you operate on them using the [`Connection`] methods (eg. to send messages).
 //! - You periodically poll and receive events using the `Receiver`
 //!   you got from [`create_connection`]. If supported you could hook this up
 //!   to your UI (eg: `iced::Task::sip`).
+//! - Instead of tracking chats yourself, you can fold the events
+//!   into a [`chats::ChatStore`] and query the chat list from it.
+//! - Dropping the [`Connection`] disconnects and cleans it up,
+//!   closing its `Receiver` too.
+//! - If you're embedding this in a TUI or daemon, call [`set_headless`]
+//!   first so nothing gets printed to stdout.
+//! - Logs from the Go side are emitted as `tracing` events with the
+//!   `whatsmeow` target, so install a subscriber to see them.
+//!   Private data is redacted from them, see [`set_log_redaction`].
+//! - Qr login codes can be rendered for the terminal, as SVG or PNG
+//!   with the [`qr`] module (`qr` feature, on by default).
+//! - Message history isn't kept by WhatsApp, so with the `store` feature
+//!   you can keep it yourself, see [`store`].
+//! - Stickers and voice notes are sent with [`FileType::Sticker`] and
+//!   [`FileType::VoiceNote`], the latter's metadata is read with [`voice`].
 //!
 //! # Safety
 //! - These functions are implemented in memory-safe Go
 //! - The Go implementation uses Mutexes, so this should be thread-safe
-//!
-//! # TODO
-//! - Have system to prevent using connections after cleaning them up
 
-pub use sys::{ChatEvent, ConnId, Event, Jid, MsgId, StatusFlags};
+pub use sys::{
+    is_headless, set_headless, set_log_redaction, ChatEvent, Config, ConnId, DownloadFileStatus,
+    Event, GroupChange, GroupInfo, GroupSetting, Jid, LogRedaction, LoginMode, LoginProgress,
+    MessageContent, MsgId, Participant, ParticipantRole, ParticipantStatus, PollOption,
+    StatusFlags, TemplateButton, VCard, VCardPhone,
+};
 use whatsmeow_nchat_sys::{self as sys, DownloadFileAction};
 
 use std::{
     ffi::{c_char, CStr, CString},
-    path::Path,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 use tokio::sync::mpsc::UnboundedReceiver as Receiver;
 
+pub mod chats;
 mod error;
-use error::{attempt, get_error};
+#[cfg(feature = "qr")]
+pub mod qr;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(test)]
+mod testing;
+pub mod voice;
+use error::{attempt, attempt_string};
 pub use error::{Result, WhatsmeowError};
 
 static EMPTY: &CStr = c"";
 
 /// Initializes a connection. The first thing to do on startup!
 ///
-/// This returns the connection id (store it, you'll need it)
+/// This returns the [`Connection`] (store it, you'll need it)
 /// and a [`Receiver<Event>`] specific to that connection,
 /// that you'll use to receive all events (you'll need to poll it).
 pub fn create_connection(
     path: impl AsRef<Path>,
     proxy: &str,
     send_type: isize,
-) -> Result<(ConnId, Receiver<Event>)> {
-    let path = CString::new(path.as_ref().to_string_lossy().to_string())?;
+) -> Result<(Connection, Receiver<Event>)> {
+    let path = path.as_ref().to_path_buf();
+    let path_c = CString::new(path.to_string_lossy().to_string())?;
     let proxy = CString::new(proxy)?;
 
-    unsafe {
+    let (id, receiver) = unsafe {
         sys::create_connection(
-            path.as_ptr().cast_mut(),
+            path_c.as_ptr().cast_mut(),
             proxy.as_ptr().cast_mut(),
             send_type as _,
         )
+    }?;
+    Ok((Connection { id, path }, receiver))
+}
+
+/// A connection to a WhatsApp profile or account,
+/// created with [`create_connection`].
+///
+/// Dropping it disconnects the account, frees the Go-side client
+/// and closes the `Receiver` that came with it,
+/// so it can't be used after being cleaned up.
+#[derive(Debug)]
+pub struct Connection {
+    id: ConnId,
+    path: PathBuf,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // Errors are ignored, it's fine if we were never logged in
+        let _ = attempt(|err| unsafe { sys::CWmLogout(self.id.raw(), err) });
+        let _ = attempt(|err| unsafe { sys::CWmCleanup(self.id.raw(), err) });
+        sys::remove_sender(self.id);
+        sys::remove_config(self.id);
     }
-    .map_err(|()| get_error())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -65,48 +114,32 @@ pub enum AccountState {
 }
 
 impl AccountState {
-    /// Gets the current state of the connection `id`
-    #[must_use]
-    pub fn get(id: ConnId) -> Self {
-        unsafe {
-            match sys::CWmExtGetState(id.raw()) {
-                1 => Self::Connecting,
-                2 => Self::Connected,
-                3 => Self::Disconnected,
-                4 => Self::Outdated,
-                _ => Self::None,
-            }
+    fn from_raw(n: sys::GoInt64) -> Self {
+        match n {
+            1 => Self::Connecting,
+            2 => Self::Connected,
+            3 => Self::Disconnected,
+            4 => Self::Outdated,
+            _ => Self::None,
         }
     }
 }
 
-/// Logs into an account with the connection.
-/// Call this only if you haven't logged in yet.
-///
-/// Use [`AccountState::get`] to check if you're logged in.
-pub fn login(id: ConnId) -> Result<()> {
-    attempt(unsafe { sys::CWmLogin(id.raw()) })
-}
-
-/// Logs out of the account linked in the connection.
-///
-/// # Errors
-/// - `id` is invalid
-pub fn logout(id: ConnId) -> Result<()> {
-    attempt(unsafe { sys::CWmLogout(id.raw()) })
-}
-
-// TODO: find out what this is
-pub fn cleanup(id: ConnId) -> Result<()> {
-    attempt(unsafe { sys::CWmCleanup(id.raw()) })
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
     Audio,
     Video,
     Image,
     Document,
+    /// A WebP sticker, sent as such regardless of `send_type`.
+    /// Text and replies aren't shown with it.
+    Sticker {
+        animated: bool,
+    },
+    /// An Ogg/Opus voice note, shown as a playable bubble.
+    /// The duration and waveform are read from the file,
+    /// see [`voice::VoiceNoteInfo`].
+    VoiceNote,
 }
 
 impl FileType {
@@ -116,263 +149,1230 @@ impl FileType {
             FileType::Video => "video",
             FileType::Image => "image",
             FileType::Document => "document",
+            FileType::Sticker { .. } => "image/webp",
+            FileType::VoiceNote => "audio/ogg",
         }
     }
-}
 
-pub struct QuotedMessage {
-    pub sender: Jid,
-    pub contents: String,
-    pub message_id: MsgId,
+    /// Options for the Go side that `send_type` doesn't cover.
+    fn info_json(self, path: &Path) -> Result<Option<String>> {
+        let info = match self {
+            FileType::Sticker { animated } => serde_json::json!({
+                "sticker": true,
+                "animated": animated,
+            }),
+            FileType::VoiceNote => {
+                let info = voice::VoiceNoteInfo::read(path)?;
+                serde_json::json!({
+                    "ptt": true,
+                    "seconds": info.seconds(),
+                    "waveform": info.waveform,
+                })
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(info.to_string()))
+    }
 }
 
-/// Sends/edits a message with the given parameters.
-///
-/// Optional parameters:
+/// Normalizes a phone number for [`Connection::login_with_pairing_code`]
+/// to digits only, in international format without a leading `+`.
 ///
-/// - To edit the message instead of sending it,
-///   use `edit_msg` argument (Id and timestamp of message being edited)
-/// - To attach a file, use `file` argument
-/// - To reply to a quoted message, use `reply_to` argument
-/// - To mention users in the message, use `mentions` argument
-///   with display names and Jids of the mentioned users
+/// Spaces, dashes, dots and parentheses are removed,
+/// as is a leading `+` or `00`.
 ///
 /// # Errors
-/// - `id` is invalid
-/// - not logged in
-/// - JID couldn't be parsed
-/// - attached file couldn't be read or uploaded
-/// - other errors from sending messages
-pub fn send_message(
-    id: ConnId,
-    chat_id: &Jid,
-    contents: &str,
-    reply_to: Option<&QuotedMessage>,
-    file: Option<(impl AsRef<Path>, FileType)>,
-    edit_msg: Option<(&MsgId, isize)>,
-    mentions: &[(String, Jid)],
-) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    let text = CString::new(contents)?;
-
-    let (quoted_id, quoted_text, quoted_sender) = if let Some(msg) = reply_to {
-        (
-            Some((&msg.message_id).try_into()?),
-            Some(CString::new(msg.contents.as_str())?),
-            Some((&msg.sender).try_into()?),
-        )
-    } else {
-        (None, None, None)
-    };
-
-    let (edit_msg_id, edit_msg_sent) = if let Some((id, timestamp)) = &edit_msg {
-        (Some(CString::new(id.0.as_str())?), *timestamp as _)
-    } else {
-        (None, 0)
-    };
-
-    let (file_path, file_type) = if let Some((path, file_type)) = file {
-        (
-            Some(CString::new(path.as_ref().to_string_lossy().to_string())?),
-            Some(CString::new(file_type.as_str())?),
-        )
-    } else {
-        (None, None)
-    };
-
-    let mentions_json =
-        CString::new(serde_json::to_string(mentions).unwrap_or_else(|_| "{}".to_owned()))?;
-
-    attempt(unsafe {
-        sys::CWmSendMessage(
-            id.raw(),
-            chat_id.as_ptr().cast_mut(),
-            text.as_ptr().cast_mut(),
-            cstr_maybe(quoted_id.as_ref()),
-            cstr_maybe(quoted_text.as_ref()),
-            cstr_maybe(quoted_sender.as_ref()),
-            cstr_maybe(file_path.as_ref()),
-            cstr_maybe(file_type.as_ref()),
-            cstr_maybe(edit_msg_id.as_ref()),
-            edit_msg_sent,
-            mentions_json.as_ptr().cast_mut(),
-        )
-    })
-}
+/// [`WhatsmeowError::InvalidPhoneNumber`] if anything else is left
+/// or the number doesn't have 7 to 15 digits.
+pub fn normalize_phone_number(phone: &str) -> Result<String> {
+    let invalid = || WhatsmeowError::InvalidPhoneNumber(phone.to_owned());
 
-fn cstr_maybe(c: Option<&CString>) -> *mut c_char {
-    c.as_ref().map_or(EMPTY.as_ptr(), |n| n.as_ptr()).cast_mut()
-}
+    let trimmed = phone.trim();
+    let trimmed = trimmed
+        .strip_prefix('+')
+        .or_else(|| trimmed.strip_prefix("00"))
+        .unwrap_or(trimmed);
 
-/// Forces an update of the contact list with new info if any.
-///
-/// As the new info loads, it will be streamed in
-/// through [`ChatEvent::NewContactsNotify`],
-/// so watch your events for that.
-///
-/// # Errors
-/// - `id` is invalid
-pub fn fetch_contacts(id: ConnId) -> Result<()> {
-    attempt(unsafe { sys::CWmGetContacts(id.raw()) })
-}
+    let mut digits = String::with_capacity(trimmed.len());
+    for c in trimmed.chars() {
+        match c {
+            '0'..='9' => digits.push(c),
+            ' ' | '-' | '.' | '(' | ')' => {}
+            _ => return Err(invalid()),
+        }
+    }
 
-/// Fetches the status (online/offline/...) of a user.
-///
-/// The actual data will be returned later through [`Event`].
-/// Call this when you open a DM page.
-///
-/// # Errors
-/// - `id` is invalid
-/// - Tried checking status of self
-/// - Other protocol errors
-pub fn fetch_status(id: ConnId, user_id: &Jid) -> Result<()> {
-    let user_id_c: CString = user_id.try_into()?;
-    let r = unsafe { sys::CWmGetStatus(id.raw(), user_id_c.as_ptr().cast_mut()) };
-    if r == -1 {
-        Err(get_error())
-    } else {
-        Ok(())
+    // E.164 numbers have at most 15 digits, country code included
+    if !(7..=15).contains(&digits.len()) || digits.starts_with('0') {
+        return Err(invalid());
     }
+    Ok(digits)
 }
 
-/// Marks a message as read.
-///
-/// You might need to call this for every unread message,
-/// even if they come in bulk (not sure).
-///
-/// # Errors
-/// - `id` is invalid
-/// - Not logged in
-/// - Other protocol errors
-pub fn mark_message_read(id: ConnId, chat_id: &Jid, sender_id: &Jid, msg: &MsgId) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    let sender_id: CString = sender_id.try_into()?;
-    let msg_id: CString = msg.try_into()?;
-    attempt(unsafe {
-        sys::CWmMarkMessageRead(
-            id.raw(),
-            chat_id.as_ptr().cast_mut(),
-            sender_id.as_ptr().cast_mut(),
-            msg_id.as_ptr().cast_mut(),
-        )
-    })
+/// Cancels the login if [`Connection::login`]'s future
+/// is dropped before it completes.
+struct CancelLoginOnDrop<'a> {
+    conn: &'a Connection,
+    armed: bool,
 }
 
-/// Deletes the specified message.
-///
-/// **You can only delete either your own message,
-/// or messages in a group you're admin in.**
-/// Otherwise this simply does nothing
-pub fn delete_message(id: ConnId, chat_id: &Jid, sender_id: &Jid, msg: &MsgId) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    let sender_id: CString = sender_id.try_into()?;
-    let msg_id: CString = msg.try_into()?;
-    attempt(unsafe {
-        sys::CWmDeleteMessage(
-            id.raw(),
-            chat_id.as_ptr().cast_mut(),
-            sender_id.as_ptr().cast_mut(),
-            msg_id.as_ptr().cast_mut(),
-        )
-    })
+impl Drop for CancelLoginOnDrop<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            _ = self.conn.cancel_login();
+        }
+    }
 }
 
-/// Exits a group
-pub fn exit_group(id: ConnId, chat_id: &Jid) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    attempt(unsafe { sys::CWmDeleteChat(id.raw(), chat_id.as_ptr().cast_mut()) })
+pub struct QuotedMessage {
+    pub sender: Jid,
+    pub contents: String,
+    pub message_id: MsgId,
 }
 
-/// Enables/disables the "PERSON is typing..." indicator.
+/// An ongoing live-location share,
+/// started with [`Connection::start_live_location`].
 ///
-/// # Errors
-/// - `id` is invalid
-/// - Not logged in
-/// - Other protocol errors
-pub fn send_typing_indicator(id: ConnId, chat_id: &Jid, is_typing: bool) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    attempt(unsafe { sys::CWmSendTyping(id.raw(), chat_id.as_ptr().cast_mut(), is_typing.into()) })
+/// Recipients see a single message that moves with each
+/// [`LiveLocation::update`], so call it periodically
+/// (eg. whenever the position changes), then [`LiveLocation::stop`].
+/// Dropping it without stopping leaves the last position
+/// shown as live until WhatsApp expires it.
+#[derive(Debug, Clone)]
+pub struct LiveLocation {
+    chat_id: Jid,
+    msg_id: MsgId,
+    caption: String,
+    sequence: isize,
 }
 
-/// Sets your online status.
-///
-/// This will also trigger a
-/// [`Event::SetStatus`] or [`Event::ClearStatus`]
-/// for client-side updation.
-pub fn set_is_online(id: ConnId, is_online: bool) -> Result<()> {
-    attempt(unsafe { sys::CWmSendStatus(id.raw(), is_online.into()) })
-}
+impl LiveLocation {
+    /// Id of the live-location message.
+    #[must_use]
+    pub fn msg_id(&self) -> &MsgId {
+        &self.msg_id
+    }
 
-/// Downloads a file attachment.
-///
-/// This triggers a [`ChatEvent::NewMessageFileNotify`].
-/// The `action` is just passed along to that event for later use
-/// and doesn't affect the logic here.
-pub fn download_file(
-    id: ConnId,
-    chat_id: &Jid,
-    msg: &MsgId,
-    file_id: &str,
-    action: DownloadFileAction,
-) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    let msg: CString = msg.try_into()?;
-    let file_id = CString::new(file_id)?;
-
-    attempt(unsafe {
-        sys::CWmDownloadFile(
-            id.raw(),
-            chat_id.as_ptr().cast_mut(),
-            msg.as_ptr().cast_mut(),
-            file_id.as_ptr().cast_mut(),
-            action as _,
-        )
-    })
+    /// Moves the shared location to `lat`, `lon`
+    /// (in degrees), `accuracy_m` meters around it.
+    ///
+    /// # Errors
+    /// - coordinates out of range
+    /// - errors from sending messages
+    pub fn update(&mut self, conn: &Connection, lat: f64, lon: f64, accuracy_m: u32) -> Result<()> {
+        self.sequence += 1;
+        conn.send_live_location(
+            &self.chat_id,
+            Some(&self.msg_id),
+            (lat, lon),
+            accuracy_m,
+            &self.caption,
+            self.sequence,
+            false,
+        )?;
+        Ok(())
+    }
+
+    /// Stops sharing, leaving `lat`, `lon`
+    /// as a static location in the chat.
+    ///
+    /// # Errors
+    /// - coordinates out of range
+    /// - errors from sending messages
+    pub fn stop(self, conn: &Connection, lat: f64, lon: f64) -> Result<()> {
+        conn.send_live_location(
+            &self.chat_id,
+            Some(&self.msg_id),
+            (lat, lon),
+            0,
+            &self.caption,
+            self.sequence + 1,
+            true,
+        )?;
+        Ok(())
+    }
 }
 
-/// Reacts to a message with an emoji.
-///
-/// Triggers a [`ChatEvent::NewMessageReactionNotify`]
-/// for client-side updation.
-pub fn send_reaction(
-    id: ConnId,
-    chat_id: &Jid,
-    sender_id: &Jid,
-    msg_id: &MsgId,
-    emoji: &str,
-) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    let sender_id: CString = sender_id.try_into()?;
-    let msg_id: CString = msg_id.try_into()?;
-    let emoji = CString::new(emoji)?;
-
-    attempt(unsafe {
-        sys::CWmSendReaction(
-            id.raw(),
-            chat_id.as_ptr().cast_mut(),
-            sender_id.as_ptr().cast_mut(),
-            msg_id.as_ptr().cast_mut(),
-            emoji.as_ptr().cast_mut(),
-        )
-    })
+impl Connection {
+    /// Returns the id of the connection.
+    #[must_use]
+    pub fn id(&self) -> ConnId {
+        self.id
+    }
+
+    /// Gets the current state of the connection
+    #[must_use]
+    pub fn state(&self) -> AccountState {
+        AccountState::from_raw(unsafe { sys::CWmExtGetState(self.id.raw()) })
+    }
+
+    /// Returns the settings of the connection.
+    #[must_use]
+    pub fn config(&self) -> Config {
+        sys::config(self.id)
+    }
+
+    /// Changes the settings of the connection,
+    /// eg. to log in with a pairing code instead of a Qr code.
+    ///
+    /// Saved to the profile directory if
+    /// [`Connection::persist_config`] was called.
+    ///
+    /// # Errors
+    /// If saving failed, the new settings are used anyway.
+    pub fn set_config(&self, config: Config) -> Result<()> {
+        Ok(sys::set_config(self.id, config)?)
+    }
+
+    /// Keeps the settings in `app.conf` in the profile directory.
+    ///
+    /// If the file exists, the settings are loaded from it,
+    /// otherwise the current settings are saved to it.
+    /// Every later change is saved too.
+    ///
+    /// # Errors
+    /// If reading or writing the file failed.
+    pub fn persist_config(&self) -> Result<()> {
+        Ok(sys::persist_config(self.id, &self.path)?)
+    }
+
+    /// Opens the local message history of the profile,
+    /// kept as [`store::STORE_FILE_NAME`] in the profile directory.
+    ///
+    /// # Errors
+    /// If the database couldn't be opened.
+    #[cfg(feature = "store")]
+    pub fn open_message_store(&self) -> Result<store::MessageStore> {
+        store::MessageStore::open(self.path.join(store::STORE_FILE_NAME))
+    }
+
+    /// Logs into an account with the connection.
+    /// Call this only if you haven't logged in yet.
+    ///
+    /// This doesn't block the thread. Progress (Qr code available,
+    /// pair success, ...) is reported through [`Event::LoginProgress`]
+    /// while the returned future waits for the outcome.
+    ///
+    /// Dropping the future before it completes cancels the login,
+    /// see also [`Connection::cancel_login`].
+    ///
+    /// Use [`Connection::state`] to check if you're logged in.
+    ///
+    /// # Errors
+    /// - A login is already in progress
+    /// - [`WhatsmeowError::Timeout`] if linking or connecting took too long
+    /// - [`WhatsmeowError::Outdated`] if the WhatsApp client is outdated
+    /// - [`WhatsmeowError::Cancelled`] if the login was cancelled
+    pub async fn login(&self) -> Result<()> {
+        self.login_with(LoginMode::Default, EMPTY).await
+    }
+
+    /// Like [`Connection::login`], but always links the device
+    /// with a Qr code (see [`Event::QrCode`]), regardless of the config.
+    ///
+    /// # Errors
+    /// See [`Connection::login`].
+    pub async fn login_with_qr(&self) -> Result<()> {
+        self.login_with(LoginMode::Qr, EMPTY).await
+    }
+
+    /// Like [`Connection::login`], but links the device with
+    /// a pairing code (see [`Event::PairingCode`]) entered
+    /// on the phone with the number `phone`.
+    ///
+    /// `phone` is normalized with [`normalize_phone_number`],
+    /// so eg. `+1 (555) 010-0200` is fine.
+    ///
+    /// # Errors
+    /// - [`WhatsmeowError::InvalidPhoneNumber`] if `phone` isn't a valid number
+    /// - See [`Connection::login`].
+    pub async fn login_with_pairing_code(&self, phone: &str) -> Result<()> {
+        let phone = CString::new(normalize_phone_number(phone)?)?;
+        self.login_with(LoginMode::PairingCode, &phone).await
+    }
+
+    async fn login_with(&self, mode: LoginMode, phone: &CStr) -> Result<()> {
+        let Some(outcome) = sys::wait_login(self.id) else {
+            return Err(WhatsmeowError::InvalidArgument(
+                "login already in progress".to_owned(),
+            ));
+        };
+        attempt(|err| unsafe {
+            sys::CWmLogin(self.id.raw(), mode as _, phone.as_ptr().cast_mut(), err)
+        })?;
+
+        let mut guard = CancelLoginOnDrop {
+            conn: self,
+            armed: true,
+        };
+        let progress = outcome.await.unwrap_or(LoginProgress::Cancelled);
+        guard.armed = false;
+
+        match progress {
+            LoginProgress::Connected => Ok(()),
+            LoginProgress::Timeout => Err(WhatsmeowError::Timeout("login timed out".to_owned())),
+            LoginProgress::Outdated => Err(WhatsmeowError::Outdated(
+                "whatsapp client is outdated".to_owned(),
+            )),
+            LoginProgress::Cancelled => Err(WhatsmeowError::Cancelled),
+            _ => Err(WhatsmeowError::NotLoggedIn("login failed".to_owned())),
+        }
+    }
+
+    /// Cancels a login started with [`Connection::login`].
+    ///
+    /// Does nothing if no login is in progress.
+    pub fn cancel_login(&self) -> Result<()> {
+        attempt(|err| unsafe { sys::CWmLoginCancel(self.id.raw(), err) })
+    }
+
+    /// Logs out of the account linked in the connection.
+    pub fn logout(&self) -> Result<()> {
+        attempt(|err| unsafe { sys::CWmLogout(self.id.raw(), err) })
+    }
+
+    /// Sends/edits a message with the given parameters.
+    ///
+    /// Optional parameters:
+    ///
+    /// - To edit the message instead of sending it,
+    ///   use `edit_msg` argument (Id and timestamp of message being edited)
+    /// - To attach a file, use `file` argument
+    /// - To reply to a quoted message, use `reply_to` argument
+    /// - To mention users in the message, use `mentions` argument
+    ///   with display names and Jids of the mentioned users
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - attached file couldn't be read or uploaded
+    /// - voice note isn't an Ogg/Opus file
+    /// - other errors from sending messages
+    pub fn send_message(
+        &self,
+        chat_id: &Jid,
+        contents: &str,
+        reply_to: Option<&QuotedMessage>,
+        file: Option<(impl AsRef<Path>, FileType)>,
+        edit_msg: Option<(&MsgId, isize)>,
+        mentions: &[(String, Jid)],
+    ) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let text = CString::new(contents)?;
+
+        let (quoted_id, quoted_text, quoted_sender) = if let Some(msg) = reply_to {
+            (
+                Some((&msg.message_id).try_into()?),
+                Some(CString::new(msg.contents.as_str())?),
+                Some((&msg.sender).try_into()?),
+            )
+        } else {
+            (None, None, None)
+        };
+
+        let (edit_msg_id, edit_msg_sent) = if let Some((id, timestamp)) = &edit_msg {
+            (Some(CString::new(id.0.as_str())?), *timestamp as _)
+        } else {
+            (None, 0)
+        };
+
+        let (file_path, file_type, file_info) = if let Some((path, file_type)) = file {
+            let file_info = file_type.info_json(path.as_ref())?;
+            (
+                Some(CString::new(path.as_ref().to_string_lossy().to_string())?),
+                Some(CString::new(file_type.as_str())?),
+                file_info.map(CString::new).transpose()?,
+            )
+        } else {
+            (None, None, None)
+        };
+
+        let mentions_json =
+            CString::new(serde_json::to_string(mentions).unwrap_or_else(|_| "{}".to_owned()))?;
+
+        attempt(|err| unsafe {
+            sys::CWmSendMessage(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                text.as_ptr().cast_mut(),
+                cstr_maybe(quoted_id.as_ref()),
+                cstr_maybe(quoted_text.as_ref()),
+                cstr_maybe(quoted_sender.as_ref()),
+                cstr_maybe(file_path.as_ref()),
+                cstr_maybe(file_type.as_ref()),
+                cstr_maybe(file_info.as_ref()),
+                cstr_maybe(edit_msg_id.as_ref()),
+                edit_msg_sent,
+                mentions_json.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Forces an update of the contact list with new info if any.
+    ///
+    /// As the new info loads, it will be streamed in
+    /// through [`ChatEvent::NewContactsNotify`],
+    /// so watch your events for that.
+    pub fn fetch_contacts(&self) -> Result<()> {
+        attempt(|err| unsafe { sys::CWmGetContacts(self.id.raw(), err) })
+    }
+
+    /// Fetches the status (online/offline/...) of a user.
+    ///
+    /// The actual data will be returned later through [`Event`].
+    /// Call this when you open a DM page.
+    ///
+    /// # Errors
+    /// - Tried checking status of self
+    /// - Other protocol errors
+    pub fn fetch_status(&self, user_id: &Jid) -> Result<()> {
+        let user_id_c: CString = user_id.try_into()?;
+        attempt(|err| unsafe {
+            sys::CWmGetStatus(self.id.raw(), user_id_c.as_ptr().cast_mut(), err)
+        })
+    }
+
+    /// Marks a message as read.
+    ///
+    /// You might need to call this for every unread message,
+    /// even if they come in bulk (not sure).
+    ///
+    /// # Errors
+    /// - Not logged in
+    /// - Other protocol errors
+    pub fn mark_message_read(&self, chat_id: &Jid, sender_id: &Jid, msg: &MsgId) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let sender_id: CString = sender_id.try_into()?;
+        let msg_id: CString = msg.try_into()?;
+        attempt(|err| unsafe {
+            sys::CWmMarkMessageRead(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                sender_id.as_ptr().cast_mut(),
+                msg_id.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Deletes the specified message.
+    ///
+    /// **You can only delete either your own message,
+    /// or messages in a group you're admin in.**
+    /// Otherwise this simply does nothing
+    pub fn delete_message(&self, chat_id: &Jid, sender_id: &Jid, msg: &MsgId) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let sender_id: CString = sender_id.try_into()?;
+        let msg_id: CString = msg.try_into()?;
+        attempt(|err| unsafe {
+            sys::CWmDeleteMessage(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                sender_id.as_ptr().cast_mut(),
+                msg_id.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Exits a group
+    pub fn exit_group(&self, chat_id: &Jid) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        attempt(|err| unsafe {
+            sys::CWmDeleteChat(self.id.raw(), chat_id.as_ptr().cast_mut(), err)
+        })
+    }
+
+    /// Enables/disables the "PERSON is typing..." indicator.
+    ///
+    /// # Errors
+    /// - Not logged in
+    /// - Other protocol errors
+    pub fn send_typing_indicator(&self, chat_id: &Jid, is_typing: bool) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        attempt(|err| unsafe {
+            sys::CWmSendTyping(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                is_typing.into(),
+                err,
+            )
+        })
+    }
+
+    /// Sets your online status.
+    ///
+    /// This will also trigger a
+    /// [`Event::SetStatus`] or [`Event::ClearStatus`]
+    /// for client-side updation.
+    pub fn set_is_online(&self, is_online: bool) -> Result<()> {
+        attempt(|err| unsafe { sys::CWmSendStatus(self.id.raw(), is_online.into(), err) })
+    }
+
+    /// Downloads a file attachment.
+    ///
+    /// This triggers a [`ChatEvent::NewMessageFileNotify`].
+    /// The `action` is just passed along to that event for later use
+    /// and doesn't affect the logic here.
+    pub fn download_file(
+        &self,
+        chat_id: &Jid,
+        msg: &MsgId,
+        file_id: &str,
+        action: DownloadFileAction,
+    ) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let msg: CString = msg.try_into()?;
+        let file_id = CString::new(file_id)?;
+
+        attempt(|err| unsafe {
+            sys::CWmDownloadFile(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                msg.as_ptr().cast_mut(),
+                file_id.as_ptr().cast_mut(),
+                action as _,
+                err,
+            )
+        })
+    }
+
+    /// Reacts to a message with an emoji.
+    ///
+    /// Triggers a [`ChatEvent::NewMessageReactionNotify`]
+    /// for client-side updation.
+    pub fn send_reaction(
+        &self,
+        chat_id: &Jid,
+        sender_id: &Jid,
+        msg_id: &MsgId,
+        emoji: &str,
+    ) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let sender_id: CString = sender_id.try_into()?;
+        let msg_id: CString = msg_id.try_into()?;
+        let emoji = CString::new(emoji)?;
+
+        attempt(|err| unsafe {
+            sys::CWmSendReaction(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                sender_id.as_ptr().cast_mut(),
+                msg_id.as_ptr().cast_mut(),
+                emoji.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Sends a location pin at `lat`, `lon` (in degrees),
+    /// with an optional place `name` and `address` (empty to leave out).
+    ///
+    /// Incoming locations arrive as [`MessageContent::Location`].
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - coordinates out of range
+    /// - other errors from sending messages
+    pub fn send_location(
+        &self,
+        chat_id: &Jid,
+        lat: f64,
+        lon: f64,
+        name: &str,
+        address: &str,
+    ) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let name = CString::new(name)?;
+        let address = CString::new(address)?;
+
+        attempt(|err| unsafe {
+            sys::CWmSendLocation(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                lat,
+                lon,
+                name.as_ptr().cast_mut(),
+                address.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Starts sharing a live location at `lat`, `lon` (in degrees),
+    /// `accuracy_m` meters around it, with an optional `caption`.
+    ///
+    /// Keep the returned [`LiveLocation`] to update and stop it.
+    /// Incoming ones arrive as [`MessageContent::Location`]
+    /// with `is_live` set, and updates as edits of it.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - coordinates out of range
+    /// - other errors from sending messages
+    pub fn start_live_location(
+        &self,
+        chat_id: &Jid,
+        lat: f64,
+        lon: f64,
+        accuracy_m: u32,
+        caption: &str,
+    ) -> Result<LiveLocation> {
+        let msg_id =
+            self.send_live_location(chat_id, None, (lat, lon), accuracy_m, caption, 0, false)?;
+        Ok(LiveLocation {
+            chat_id: chat_id.clone(),
+            msg_id,
+            caption: caption.to_owned(),
+            sequence: 0,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn send_live_location(
+        &self,
+        chat_id: &Jid,
+        live_msg_id: Option<&MsgId>,
+        (lat, lon): (f64, f64),
+        accuracy_m: u32,
+        caption: &str,
+        sequence: isize,
+        is_stop: bool,
+    ) -> Result<MsgId> {
+        let chat_id: CString = chat_id.try_into()?;
+        let live_msg_id: Option<CString> = live_msg_id.map(TryInto::try_into).transpose()?;
+        let caption = CString::new(caption)?;
+
+        let msg_id = attempt_string(|msg_id, err| unsafe {
+            sys::CWmSendLiveLocation(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                cstr_maybe(live_msg_id.as_ref()),
+                lat,
+                lon,
+                accuracy_m as _,
+                caption.as_ptr().cast_mut(),
+                sequence as _,
+                is_stop.into(),
+                msg_id,
+                err,
+            )
+        })?;
+        Ok(MsgId(msg_id))
+    }
+
+    /// Shares contact cards, as a single contact
+    /// or a contact list if there's more than one.
+    ///
+    /// Incoming ones arrive as [`MessageContent::Contact`].
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - no cards given
+    /// - other errors from sending messages
+    pub fn send_contact(&self, chat_id: &Jid, cards: &[VCard]) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let contacts: Vec<serde_json::Value> = cards
+            .iter()
+            .map(|card| {
+                serde_json::json!({
+                    "display_name": card.full_name,
+                    "vcard": card.to_string(),
+                })
+            })
+            .collect();
+        let contacts_json = CString::new(serde_json::Value::from(contacts).to_string())?;
+
+        attempt(|err| unsafe {
+            sys::CWmSendContacts(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                contacts_json.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Sends a poll with a `question` and `options` to pick from,
+    /// returning its message id.
+    ///
+    /// Voters can pick up to `selectable_count` options,
+    /// `0` (or more than there are options) for any number.
+    /// Results arrive through [`ChatEvent::PollUpdate`].
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - less than two options, or duplicate options
+    /// - other errors from sending messages
+    pub fn create_poll(
+        &self,
+        chat_id: &Jid,
+        question: &str,
+        options: &[&str],
+        selectable_count: u32,
+    ) -> Result<MsgId> {
+        let chat_id: CString = chat_id.try_into()?;
+        let question = CString::new(question)?;
+        let options_json = CString::new(serde_json::Value::from(options).to_string())?;
+
+        let msg_id = attempt_string(|msg_id, err| unsafe {
+            sys::CWmCreatePoll(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                question.as_ptr().cast_mut(),
+                options_json.as_ptr().cast_mut(),
+                selectable_count as _,
+                msg_id,
+                err,
+            )
+        })?;
+        Ok(MsgId(msg_id))
+    }
+
+    /// Votes on poll `poll_id` sent by `sender_id`, replacing
+    /// any earlier vote. An empty `options` retracts the vote.
+    ///
+    /// The new results are emitted as [`ChatEvent::PollUpdate`].
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - the poll wasn't received by this device, or isn't one of
+    ///   the 1000 newest, so it can't be voted on
+    /// - other errors from sending messages
+    pub fn vote_poll(
+        &self,
+        chat_id: &Jid,
+        sender_id: &Jid,
+        poll_id: &MsgId,
+        options: &[&str],
+    ) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let sender_id: CString = sender_id.try_into()?;
+        let poll_id: CString = poll_id.try_into()?;
+        let options_json = CString::new(serde_json::Value::from(options).to_string())?;
+
+        attempt(|err| unsafe {
+            sys::CWmVotePoll(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                sender_id.as_ptr().cast_mut(),
+                poll_id.as_ptr().cast_mut(),
+                options_json.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Turns disappearing messages on for a chat, keeping messages for
+    /// `timer` (rounded down to seconds), or off if it's zero.
+    /// WhatsApp only offers 24 hours, 7 days and 90 days.
+    ///
+    /// Emits a [`ChatEvent::EphemeralSettingChanged`], and messages
+    /// sent afterwards disappear too.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - the timer isn't one WhatsApp supports
+    /// - not an admin of a group that only lets admins change it
+    pub fn set_disappearing_timer(&self, chat_id: &Jid, timer: Duration) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        let seconds = timer_secs(timer)?;
+        attempt(|err| unsafe {
+            sys::CWmSetDisappearingTimer(self.id.raw(), chat_id.as_ptr().cast_mut(), seconds, err)
+        })
+    }
+
+    /// Sets the disappearing messages timer new chats start with,
+    /// zero to turn it off. Existing chats keep their timer.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - the timer isn't one WhatsApp supports
+    pub fn set_default_disappearing_timer(&self, timer: Duration) -> Result<()> {
+        let seconds = timer_secs(timer)?;
+        attempt(|err| unsafe { sys::CWmSetDefaultDisappearingTimer(self.id.raw(), seconds, err) })
+    }
+
+    /// Fetches the info and members of a group in the background.
+    /// Emits a [`ChatEvent::GroupInfo`], or nothing if it fails.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    pub fn fetch_group_info(&self, group_id: &Jid) -> Result<()> {
+        let group_id: CString = group_id.try_into()?;
+        attempt(|err| unsafe {
+            sys::CWmGetGroupInfo(self.id.raw(), group_id.as_ptr().cast_mut(), err)
+        })
+    }
+
+    /// Fetches the info and members of a group.
+    ///
+    /// Also emits a [`ChatEvent::GroupInfo`],
+    /// like [`Connection::fetch_group_info`].
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not a member of the group
+    pub async fn get_group_info(&self, group_id: &Jid) -> Result<GroupInfo> {
+        let info = sys::wait_group_info(self.id, group_id.clone());
+        self.fetch_group_info(group_id)?;
+        match info.await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(WhatsmeowError::Unknown(
+                "group info request dropped".to_owned(),
+            )),
+        }
+    }
+
+    /// Adds users to a group, returning the result for each of them.
+    ///
+    /// Users whose privacy settings don't let you add them are
+    /// sent an invite instead, like the phone app does.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn add_participants(
+        &self,
+        group_id: &Jid,
+        user_ids: &[Jid],
+    ) -> Result<Vec<(Jid, ParticipantStatus)>> {
+        self.update_participants(group_id, user_ids, c"add")
+    }
+
+    /// Removes users from a group, returning the result for each of them.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn remove_participants(
+        &self,
+        group_id: &Jid,
+        user_ids: &[Jid],
+    ) -> Result<Vec<(Jid, ParticipantStatus)>> {
+        self.update_participants(group_id, user_ids, c"remove")
+    }
+
+    /// Makes members of a group admins,
+    /// returning the result for each of them.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn promote_admins(
+        &self,
+        group_id: &Jid,
+        user_ids: &[Jid],
+    ) -> Result<Vec<(Jid, ParticipantStatus)>> {
+        self.update_participants(group_id, user_ids, c"promote")
+    }
+
+    /// Makes admins of a group regular members again,
+    /// returning the result for each of them.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn demote_admins(
+        &self,
+        group_id: &Jid,
+        user_ids: &[Jid],
+    ) -> Result<Vec<(Jid, ParticipantStatus)>> {
+        self.update_participants(group_id, user_ids, c"demote")
+    }
+
+    fn update_participants(
+        &self,
+        group_id: &Jid,
+        user_ids: &[Jid],
+        action: &CStr,
+    ) -> Result<Vec<(Jid, ParticipantStatus)>> {
+        let group_id: CString = group_id.try_into()?;
+        let user_ids_json = CString::new(
+            serde_json::Value::from_iter(user_ids.iter().map(Jid::to_id)).to_string(),
+        )?;
+
+        let results_json = attempt_string(|results_json, err| unsafe {
+            sys::CWmUpdateGroupParticipants(
+                self.id.raw(),
+                group_id.as_ptr().cast_mut(),
+                user_ids_json.as_ptr().cast_mut(),
+                action.as_ptr().cast_mut(),
+                results_json,
+                err,
+            )
+        })?;
+        let results: Vec<(String, ParticipantStatus)> = serde_json::from_str(&results_json)
+            .map_err(|err| WhatsmeowError::Unknown(format!("participant results: {err}")))?;
+        Ok(results
+            .into_iter()
+            .filter_map(|(user_id, status)| Some((Jid::parse(&user_id)?, status)))
+            .collect())
+    }
+
+    /// Creates a group with you as its admin, returning its id.
+    ///
+    /// Group names are limited to 25 characters.
+    /// Users whose privacy settings don't let you add them
+    /// are left out, use [`Connection::add_participants`]
+    /// afterwards to send them an invite.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - empty or too long name
+    pub fn create_group(&self, name: &str, participants: &[Jid]) -> Result<Jid> {
+        let name = CString::new(name)?;
+        let user_ids_json = CString::new(
+            serde_json::Value::from_iter(participants.iter().map(Jid::to_id)).to_string(),
+        )?;
+
+        let group_id = attempt_string(|group_id, err| unsafe {
+            sys::CWmCreateGroup(
+                self.id.raw(),
+                name.as_ptr().cast_mut(),
+                user_ids_json.as_ptr().cast_mut(),
+                group_id,
+                err,
+            )
+        })?;
+        Jid::parse(&group_id)
+            .ok_or_else(|| WhatsmeowError::InvalidJid(format!("created group {group_id}")))
+    }
+
+    /// Renames a group (25 characters at most).
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not allowed to edit the group info, see [`Connection::set_group_locked`]
+    pub fn set_group_name(&self, group_id: &Jid, name: &str) -> Result<()> {
+        let group_id: CString = group_id.try_into()?;
+        let name = CString::new(name)?;
+        attempt(|err| unsafe {
+            sys::CWmSetGroupName(
+                self.id.raw(),
+                group_id.as_ptr().cast_mut(),
+                name.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Sets the description (topic) of a group, empty to remove it.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not allowed to edit the group info, see [`Connection::set_group_locked`]
+    pub fn set_group_description(&self, group_id: &Jid, description: &str) -> Result<()> {
+        let group_id: CString = group_id.try_into()?;
+        let description = CString::new(description)?;
+        attempt(|err| unsafe {
+            sys::CWmSetGroupDescription(
+                self.id.raw(),
+                group_id.as_ptr().cast_mut(),
+                description.as_ptr().cast_mut(),
+                err,
+            )
+        })
+    }
+
+    /// Sets the photo of a group to the JPEG at `path`,
+    /// or removes it if `None`.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - the file couldn't be read or isn't a JPEG
+    /// - not allowed to edit the group info, see [`Connection::set_group_locked`]
+    pub fn set_group_photo(&self, group_id: &Jid, path: Option<&Path>) -> Result<()> {
+        let group_id: CString = group_id.try_into()?;
+        let path = path
+            .map(|path| CString::new(path.to_string_lossy().to_string()))
+            .transpose()?;
+        attempt(|err| unsafe {
+            sys::CWmSetGroupPhoto(
+                self.id.raw(),
+                group_id.as_ptr().cast_mut(),
+                cstr_maybe(path.as_ref()),
+                err,
+            )
+        })
+    }
+
+    /// Only lets admins send messages to a group (`true`),
+    /// or everyone (`false`).
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn set_group_announce(&self, group_id: &Jid, announce_only: bool) -> Result<()> {
+        self.set_group_flag(group_id, announce_only, sys::CWmSetGroupAnnounce)
+    }
+
+    /// Only lets admins edit the name, description and photo
+    /// of a group (`true`), or everyone (`false`).
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn set_group_locked(&self, group_id: &Jid, locked: bool) -> Result<()> {
+        self.set_group_flag(group_id, locked, sys::CWmSetGroupLocked)
+    }
+
+    /// Only lets admins add members to a group (`true`),
+    /// or everyone (`false`).
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn set_group_member_add_mode(&self, group_id: &Jid, admins_only: bool) -> Result<()> {
+        self.set_group_flag(group_id, admins_only, sys::CWmSetGroupMemberAddMode)
+    }
+
+    /// Makes admins approve users joining a group
+    /// with an invite link (`true`), or not (`false`).
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn set_group_join_approval_mode(&self, group_id: &Jid, approval: bool) -> Result<()> {
+        self.set_group_flag(group_id, approval, sys::CWmSetGroupJoinApprovalMode)
+    }
+
+    /// Returns the invite link of a group
+    /// (`https://chat.whatsapp.com/...`).
+    /// With `reset`, the old link stops working and a new one is made.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed, or `group_id` isn't a group
+    /// - not an admin of the group
+    pub fn get_invite_link(&self, group_id: &Jid, reset: bool) -> Result<String> {
+        let group_id: CString = group_id.try_into()?;
+        attempt_string(|link, err| unsafe {
+            sys::CWmGetGroupInviteLink(
+                self.id.raw(),
+                group_id.as_ptr().cast_mut(),
+                reset.into(),
+                link,
+                err,
+            )
+        })
+    }
+
+    /// Looks up the group of an invite link without joining it.
+    /// `link` can be the full link or just its code.
+    ///
+    /// Only some of the participants are listed,
+    /// see [`GroupInfo::size`] for the number of members.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - [`WhatsmeowError::InvalidArgument`] if the link is invalid or was reset
+    pub fn preview_invite(&self, link: &str) -> Result<GroupInfo> {
+        let link = CString::new(link)?;
+        let info_json = attempt_string(|info_json, err| unsafe {
+            sys::CWmPreviewGroupInvite(self.id.raw(), link.as_ptr().cast_mut(), info_json, err)
+        })?;
+        Ok(GroupInfo::from_json(&info_json)?)
+    }
+
+    /// Joins a group with an invite link (or just its code),
+    /// returning the group's id.
+    ///
+    /// If the group requires approval, this only asks to join.
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - [`WhatsmeowError::InvalidArgument`] if the link is invalid or was reset
+    pub fn join_via_link(&self, link: &str) -> Result<Jid> {
+        let link = CString::new(link)?;
+        let group_id = attempt_string(|group_id, err| unsafe {
+            sys::CWmJoinGroupWithLink(self.id.raw(), link.as_ptr().cast_mut(), group_id, err)
+        })?;
+        Jid::parse(&group_id)
+            .ok_or_else(|| WhatsmeowError::InvalidJid(format!("joined group {group_id}")))
+    }
+
+    /// Joins a group with an invite received as
+    /// a [`MessageContent::GroupInvite`] from `inviter_id`
+    /// (the sender of the message).
+    ///
+    /// # Errors
+    /// - not logged in
+    /// - JID couldn't be parsed
+    /// - [`WhatsmeowError::InvalidArgument`] if `invite` isn't a group invite,
+    ///   or it expired or was revoked
+    pub fn accept_group_invite(&self, inviter_id: &Jid, invite: &MessageContent) -> Result<()> {
+        let MessageContent::GroupInvite {
+            group_id,
+            code,
+            expiration,
+            ..
+        } = invite
+        else {
+            return Err(WhatsmeowError::InvalidArgument(
+                "not a group invite".to_owned(),
+            ));
+        };
+        let group_id: CString = group_id.try_into()?;
+        let inviter_id: CString = inviter_id.try_into()?;
+        let code = CString::new(code.as_str())?;
+        attempt(|err| unsafe {
+            sys::CWmAcceptGroupInvite(
+                self.id.raw(),
+                group_id.as_ptr().cast_mut(),
+                inviter_id.as_ptr().cast_mut(),
+                code.as_ptr().cast_mut(),
+                *expiration as _,
+                err,
+            )
+        })
+    }
+
+    fn set_group_flag(
+        &self,
+        group_id: &Jid,
+        value: bool,
+        f: unsafe extern "C" fn(
+            sys::GoInt,
+            *mut c_char,
+            sys::GoInt,
+            *mut *mut c_char,
+        ) -> sys::GoInt,
+    ) -> Result<()> {
+        let group_id: CString = group_id.try_into()?;
+        attempt(|err| unsafe {
+            f(
+                self.id.raw(),
+                group_id.as_ptr().cast_mut(),
+                value.into(),
+                err,
+            )
+        })
+    }
+
+    /// Archives/unarchives a chat (a special subfolder)
+    pub fn archive_chat_toggle(&self, chat_id: &Jid, is_archived: bool) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        attempt(|err| unsafe {
+            sys::CWmArchiveChat(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                is_archived.into(),
+                err,
+            )
+        })
+    }
+
+    /// Pins/unpins a chat to the top of the list.
+    pub fn pin_chat_toggle(&self, chat_id: &Jid, is_pinned: bool) -> Result<()> {
+        let chat_id: CString = chat_id.try_into()?;
+        attempt(|err| unsafe {
+            sys::CWmPinChat(
+                self.id.raw(),
+                chat_id.as_ptr().cast_mut(),
+                is_pinned.into(),
+                err,
+            )
+        })
+    }
 }
 
-/// Fetches members in a group. Emits a [`ChatEvent::GroupMembers`]
-pub fn get_group_members(id: ConnId, chat_id: &Jid) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    attempt(unsafe { sys::CWmGetGroupMembers(id.raw(), chat_id.as_ptr().cast_mut()) })
+/// Disappearing message timers WhatsApp offers, besides off:
+/// 24 hours, 7 days and 90 days.
+const DISAPPEARING_TIMER_SECS: [sys::GoInt; 3] = [24 * 3600, 7 * 24 * 3600, 90 * 24 * 3600];
+
+fn timer_secs(timer: Duration) -> Result<sys::GoInt> {
+    match sys::GoInt::try_from(timer.as_secs()) {
+        Ok(secs) if secs == 0 || DISAPPEARING_TIMER_SECS.contains(&secs) => Ok(secs),
+        _ => Err(WhatsmeowError::InvalidArgument(format!(
+            "unsupported disappearing timer {timer:?}"
+        ))),
+    }
 }
 
-/// Archives/unarchives a chat (a special subfolder)
-pub fn archive_chat_toggle(id: ConnId, chat_id: &Jid, is_archived: bool) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    attempt(unsafe {
-        sys::CWmArchiveChat(id.raw(), chat_id.as_ptr().cast_mut(), is_archived.into())
-    })
+fn cstr_maybe(c: Option<&CString>) -> *mut c_char {
+    c.as_ref().map_or(EMPTY.as_ptr(), |n| n.as_ptr()).cast_mut()
 }
 
-/// Pins/unpins a chat to the top of the list.
-pub fn pin_chat_toggle(id: ConnId, chat_id: &Jid, is_pinned: bool) -> Result<()> {
-    let chat_id: CString = chat_id.try_into()?;
-    attempt(unsafe { sys::CWmPinChat(id.raw(), chat_id.as_ptr().cast_mut(), is_pinned.into()) })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_secs_accepts_only_whatsapp_timers() {
+        let day: u64 = 24 * 3600;
+        for secs in [0, day, 7 * day, 90 * day] {
+            let expected = sys::GoInt::try_from(secs).unwrap();
+            assert_eq!(timer_secs(Duration::from_secs(secs)).unwrap(), expected);
+        }
+        // Rounded down to seconds first
+        let timer = Duration::from_secs(day) + Duration::from_millis(999);
+        assert_eq!(timer_secs(timer).unwrap(), 24 * 3600);
+        for secs in [1, day - 1, 2 * day, 30 * day, 365 * day, u64::MAX] {
+            assert!(
+                matches!(
+                    timer_secs(Duration::from_secs(secs)),
+                    Err(WhatsmeowError::InvalidArgument(_))
+                ),
+                "{secs}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_phone_number_rules() {
+        let cases = [
+            ("+6511111111", Some("6511111111")),
+            ("006511111111", Some("6511111111")),
+            ("6511111111", Some("6511111111")),
+            ("  +65 1111-1111 ", Some("6511111111")),
+            ("+1 (555) 123.4567", Some("15551234567")),
+            // 7 and 15 digits are the bounds
+            ("1234567", Some("1234567")),
+            ("123456", None),
+            ("123456789012345", Some("123456789012345")),
+            ("1234567890123456", None),
+            // Separators don't count as digits
+            ("12-34-56", None),
+            // Local numbers without a country code
+            ("0612345678", None),
+            ("+0612345678", None),
+            ("000612345678", None),
+            ("++6511111111", None),
+            ("+65/11111111", None),
+            ("+65 1111 111a", None),
+            ("", None),
+            ("+", None),
+        ];
+        for (input, expected) in cases {
+            match (normalize_phone_number(input), expected) {
+                (Ok(digits), Some(expected)) => assert_eq!(digits, expected, "{input:?}"),
+                (Err(WhatsmeowError::InvalidPhoneNumber(phone)), None) => {
+                    assert_eq!(phone, input);
+                }
+                (result, _) => panic!("{input:?}: unexpected {result:?}"),
+            }
+        }
+    }
 }
@@ -0,0 +1,222 @@
+//! Rendering of Qr login codes, see [`crate::Event::QrCode`].
+//!
+//! ```no_run
+//! # fn show(code: &str) -> whatsmeow_nchat::Result<()> {
+//! use whatsmeow_nchat::qr::LoginQr;
+//!
+//! let qr = LoginQr::new(code)?;
+//! println!("{}", qr.to_terminal(true));
+//! std::fs::write("qr.svg", qr.to_svg())?;
+//! std::fs::write("qr.png", qr.to_png(8)?)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use qrcode::{
+    render::{svg, unicode::Dense1x2},
+    Color, QrCode,
+};
+
+use crate::{Result, WhatsmeowError};
+
+/// Width of the blank border around the code, in modules.
+/// Scanners need at least 4 to find the code reliably.
+const QUIET_ZONE: usize = 4;
+
+/// Largest PNG module size, in pixels: about 3000px for the
+/// largest codes, far more than any scanner needs.
+pub const MAX_PNG_SCALE: u32 = 16;
+
+/// A Qr login code, encoded once and rendered in any format.
+#[derive(Clone)]
+pub struct LoginQr {
+    code: QrCode,
+}
+
+impl LoginQr {
+    /// Encodes the payload of [`crate::Event::QrCode`]
+    /// or [`crate::Event::QrCodeRefreshed`].
+    ///
+    /// # Errors
+    /// If the payload is too long to fit in a Qr code.
+    pub fn new(payload: &str) -> Result<Self> {
+        let code =
+            QrCode::new(payload.as_bytes()).map_err(|err| WhatsmeowError::Qr(err.to_string()))?;
+        Ok(Self { code })
+    }
+
+    /// Number of modules on each side, without the quiet zone.
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.code.width()
+    }
+
+    /// Renders the code as Unicode half blocks (`▀`, `▄`, `█`),
+    /// two modules per character, so it stays square in a terminal.
+    ///
+    /// Blocks are drawn in the foreground color, so pass
+    /// `dark_background = true` for light text on a dark terminal,
+    /// otherwise the code comes out inverted and won't scan.
+    #[must_use]
+    pub fn to_terminal(&self, dark_background: bool) -> String {
+        let (dark, light) = if dark_background {
+            (Dense1x2::Light, Dense1x2::Dark)
+        } else {
+            (Dense1x2::Dark, Dense1x2::Light)
+        };
+        self.code
+            .render::<Dense1x2>()
+            .dark_color(dark)
+            .light_color(light)
+            .quiet_zone(true)
+            .build()
+    }
+
+    /// Renders the code as a standalone SVG document,
+    /// black on white.
+    #[must_use]
+    pub fn to_svg(&self) -> String {
+        self.code
+            .render::<svg::Color>()
+            .min_dimensions(256, 256)
+            .quiet_zone(true)
+            .build()
+    }
+
+    /// Renders the code as a grayscale PNG, black on white,
+    /// with each module `scale` pixels wide, from 1 to [`MAX_PNG_SCALE`].
+    ///
+    /// # Errors
+    /// If the PNG couldn't be encoded.
+    pub fn to_png(&self, scale: u32) -> Result<Vec<u8>> {
+        let scale = scale.clamp(1, MAX_PNG_SCALE) as usize;
+        let modules = self.code.width();
+        let side = (modules + 2 * QUIET_ZONE) * scale;
+        let side_u32 = u32::try_from(side)
+            .map_err(|_| WhatsmeowError::Qr(format!("image too large ({side}px)")))?;
+
+        let colors = self.code.to_colors();
+        let mut pixels = vec![0xff_u8; side * side];
+        for (i, color) in colors.iter().enumerate() {
+            if *color != Color::Dark {
+                continue;
+            }
+            let x = (i % modules + QUIET_ZONE) * scale;
+            let y = (i / modules + QUIET_ZONE) * scale;
+            for row in y..y + scale {
+                pixels[row * side + x..row * side + x + scale].fill(0);
+            }
+        }
+
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, side_u32, side_u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| {
+                writer.write_image_data(&pixels)?;
+                writer.finish()
+            })
+            .map_err(|err| WhatsmeowError::Qr(err.to_string()))?;
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shaped like a real login code: ref, noise key, identity key and adv secret.
+    const PAYLOAD: &str =
+        "2@Q5xEbqbiXWOvDqzm8ZT7ms1xhNW5Vm3cdVlzpEeGiFZUsfIyHJp5w0Ze+wvmbTpeOG6D5R7j7Uw==,\
+        oVNZAwzSAlkXEJGLMpTIbjwXZ4Im34rCKGuE51S8KnE=,\
+        Zq3YH0OVXr0D8vDNr9csyIHwnGKX0LDwpoLJkDpvcyw=,\
+        Bnq1LDPTJlI1Es7f2JOwEsqH5Uf5a+dxuE1R8eAoJ5k=";
+
+    fn decode_png(data: &[u8]) -> (u32, Vec<u8>) {
+        let decoder = png::Decoder::new(std::io::Cursor::new(data));
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(info.width, info.height);
+        assert_eq!(info.color_type, png::ColorType::Grayscale);
+        pixels.truncate(info.buffer_size());
+        (info.width, pixels)
+    }
+
+    #[test]
+    fn width_follows_the_payload() {
+        assert_eq!(LoginQr::new("hello").unwrap().width(), 21);
+        let width = LoginQr::new(PAYLOAD).unwrap().width();
+        // Versions grow by 4 modules
+        assert!(width > 21 && (width - 17).is_multiple_of(4), "{width}");
+    }
+
+    #[test]
+    fn terminal_packs_two_rows_per_line() {
+        let qr = LoginQr::new(PAYLOAD).unwrap();
+        let side = qr.width() + 2 * QUIET_ZONE;
+        let dark = qr.to_terminal(true);
+        let lines: Vec<_> = dark.lines().collect();
+        assert_eq!(lines.len(), side.div_ceil(2));
+        assert!(lines.iter().all(|line| line.chars().count() == side));
+        // The quiet zone is light, so drawn only on a dark background
+        assert!(lines[0].chars().all(|c| c == '█'));
+
+        let light = qr.to_terminal(false);
+        assert!(light.lines().next().unwrap().chars().all(|c| c == ' '));
+        // Same code, inverted. The height is odd, so the last line
+        // only has top halves and is left out.
+        let full_lines = |text: &str| {
+            let lines: Vec<_> = text.lines().collect();
+            lines[..lines.len() - 1].concat()
+        };
+        for (a, b) in full_lines(&dark).chars().zip(full_lines(&light).chars()) {
+            let inverted = match a {
+                '█' => ' ',
+                ' ' => '█',
+                '▀' => '▄',
+                '▄' => '▀',
+                other => other,
+            };
+            assert_eq!(inverted, b);
+        }
+    }
+
+    #[test]
+    fn svg_is_a_standalone_document() {
+        let svg = LoginQr::new(PAYLOAD).unwrap().to_svg();
+        assert!(svg.starts_with("<?xml"), "{svg}");
+        assert!(svg.contains("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn png_has_a_quiet_zone_and_scaled_modules() {
+        let qr = LoginQr::new(PAYLOAD).unwrap();
+        let modules = qr.width() + 2 * QUIET_ZONE;
+        let (side, pixels) = decode_png(&qr.to_png(3).unwrap());
+        assert_eq!(side as usize, modules * 3);
+        let side = side as usize;
+        let pixel = |x: usize, y: usize| pixels[y * side + x];
+        // White border, then the top left finder pattern starts dark
+        assert!((0..QUIET_ZONE * 3).all(|i| pixel(i, i) == 0xff && pixel(i, 0) == 0xff));
+        let start = QUIET_ZONE * 3;
+        for (x, y) in [(start, start), (start + 2, start + 2), (start + 20, start)] {
+            assert_eq!(pixel(x, y), 0, "({x}, {y})");
+        }
+        // The ring inside the finder pattern is light
+        assert_eq!(pixel(start + 3, start + 3), 0xff);
+    }
+
+    #[test]
+    fn png_scale_is_clamped() {
+        let qr = LoginQr::new(PAYLOAD).unwrap();
+        let modules = (qr.width() + 2 * QUIET_ZONE) as u32;
+        let (side, _) = decode_png(&qr.to_png(0).unwrap());
+        assert_eq!(side, modules);
+        let (side, _) = decode_png(&qr.to_png(u32::MAX).unwrap());
+        assert_eq!(side, modules * MAX_PNG_SCALE);
+    }
+}
@@ -0,0 +1,960 @@
+//! Local message history, kept in SQLite.
+//!
+//! WhatsApp multi-device doesn't let you fetch old messages from the
+//! server (`CWmGetMessages` always fails), so to page back through
+//! a chat after a restart you need to keep them yourself.
+//!
+//! Feed every event you receive to [`MessageStore::ingest`], then use
+//! [`MessageStore::get_messages`] to page through the history,
+//! or [`MessageStore::search_messages`] to find old messages.
+//!
+//! ```no_run
+//! # async fn run() -> whatsmeow_nchat::Result<()> {
+//! let (conn, mut events) = whatsmeow_nchat::create_connection("./profile", "", 0)?;
+//! let store = conn.open_message_store()?;
+//! while let Some(event) = events.recv().await {
+//!     store.ingest(&event)?;
+//!     // ... handle the event
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{ops::Range, path::Path, sync::Mutex};
+
+use rusqlite::{params, OptionalExtension, Row};
+
+use crate::{
+    ChatEvent, DownloadFileStatus, Event, Jid, MessageContent, MsgId, Result, WhatsmeowError,
+};
+
+/// Name of the database file inside the profile directory,
+/// see [`crate::Connection::open_message_store`].
+pub const STORE_FILE_NAME: &str = "messages.db";
+
+/// `id` is only there to link `messages_fts` to its rows:
+/// unlike an implicit rowid, `VACUUM` doesn't renumber it.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS messages (
+    id          INTEGER PRIMARY KEY,
+    chat_id     TEXT NOT NULL,
+    msg_id      TEXT NOT NULL,
+    sender_id   TEXT NOT NULL,
+    text        TEXT NOT NULL,
+    from_me     INTEGER NOT NULL,
+    quoted_id   TEXT,
+    file_id     TEXT,
+    file_path   TEXT,
+    file_status INTEGER NOT NULL,
+    time_sent   INTEGER NOT NULL,
+    is_read     INTEGER NOT NULL,
+    is_edited   INTEGER NOT NULL,
+    content     TEXT NOT NULL,
+    expires_at  INTEGER NOT NULL,
+    UNIQUE (chat_id, msg_id)
+);
+CREATE INDEX IF NOT EXISTS messages_by_time ON messages (chat_id, time_sent, msg_id);
+CREATE TABLE IF NOT EXISTS reactions (
+    chat_id   TEXT NOT NULL,
+    msg_id    TEXT NOT NULL,
+    sender_id TEXT NOT NULL,
+    emoji     TEXT NOT NULL,
+    from_me   INTEGER NOT NULL,
+    PRIMARY KEY (chat_id, msg_id, sender_id)
+);
+";
+
+/// Full-text index of message bodies, kept up to date by triggers.
+const FTS_SCHEMA: &str = "
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5 (
+    text,
+    content = 'messages',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, text) VALUES (new.id, new.text);
+END;
+CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, text) VALUES ('delete', old.id, old.text);
+END;
+CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF text ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, text) VALUES ('delete', old.id, old.text);
+    INSERT INTO messages_fts (rowid, text) VALUES (new.id, new.text);
+END;
+";
+
+/// Wrap matches in snippets, stripped again in [`SearchHit`].
+const HIGHLIGHT_START: char = '\u{1}';
+const HIGHLIGHT_END: char = '\u{2}';
+
+/// A message from the local history,
+/// with every later change (edits, reactions, ...) applied.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct StoredMessage {
+    pub chat_id: Jid,
+    pub msg_id: MsgId,
+    pub sender_id: Jid,
+    pub text: String,
+    pub content: MessageContent,
+    pub from_me: bool,
+    pub quoted_id: Option<MsgId>,
+    /// File id and path of the attachment, if any.
+    pub file_id_path: Option<(String, String)>,
+    pub file_status: DownloadFileStatus,
+    /// When the message was first sent, edits don't change it.
+    pub time_sent: isize,
+    /// Unix time when this disappearing message expires,
+    /// `0` if it doesn't. See [`MessageStore::purge_expired`].
+    pub expires_at: isize,
+    pub is_read: bool,
+    pub is_edited: bool,
+    /// Sender and emoji of every reaction.
+    pub reactions: Vec<(Jid, String)>,
+}
+
+/// Narrows down [`MessageStore::search_messages`],
+/// `None` fields match everything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchFilters {
+    pub chat_id: Option<Jid>,
+    pub sender_id: Option<Jid>,
+    /// Only messages sent at or after this unix time (seconds).
+    pub sent_after: Option<isize>,
+    /// Only messages sent before this unix time (seconds).
+    pub sent_before: Option<isize>,
+    /// Only messages with (`true`) or without (`false`) a file attachment.
+    pub has_attachment: Option<bool>,
+    /// Maximum number of hits, defaults to 50.
+    pub limit: usize,
+}
+
+impl Default for SearchFilters {
+    fn default() -> Self {
+        Self {
+            chat_id: None,
+            sender_id: None,
+            sent_after: None,
+            sent_before: None,
+            has_attachment: None,
+            limit: 50,
+        }
+    }
+}
+
+/// A message matching a search, best matches first.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SearchHit {
+    pub message: StoredMessage,
+    /// The part of the message around the matches,
+    /// with `…` where it was cut.
+    pub snippet: String,
+    /// Byte ranges of the matched words in `snippet`, to highlight.
+    pub highlights: Vec<Range<usize>>,
+    /// How well the message matches, higher is better.
+    /// Only meaningful relative to the other hits of the same search.
+    pub score: f64,
+}
+
+/// SQLite-backed history of the messages of one connection.
+pub struct MessageStore {
+    db: Mutex<rusqlite::Connection>,
+}
+
+impl MessageStore {
+    /// Opens (or creates) the store at `path`.
+    ///
+    /// # Errors
+    /// If the database couldn't be opened or isn't a message store.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(rusqlite::Connection::open(path)?)
+    }
+
+    /// Opens a store that's lost when dropped.
+    ///
+    /// # Errors
+    /// If SQLite couldn't be initialized.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(rusqlite::Connection::open_in_memory()?)
+    }
+
+    fn init(db: rusqlite::Connection) -> Result<Self> {
+        db.execute_batch(SCHEMA)?;
+        db.execute_batch(FTS_SCHEMA)?;
+        Ok(Self { db: Mutex::new(db) })
+    }
+
+    /// Records the changes in `event`, if it's a [`Event::ChatEvent`].
+    /// Other events are ignored.
+    ///
+    /// # Errors
+    /// If writing to the database failed.
+    pub fn ingest(&self, event: &Event) -> Result<()> {
+        match event {
+            Event::ChatEvent(chat_id, event) => self.ingest_chat_event(chat_id, event),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records the changes in `event`, that happened in `chat_id`.
+    ///
+    /// Handles new and edited messages, deletions, reactions,
+    /// read status and file status. Other events are ignored.
+    ///
+    /// # Errors
+    /// If writing to the database failed.
+    pub fn ingest_chat_event(&self, chat_id: &Jid, event: &ChatEvent) -> Result<()> {
+        let db = self.db.lock().map_err(|_| WhatsmeowError::Poison)?;
+        let chat = chat_id.to_id();
+        match event {
+            ChatEvent::NewMessagesNotify {
+                msg_id,
+                sender_id,
+                text,
+                content,
+                from_me,
+                quoted_id,
+                file_id_path,
+                file_status,
+                time_sent,
+                expires_at,
+                is_read,
+                is_edited,
+            } => {
+                // An edit carries the id of the original message,
+                // keep its position in the history
+                db.execute(
+                    "INSERT INTO messages (chat_id, msg_id, sender_id, text, from_me, quoted_id,
+                        file_id, file_path, file_status, time_sent, is_read, is_edited, content,
+                        expires_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                     ON CONFLICT (chat_id, msg_id) DO UPDATE SET
+                        text = excluded.text,
+                        content = excluded.content,
+                        is_edited = is_edited OR excluded.is_edited,
+                        is_read = is_read OR excluded.is_read,
+                        time_sent = CASE WHEN excluded.is_edited
+                            THEN time_sent ELSE excluded.time_sent END,
+                        expires_at = CASE WHEN excluded.is_edited
+                            THEN expires_at ELSE excluded.expires_at END,
+                        quoted_id = coalesce(excluded.quoted_id, quoted_id),
+                        file_status = CASE WHEN excluded.file_id IS NULL
+                            THEN file_status ELSE excluded.file_status END,
+                        file_path = coalesce(excluded.file_path, file_path),
+                        file_id = coalesce(excluded.file_id, file_id)",
+                    params![
+                        chat,
+                        msg_id.0,
+                        sender_id.to_id(),
+                        text,
+                        from_me,
+                        quoted_id.as_ref().map(|id| &id.0),
+                        file_id_path.as_ref().map(|(id, _)| id),
+                        file_id_path.as_ref().map(|(_, path)| path),
+                        *file_status as i32,
+                        *time_sent as i64,
+                        is_read,
+                        is_edited,
+                        serde_json::to_string(content).unwrap_or_default(),
+                        *expires_at as i64,
+                    ],
+                )?;
+            }
+            ChatEvent::NewMessageStatusNotify { msg_id, is_read } => {
+                db.execute(
+                    "UPDATE messages SET is_read = ?3 WHERE chat_id = ?1 AND msg_id = ?2",
+                    params![chat, msg_id.0, is_read],
+                )?;
+            }
+            ChatEvent::NewMessageFileNotify {
+                msg_id,
+                file_path,
+                file_status,
+                ..
+            } => {
+                db.execute(
+                    "UPDATE messages SET file_path = ?3, file_status = ?4
+                     WHERE chat_id = ?1 AND msg_id = ?2",
+                    params![chat, msg_id.0, file_path, *file_status as i32],
+                )?;
+            }
+            ChatEvent::NewMessageReactionNotify {
+                msg_id,
+                sender_id,
+                emoji,
+                from_me,
+            } => {
+                // An empty emoji removes the reaction
+                if emoji.is_empty() {
+                    db.execute(
+                        "DELETE FROM reactions
+                         WHERE chat_id = ?1 AND msg_id = ?2 AND sender_id = ?3",
+                        params![chat, msg_id.0, sender_id.to_id()],
+                    )?;
+                } else {
+                    db.execute(
+                        "INSERT OR REPLACE INTO reactions VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![chat, msg_id.0, sender_id.to_id(), emoji, from_me],
+                    )?;
+                }
+            }
+            ChatEvent::DeleteMessageNotify(msg_id) => {
+                db.execute(
+                    "DELETE FROM messages WHERE chat_id = ?1 AND msg_id = ?2",
+                    params![chat, msg_id.0],
+                )?;
+                db.execute(
+                    "DELETE FROM reactions WHERE chat_id = ?1 AND msg_id = ?2",
+                    params![chat, msg_id.0],
+                )?;
+            }
+            ChatEvent::DeleteChatNotify => {
+                db.execute("DELETE FROM messages WHERE chat_id = ?1", params![chat])?;
+                db.execute("DELETE FROM reactions WHERE chat_id = ?1", params![chat])?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns up to `limit` messages of `chat_id`, newest first.
+    ///
+    /// Pass the id of the last (oldest) message you got as `before`
+    /// to get the next page, or `None` to start from the newest.
+    ///
+    /// # Errors
+    /// - [`WhatsmeowError::InvalidArgument`] if `before` isn't in the store
+    /// - If reading from the database failed
+    pub fn get_messages(
+        &self,
+        chat_id: &Jid,
+        limit: usize,
+        before: Option<&MsgId>,
+    ) -> Result<Vec<StoredMessage>> {
+        let db = self.db.lock().map_err(|_| WhatsmeowError::Poison)?;
+        let chat = chat_id.to_id();
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+
+        // Messages sent in the same second are ordered by id
+        let (time_sent, before_id) = match before {
+            Some(msg_id) => {
+                let time_sent: Option<i64> = db
+                    .query_row(
+                        "SELECT time_sent FROM messages WHERE chat_id = ?1 AND msg_id = ?2",
+                        params![chat, msg_id.0],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let Some(time_sent) = time_sent else {
+                    return Err(WhatsmeowError::InvalidArgument(format!(
+                        "message {} not in store",
+                        msg_id.0
+                    )));
+                };
+                (time_sent, msg_id.0.as_str())
+            }
+            None => (i64::MAX, ""),
+        };
+
+        let mut query = db.prepare_cached(
+            "SELECT * FROM messages
+             WHERE chat_id = ?1 AND (time_sent, msg_id) < (?2, ?3)
+             ORDER BY time_sent DESC, msg_id DESC
+             LIMIT ?4",
+        )?;
+        let mut messages = query
+            .query_map(params![chat, time_sent, before_id, limit], message_from_row)?
+            .filter_map(std::result::Result::transpose)
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        for message in &mut messages {
+            message.reactions = load_reactions(&db, message)?;
+        }
+        Ok(messages)
+    }
+
+    /// Deletes the disappearing messages that expired at or before
+    /// `now` (unix time, seconds), with their reactions.
+    ///
+    /// WhatsApp doesn't notify when a message disappears,
+    /// so call this every now and then.
+    /// Returns the number of messages deleted.
+    ///
+    /// # Errors
+    /// If writing to the database failed.
+    pub fn purge_expired(&self, now: isize) -> Result<usize> {
+        let mut db = self.db.lock().map_err(|_| WhatsmeowError::Poison)?;
+        let now = now as i64;
+        let tx = db.transaction()?;
+        tx.execute(
+            "DELETE FROM reactions WHERE (chat_id, msg_id) IN (
+                SELECT chat_id, msg_id FROM messages WHERE expires_at != 0 AND expires_at <= ?1
+             )",
+            params![now],
+        )?;
+        let purged = tx.execute(
+            "DELETE FROM messages WHERE expires_at != 0 AND expires_at <= ?1",
+            params![now],
+        )?;
+        tx.commit()?;
+        Ok(purged)
+    }
+
+    /// Searches the text of stored messages, best matches first.
+    ///
+    /// Every word in `query` must appear in the message, as a word or
+    /// the start of one (`addr` matches "address"). Punctuation splits
+    /// words, so `example.com/page` matches that link anywhere in a message.
+    /// Case and diacritics are ignored.
+    ///
+    /// # Errors
+    /// If reading from the database failed.
+    pub fn search_messages(&self, query: &str, filters: &SearchFilters) -> Result<Vec<SearchHit>> {
+        let Some(query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let db = self.db.lock().map_err(|_| WhatsmeowError::Poison)?;
+        let limit = i64::try_from(filters.limit).unwrap_or(i64::MAX);
+
+        let mut search = db.prepare_cached(
+            "SELECT m.*,
+                snippet(messages_fts, 0, char(1), char(2), '…', 16) AS snippet,
+                bm25(messages_fts) AS rank
+             FROM messages_fts JOIN messages m ON m.id = messages_fts.rowid
+             WHERE messages_fts MATCH ?1
+                AND (?2 IS NULL OR m.chat_id = ?2)
+                AND (?3 IS NULL OR m.sender_id = ?3)
+                AND (?4 IS NULL OR m.time_sent >= ?4)
+                AND (?5 IS NULL OR m.time_sent < ?5)
+                AND (?6 IS NULL OR (m.file_id IS NOT NULL) = ?6)
+             ORDER BY rank
+             LIMIT ?7",
+        )?;
+        let mut hits = search
+            .query_map(
+                params![
+                    query,
+                    filters.chat_id.as_ref().map(Jid::to_id),
+                    filters.sender_id.as_ref().map(Jid::to_id),
+                    filters.sent_after.map(|t| t as i64),
+                    filters.sent_before.map(|t| t as i64),
+                    filters.has_attachment,
+                    limit,
+                ],
+                |row| {
+                    let Some(message) = message_from_row(row)? else {
+                        return Ok(None);
+                    };
+                    let (snippet, highlights) = parse_snippet(&row.get::<_, String>("snippet")?);
+                    let rank: f64 = row.get("rank")?;
+                    Ok(Some(SearchHit {
+                        message,
+                        snippet,
+                        highlights,
+                        // bm25 is lower for better matches
+                        score: -rank,
+                    }))
+                },
+            )?
+            .filter_map(std::result::Result::transpose)
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        for hit in &mut hits {
+            hit.message.reactions = load_reactions(&db, &hit.message)?;
+        }
+        Ok(hits)
+    }
+}
+
+/// Turns user input into an FTS5 query, so that punctuation
+/// and operators (`AND`, `"`, `*`, ...) are searched for as text.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Strips the highlight markers out of a snippet,
+/// returning where they were.
+fn parse_snippet(marked: &str) -> (String, Vec<Range<usize>>) {
+    let mut snippet = String::with_capacity(marked.len());
+    let mut highlights = Vec::new();
+    let mut start = 0;
+    for c in marked.chars() {
+        match c {
+            HIGHLIGHT_START => start = snippet.len(),
+            HIGHLIGHT_END => highlights.push(start..snippet.len()),
+            _ => snippet.push(c),
+        }
+    }
+    (snippet, highlights)
+}
+
+fn load_reactions(
+    db: &rusqlite::Connection,
+    message: &StoredMessage,
+) -> rusqlite::Result<Vec<(Jid, String)>> {
+    let mut reactions = db.prepare_cached(
+        "SELECT sender_id, emoji FROM reactions WHERE chat_id = ?1 AND msg_id = ?2",
+    )?;
+    let rows = reactions
+        .query_map(params![message.chat_id.to_id(), message.msg_id.0], |row| {
+            let sender: String = row.get(0)?;
+            let emoji: String = row.get(1)?;
+            Ok(Jid::parse(&sender).map(|sender| (sender, emoji)))
+        })?
+        .filter_map(std::result::Result::transpose)
+        .collect();
+    rows
+}
+
+/// Rows with a chat or sender that's not a valid [`Jid`] are skipped.
+fn message_from_row(row: &Row) -> rusqlite::Result<Option<StoredMessage>> {
+    let chat: String = row.get("chat_id")?;
+    let sender: String = row.get("sender_id")?;
+    let (Some(chat_id), Some(sender_id)) = (Jid::parse(&chat), Jid::parse(&sender)) else {
+        return Ok(None);
+    };
+    let file_id: Option<String> = row.get("file_id")?;
+    let file_path: Option<String> = row.get("file_path")?;
+    let time_sent: i64 = row.get("time_sent")?;
+    Ok(Some(StoredMessage {
+        chat_id,
+        msg_id: MsgId(row.get("msg_id")?),
+        sender_id,
+        text: row.get("text")?,
+        content: MessageContent::from_json(&row.get::<_, String>("content")?),
+        from_me: row.get("from_me")?,
+        quoted_id: row.get::<_, Option<String>>("quoted_id")?.map(MsgId),
+        file_id_path: file_id.zip(file_path),
+        file_status: DownloadFileStatus::from_raw(row.get("file_status")?),
+        time_sent: time_sent as _,
+        expires_at: row.get::<_, i64>("expires_at")? as _,
+        is_read: row.get("is_read")?,
+        is_edited: row.get("is_edited")?,
+        reactions: Vec::new(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{edit, jid, new_message, user};
+
+    fn ids(messages: &[StoredMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.msg_id.0.as_str()).collect()
+    }
+
+    #[test]
+    fn edit_keeps_time_sent() {
+        let store = MessageStore::open_in_memory().unwrap();
+        store
+            .ingest_chat_event(&user(), &new_message("A", 100, "helo"))
+            .unwrap();
+        store
+            .ingest_chat_event(&user(), &edit("A", 250, "hello"))
+            .unwrap();
+
+        let messages = store.get_messages(&user(), 10, None).unwrap();
+        assert_eq!(ids(&messages), ["A"]);
+        assert_eq!(messages[0].text, "hello");
+        assert_eq!(messages[0].time_sent, 100);
+        assert!(messages[0].is_edited);
+
+        // A later resend of the original doesn't clear the flag
+        store
+            .ingest_chat_event(&user(), &new_message("A", 100, "hello"))
+            .unwrap();
+        assert!(store.get_messages(&user(), 10, None).unwrap()[0].is_edited);
+    }
+
+    #[test]
+    fn pages_through_messages_sent_in_the_same_second() {
+        let store = MessageStore::open_in_memory().unwrap();
+        for (msg_id, time_sent) in [("A", 100), ("B", 200), ("C", 200), ("D", 200), ("E", 300)] {
+            store
+                .ingest_chat_event(&user(), &new_message(msg_id, time_sent, msg_id))
+                .unwrap();
+        }
+        // Another chat doesn't show up
+        store
+            .ingest_chat_event(
+                &jid("6522222222@s.whatsapp.net"),
+                &new_message("Z", 250, "Z"),
+            )
+            .unwrap();
+
+        let page = store.get_messages(&user(), 2, None).unwrap();
+        assert_eq!(ids(&page), ["E", "D"]);
+        let page = store
+            .get_messages(&user(), 2, Some(&page[1].msg_id))
+            .unwrap();
+        assert_eq!(ids(&page), ["C", "B"]);
+        let page = store
+            .get_messages(&user(), 2, Some(&page[1].msg_id))
+            .unwrap();
+        assert_eq!(ids(&page), ["A"]);
+        let page = store
+            .get_messages(&user(), 2, Some(&page[0].msg_id))
+            .unwrap();
+        assert!(page.is_empty());
+    }
+
+    #[test]
+    fn unknown_before_is_invalid() {
+        let store = MessageStore::open_in_memory().unwrap();
+        store
+            .ingest_chat_event(&user(), &new_message("A", 100, "a"))
+            .unwrap();
+        let result = store.get_messages(&user(), 10, Some(&MsgId("missing".to_owned())));
+        assert!(matches!(result, Err(WhatsmeowError::InvalidArgument(_))));
+        // Known in another chat only
+        let result = store.get_messages(
+            &jid("6522222222@s.whatsapp.net"),
+            10,
+            Some(&MsgId("A".to_owned())),
+        );
+        assert!(matches!(result, Err(WhatsmeowError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn reactions_are_added_replaced_and_removed() {
+        let store = MessageStore::open_in_memory().unwrap();
+        store
+            .ingest_chat_event(&user(), &new_message("A", 100, "a"))
+            .unwrap();
+        let react = |emoji: &str| ChatEvent::NewMessageReactionNotify {
+            msg_id: MsgId("A".to_owned()),
+            sender_id: user(),
+            emoji: emoji.to_owned(),
+            from_me: false,
+        };
+
+        store.ingest_chat_event(&user(), &react("👍")).unwrap();
+        store.ingest_chat_event(&user(), &react("❤️")).unwrap();
+        let messages = store.get_messages(&user(), 10, None).unwrap();
+        assert_eq!(messages[0].reactions, [(user(), "❤️".to_owned())]);
+
+        store.ingest_chat_event(&user(), &react("")).unwrap();
+        let messages = store.get_messages(&user(), 10, None).unwrap();
+        assert!(messages[0].reactions.is_empty());
+    }
+
+    #[test]
+    fn delete_chat_removes_only_that_chat() {
+        let store = MessageStore::open_in_memory().unwrap();
+        let other = jid("6522222222@s.whatsapp.net");
+        store
+            .ingest_chat_event(&user(), &new_message("A", 100, "a"))
+            .unwrap();
+        store
+            .ingest_chat_event(&other, &new_message("B", 100, "b"))
+            .unwrap();
+        store
+            .ingest_chat_event(
+                &user(),
+                &ChatEvent::NewMessageReactionNotify {
+                    msg_id: MsgId("A".to_owned()),
+                    sender_id: user(),
+                    emoji: "👍".to_owned(),
+                    from_me: false,
+                },
+            )
+            .unwrap();
+
+        store
+            .ingest(&Event::ChatEvent(user(), ChatEvent::DeleteChatNotify))
+            .unwrap();
+        assert!(store.get_messages(&user(), 10, None).unwrap().is_empty());
+        assert_eq!(ids(&store.get_messages(&other, 10, None).unwrap()), ["B"]);
+
+        // The old reactions don't come back with a new message of the same id
+        store
+            .ingest_chat_event(&user(), &new_message("A", 200, "a"))
+            .unwrap();
+        assert!(store.get_messages(&user(), 10, None).unwrap()[0]
+            .reactions
+            .is_empty());
+    }
+
+    #[test]
+    fn expired_messages_are_purged_with_their_reactions() {
+        let store = MessageStore::open_in_memory().unwrap();
+        let expiring = |msg_id: &str, time_sent: isize, at: isize| {
+            let mut event = new_message(msg_id, time_sent, msg_id);
+            if let ChatEvent::NewMessagesNotify { expires_at, .. } = &mut event {
+                *expires_at = at;
+            }
+            event
+        };
+        store
+            .ingest_chat_event(&user(), &expiring("A", 100, 200))
+            .unwrap();
+        store
+            .ingest_chat_event(&user(), &new_message("B", 110, "B"))
+            .unwrap();
+        store
+            .ingest_chat_event(&user(), &expiring("C", 120, 300))
+            .unwrap();
+        store
+            .ingest_chat_event(
+                &user(),
+                &ChatEvent::NewMessageReactionNotify {
+                    msg_id: MsgId("A".to_owned()),
+                    sender_id: user(),
+                    emoji: "👍".to_owned(),
+                    from_me: false,
+                },
+            )
+            .unwrap();
+        // An edit doesn't restart the timer
+        store
+            .ingest_chat_event(&user(), &edit("A", 150, "A2"))
+            .unwrap();
+
+        let messages = store.get_messages(&user(), 10, None).unwrap();
+        let expires_at: Vec<_> = messages.iter().map(|m| m.expires_at).collect();
+        assert_eq!(expires_at, [300, 0, 200]);
+
+        assert_eq!(store.purge_expired(199).unwrap(), 0);
+        assert_eq!(store.purge_expired(200).unwrap(), 1);
+        assert_eq!(
+            ids(&store.get_messages(&user(), 10, None).unwrap()),
+            ["C", "B"]
+        );
+        let all = SearchFilters::default();
+        assert!(search_ids(&store, "A2", &all).is_empty());
+
+        // The reactions went with it
+        store
+            .ingest_chat_event(&user(), &new_message("A", 100, "A"))
+            .unwrap();
+        let messages = store.get_messages(&user(), 10, None).unwrap();
+        assert!(messages[2].reactions.is_empty());
+
+        assert_eq!(store.purge_expired(1000).unwrap(), 1);
+        assert_eq!(
+            ids(&store.get_messages(&user(), 10, None).unwrap()),
+            ["B", "A"]
+        );
+    }
+
+    fn search_store() -> MessageStore {
+        let store = MessageStore::open_in_memory().unwrap();
+        let other_sender = jid("6533333333@s.whatsapp.net");
+        let messages = [
+            (
+                "A",
+                &user(),
+                &user(),
+                100,
+                "Send me your address please",
+                false,
+            ),
+            (
+                "B",
+                &user(),
+                &other_sender,
+                200,
+                "Café opens at 9, menu on example.com/page",
+                true,
+            ),
+            (
+                "C",
+                &jid("6522222222@s.whatsapp.net"),
+                &user(),
+                300,
+                r#"rock AND roll "quoted" 100* (x"#,
+                false,
+            ),
+            ("D", &user(), &user(), 400, "address book", false),
+        ];
+        for (msg_id, chat_id, sender, time_sent, text, has_file) in messages {
+            let mut event = new_message(msg_id, time_sent, text);
+            if let ChatEvent::NewMessagesNotify {
+                sender_id,
+                file_id_path,
+                ..
+            } = &mut event
+            {
+                sender.clone_into(sender_id);
+                if has_file {
+                    *file_id_path = Some(("file".to_owned(), "/tmp/file".to_owned()));
+                }
+            }
+            store.ingest_chat_event(chat_id, &event).unwrap();
+        }
+        store
+    }
+
+    fn search_ids(store: &MessageStore, query: &str, filters: &SearchFilters) -> Vec<String> {
+        let mut ids: Vec<String> = store
+            .search_messages(query, filters)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.message.msg_id.0)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn search_matches_word_prefixes() {
+        let store = search_store();
+        let all = SearchFilters::default();
+        assert_eq!(search_ids(&store, "addr", &all), ["A", "D"]);
+        assert_eq!(search_ids(&store, "ADDRESS please", &all), ["A"]);
+        assert_eq!(search_ids(&store, "ress", &all), [] as [&str; 0]);
+        assert_eq!(search_ids(&store, "  ", &all), [] as [&str; 0]);
+    }
+
+    #[test]
+    fn search_treats_operators_and_punctuation_as_text() {
+        let store = search_store();
+        let all = SearchFilters::default();
+        assert_eq!(search_ids(&store, "AND", &all), ["C"]);
+        assert_eq!(search_ids(&store, "rock OR address", &all), [] as [&str; 0]);
+        assert_eq!(search_ids(&store, "NOT", &all), [] as [&str; 0]);
+        assert_eq!(search_ids(&store, r#""quoted""#, &all), ["C"]);
+        assert_eq!(search_ids(&store, r#""roll"#, &all), ["C"]);
+        assert_eq!(search_ids(&store, "100*", &all), ["C"]);
+        assert_eq!(search_ids(&store, "(x", &all), ["C"]);
+        assert_eq!(search_ids(&store, "example.com/page", &all), ["B"]);
+        assert_eq!(search_ids(&store, "com/page", &all), ["B"]);
+    }
+
+    #[test]
+    fn search_ignores_case_and_diacritics() {
+        let store = search_store();
+        let all = SearchFilters::default();
+        assert_eq!(search_ids(&store, "cafe", &all), ["B"]);
+        assert_eq!(search_ids(&store, "CAFÉ", &all), ["B"]);
+        assert_eq!(search_ids(&store, "ádDress", &all), ["A", "D"]);
+    }
+
+    #[test]
+    fn search_filters() {
+        let store = search_store();
+        let search = |filters: SearchFilters| search_ids(&store, "a", &filters);
+        assert_eq!(search(SearchFilters::default()), ["A", "B", "C", "D"]);
+        assert_eq!(
+            search(SearchFilters {
+                chat_id: Some(user()),
+                ..SearchFilters::default()
+            }),
+            ["A", "B", "D"]
+        );
+        assert_eq!(
+            search(SearchFilters {
+                sender_id: Some(jid("6533333333@s.whatsapp.net")),
+                ..SearchFilters::default()
+            }),
+            ["B"]
+        );
+        assert_eq!(
+            search(SearchFilters {
+                sent_after: Some(200),
+                ..SearchFilters::default()
+            }),
+            ["B", "C", "D"]
+        );
+        assert_eq!(
+            search(SearchFilters {
+                sent_before: Some(300),
+                ..SearchFilters::default()
+            }),
+            ["A", "B"]
+        );
+        assert_eq!(
+            search(SearchFilters {
+                has_attachment: Some(true),
+                ..SearchFilters::default()
+            }),
+            ["B"]
+        );
+        assert_eq!(
+            search(SearchFilters {
+                has_attachment: Some(false),
+                ..SearchFilters::default()
+            }),
+            ["A", "C", "D"]
+        );
+        assert_eq!(
+            search(SearchFilters {
+                limit: 2,
+                ..SearchFilters::default()
+            })
+            .len(),
+            2
+        );
+        assert_eq!(
+            search(SearchFilters {
+                chat_id: Some(user()),
+                sent_after: Some(150),
+                has_attachment: Some(false),
+                ..SearchFilters::default()
+            }),
+            ["D"]
+        );
+    }
+
+    #[test]
+    fn search_snippet_highlights_multibyte_text() {
+        let store = search_store();
+        let hits = store
+            .search_messages("cafe exam", &SearchFilters::default())
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.snippet, "Café opens at 9, menu on example.com/page");
+        let highlighted: Vec<&str> = hit
+            .highlights
+            .iter()
+            .map(|range| &hit.snippet[range.clone()])
+            .collect();
+        assert_eq!(highlighted, ["Café", "example"]);
+    }
+
+    #[test]
+    fn search_survives_vacuum() {
+        let store = search_store();
+        store
+            .ingest_chat_event(
+                &user(),
+                &ChatEvent::DeleteMessageNotify(MsgId("A".to_owned())),
+            )
+            .unwrap();
+        store.db.lock().unwrap().execute_batch("VACUUM").unwrap();
+        let all = SearchFilters::default();
+        assert_eq!(search_ids(&store, "addr", &all), ["D"]);
+        assert_eq!(search_ids(&store, "cafe", &all), ["B"]);
+        assert_eq!(search_ids(&store, "rock", &all), ["C"]);
+    }
+
+    #[test]
+    fn fts_query_quotes_every_term() {
+        assert_eq!(fts_query(""), None);
+        assert_eq!(fts_query(" \t\n"), None);
+        assert_eq!(fts_query("addr"), Some(r#""addr"*"#.to_owned()));
+        assert_eq!(
+            fts_query(r#"  a AND "b  c*"#),
+            Some(r#""a"* "AND"* """b"* "c*"*"#.to_owned())
+        );
+    }
+
+    #[test]
+    fn parse_snippet_returns_byte_ranges() {
+        let (snippet, highlights) =
+            parse_snippet("…über \u{1}Ärger\u{2} mit \u{1}日本語\u{2}!\u{1}\u{2}");
+        assert_eq!(snippet, "…über Ärger mit 日本語!");
+        assert_eq!(highlights, [9..15, 20..29, 30..30]);
+        assert_eq!(&snippet[highlights[0].clone()], "Ärger");
+        assert_eq!(&snippet[highlights[1].clone()], "日本語");
+
+        assert_eq!(parse_snippet("plain"), ("plain".to_owned(), Vec::new()));
+    }
+}
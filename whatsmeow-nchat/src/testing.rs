@@ -0,0 +1,39 @@
+//! Events shared by the unit tests.
+
+use crate::{ChatEvent, DownloadFileStatus, Jid, MessageContent, MsgId};
+
+pub fn jid(id: &str) -> Jid {
+    Jid::parse(id).expect("valid jid")
+}
+
+/// The chat of the tests, and sender of [`new_message`].
+pub fn user() -> Jid {
+    jid("6511111111@s.whatsapp.net")
+}
+
+/// An incoming, unread text message from [`user`].
+pub fn new_message(msg_id: &str, time_sent: isize, text: &str) -> ChatEvent {
+    ChatEvent::NewMessagesNotify {
+        msg_id: MsgId(msg_id.to_owned()),
+        sender_id: user(),
+        text: text.to_owned(),
+        content: MessageContent::Text,
+        from_me: false,
+        quoted_id: None,
+        file_id_path: None,
+        file_status: DownloadFileStatus::None,
+        time_sent,
+        expires_at: 0,
+        is_read: false,
+        is_edited: false,
+    }
+}
+
+/// Like [`new_message`], as an edit of message `msg_id`.
+pub fn edit(msg_id: &str, time_sent: isize, text: &str) -> ChatEvent {
+    let mut event = new_message(msg_id, time_sent, text);
+    if let ChatEvent::NewMessagesNotify { is_edited, .. } = &mut event {
+        *is_edited = true;
+    }
+    event
+}
@@ -0,0 +1,366 @@
+//! Metadata of voice notes, see [`crate::FileType::VoiceNote`].
+//!
+//! WhatsApp voice notes are Ogg/Opus files, sent with their
+//! duration and a waveform for the bubble. Both are read from
+//! the Ogg container, without decoding the audio.
+
+use std::{path::Path, time::Duration};
+
+use crate::{Result, WhatsmeowError};
+
+/// Number of bars in a voice note waveform.
+pub const WAVEFORM_LEN: usize = 64;
+
+/// Opus always runs at 48kHz, granule positions count these samples.
+const SAMPLE_RATE: u64 = 48_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoiceNoteInfo {
+    pub duration: Duration,
+    /// [`WAVEFORM_LEN`] levels from `0` to `100`.
+    ///
+    /// Opus isn't decoded, so the bitrate of each slice stands in
+    /// for its loudness (silence compresses to almost nothing),
+    /// which is close enough for the bubble.
+    pub waveform: Vec<u8>,
+}
+
+impl VoiceNoteInfo {
+    /// Reads the info of the Ogg/Opus file at `path`.
+    ///
+    /// # Errors
+    /// If the file can't be read or isn't Ogg/Opus.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_ogg(&std::fs::read(path)?)
+    }
+
+    /// Reads the info of an Ogg/Opus file.
+    ///
+    /// # Errors
+    /// If the data isn't Ogg/Opus.
+    pub fn from_ogg(data: &[u8]) -> Result<Self> {
+        let (packets, end_granule) = ogg_packets(data)?;
+        let mut packets = packets.iter();
+        let pre_skip = match packets.next() {
+            Some(head) if head.starts_with(b"OpusHead") && head.len() >= 19 => {
+                u64::from(u16::from_le_bytes([head[10], head[11]]))
+            }
+            _ => return Err(invalid("no OpusHead, not an Opus stream")),
+        };
+        // OpusTags
+        packets.next();
+
+        let mut slices: Vec<(u64, usize)> = Vec::new();
+        let mut total = 0;
+        for packet in packets {
+            let samples = packet_samples(packet);
+            slices.push((total, packet.len()));
+            total += samples;
+        }
+
+        // The last granule position is exact, packet lengths are a fallback
+        let samples = if end_granule > 0 { end_granule } else { total };
+        let duration =
+            Duration::from_secs_f64(samples.saturating_sub(pre_skip) as f64 / SAMPLE_RATE as f64);
+
+        Ok(Self {
+            duration,
+            waveform: waveform(&slices, total),
+        })
+    }
+
+    /// Duration in whole seconds (at least 1), as WhatsApp shows it.
+    #[must_use]
+    pub fn seconds(&self) -> u32 {
+        (self.duration.as_secs_f64().round() as u32).max(1)
+    }
+}
+
+fn invalid(msg: &str) -> WhatsmeowError {
+    WhatsmeowError::InvalidArgument(format!("voice note: {msg}"))
+}
+
+/// Splits the first logical stream into packets, returning them
+/// with the granule position of the last page.
+fn ogg_packets(data: &[u8]) -> Result<(Vec<Vec<u8>>, u64)> {
+    let mut packets = Vec::new();
+    let mut packet = Vec::new();
+    let mut serial = None;
+    let mut end_granule = 0;
+
+    let mut pos = 0;
+    while pos < data.len() {
+        if !data[pos..].starts_with(b"OggS") {
+            return Err(invalid("not an Ogg file"));
+        }
+        let header = data
+            .get(pos..pos + 27)
+            .ok_or_else(|| invalid("truncated Ogg page"))?;
+        let granule = i64::from_le_bytes(header[6..14].try_into().unwrap_or_default());
+        let page_serial = u32::from_le_bytes(header[14..18].try_into().unwrap_or_default());
+        let segments = usize::from(header[26]);
+
+        let table = data
+            .get(pos + 27..pos + 27 + segments)
+            .ok_or_else(|| invalid("truncated Ogg page"))?;
+        let mut body = pos + 27 + segments;
+        let end = body + table.iter().map(|&len| usize::from(len)).sum::<usize>();
+        if end > data.len() {
+            return Err(invalid("truncated Ogg page"));
+        }
+        pos = end;
+
+        // Skip other multiplexed streams
+        if *serial.get_or_insert(page_serial) != page_serial {
+            continue;
+        }
+        for &len in table {
+            let len = usize::from(len);
+            packet.extend_from_slice(&data[body..body + len]);
+            body += len;
+            // Packets continue across segments of 255 bytes
+            if len < 255 {
+                packets.push(std::mem::take(&mut packet));
+            }
+        }
+        // -1 means no packet ends on this page
+        if let Ok(granule) = u64::try_from(granule) {
+            end_granule = granule;
+        }
+    }
+    Ok((packets, end_granule))
+}
+
+/// Length of an Opus packet in 48kHz samples, from its TOC byte.
+fn packet_samples(packet: &[u8]) -> u64 {
+    let Some(&toc) = packet.first() else {
+        return 0;
+    };
+    let config = usize::from(toc >> 3);
+    let frame = match config {
+        // SILK: 10, 20, 40, 60ms
+        0..=11 => [480, 960, 1920, 2880][config % 4],
+        // Hybrid: 10, 20ms
+        12..=15 => [480, 960][config % 2],
+        // CELT: 2.5, 5, 10, 20ms
+        _ => [120, 240, 480, 960][config % 4],
+    };
+    let frames = match toc & 0b11 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => packet.get(1).map_or(0, |count| u64::from(count & 0x3f)),
+    };
+    frame * frames
+}
+
+/// Average packet size of each slice of the audio,
+/// scaled so the loudest slice is `100`.
+fn waveform(slices: &[(u64, usize)], total: u64) -> Vec<u8> {
+    if total == 0 {
+        return vec![0; WAVEFORM_LEN];
+    }
+    let mut bytes = [0usize; WAVEFORM_LEN];
+    let mut counts = [0usize; WAVEFORM_LEN];
+    for &(start, len) in slices {
+        let i = ((start * WAVEFORM_LEN as u64) / total) as usize;
+        bytes[i.min(WAVEFORM_LEN - 1)] += len;
+        counts[i.min(WAVEFORM_LEN - 1)] += 1;
+    }
+
+    let levels: Vec<f64> = bytes
+        .iter()
+        .zip(counts)
+        .map(|(&bytes, count)| {
+            if count == 0 {
+                0.0
+            } else {
+                bytes as f64 / count as f64
+            }
+        })
+        .collect();
+    let max = levels.iter().copied().fold(0.0, f64::max);
+    if max == 0.0 {
+        return vec![0; WAVEFORM_LEN];
+    }
+    levels
+        .iter()
+        .map(|level| (level / max * 100.0).round() as u8)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// CELT 20ms, one frame.
+    const TOC_20MS: u8 = 31 << 3;
+
+    /// An Ogg page with a raw lacing table.
+    fn raw_page(serial: u32, granule: i64, lacing: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS\0\0".to_vec();
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&serial.to_le_bytes());
+        // Sequence number and CRC aren't checked
+        page.extend_from_slice(&[0; 8]);
+        page.push(u8::try_from(lacing.len()).unwrap());
+        page.extend_from_slice(lacing);
+        page.extend_from_slice(body);
+        page
+    }
+
+    /// An Ogg page with whole packets.
+    fn page(serial: u32, granule: i64, packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut lacing = Vec::new();
+        for packet in packets {
+            lacing.extend(std::iter::repeat_n(255, packet.len() / 255));
+            lacing.push((packet.len() % 255) as u8);
+        }
+        raw_page(serial, granule, &lacing, &packets.concat())
+    }
+
+    fn opus_head(pre_skip: u16) -> Vec<u8> {
+        let mut head = b"OpusHead\x01\x01".to_vec();
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&48_000u32.to_le_bytes());
+        head.extend_from_slice(&[0, 0, 0]);
+        head
+    }
+
+    fn audio(len: usize) -> Vec<u8> {
+        let mut packet = vec![0xaa; len];
+        packet[0] = TOC_20MS;
+        packet
+    }
+
+    fn is_invalid(result: Result<impl std::fmt::Debug>) -> bool {
+        matches!(result, Err(WhatsmeowError::InvalidArgument(_)))
+    }
+
+    #[test]
+    fn reads_duration_from_last_granule() {
+        let mut packets = vec![b"OpusTags".to_vec()];
+        packets.extend((0..100).map(|i| audio(10 + i)));
+        let mut data = page(1, 0, &[opus_head(312)]);
+        // Ends 5ms into the last packet
+        data.extend(page(1, 2 * 48_000 + 312 - 720, &packets));
+
+        let info = VoiceNoteInfo::from_ogg(&data).unwrap();
+        assert_eq!(info.duration, Duration::from_millis(1985));
+        assert_eq!(info.seconds(), 2);
+        assert_eq!(info.waveform.len(), WAVEFORM_LEN);
+        assert_eq!(info.waveform.iter().max(), Some(&100));
+        // Later packets are bigger
+        assert!(info.waveform[0] < info.waveform[WAVEFORM_LEN - 1]);
+    }
+
+    #[test]
+    fn counts_packet_samples_without_granule() {
+        let mut data = page(1, 0, &[opus_head(312)]);
+        data.extend(page(1, 0, &[b"OpusTags".to_vec()]));
+        data.extend(page(1, -1, &vec![audio(20); 50]));
+
+        let info = VoiceNoteInfo::from_ogg(&data).unwrap();
+        assert_eq!(
+            info.duration,
+            Duration::from_secs_f64((48_000 - 312) as f64 / 48_000.0)
+        );
+        assert_eq!(info.seconds(), 1);
+    }
+
+    #[test]
+    fn joins_packets_across_segments_and_pages() {
+        let long = audio(600);
+        let mut data = page(1, 0, &[opus_head(0), b"OpusTags".to_vec()]);
+        // 255 + 255 on the first page, 90 on the next
+        data.extend(raw_page(1, -1, &[255, 255], &long[..510]));
+        data.extend(raw_page(
+            1,
+            1920,
+            &[90, 255, 0, 5],
+            &[&long[510..], &audio(255), &audio(5)].concat(),
+        ));
+
+        let (packets, end_granule) = ogg_packets(&data).unwrap();
+        let lens: Vec<usize> = packets.iter().map(Vec::len).collect();
+        assert_eq!(lens, [19, 8, 600, 255, 5]);
+        assert_eq!(packets[2], long);
+        assert_eq!(end_granule, 1920);
+    }
+
+    #[test]
+    fn skips_other_streams() {
+        let mut data = page(1, 0, &[opus_head(0)]);
+        data.extend(page(2, 0, &[b"OpusHead-other".to_vec()]));
+        data.extend(page(1, 0, &[b"OpusTags".to_vec()]));
+        data.extend(page(2, 9_999_999, &[vec![0xff; 300]]));
+        data.extend(page(1, 4800, &vec![audio(20); 5]));
+
+        let (packets, end_granule) = ogg_packets(&data).unwrap();
+        assert_eq!(packets.len(), 7);
+        assert!(packets.iter().all(|p| p.len() != 300));
+        assert_eq!(end_granule, 4800);
+        let info = VoiceNoteInfo::from_ogg(&data).unwrap();
+        assert_eq!(info.duration, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let valid = page(1, 0, &[opus_head(0), b"OpusTags".to_vec()]);
+        assert!(VoiceNoteInfo::from_ogg(&valid).is_ok());
+
+        // Header, lacing table and body cut short
+        for len in [10, 27, 28, valid.len() - 1] {
+            assert!(is_invalid(ogg_packets(&valid[..len])), "cut at {len}");
+        }
+        assert!(is_invalid(ogg_packets(b"RIFF....WAVEfmt ")));
+        assert!(is_invalid(ogg_packets(
+            &[valid.clone(), b"junk".to_vec()].concat()
+        )));
+
+        assert!(is_invalid(VoiceNoteInfo::from_ogg(&[])));
+        assert!(is_invalid(VoiceNoteInfo::from_ogg(&page(
+            1,
+            0,
+            &[b"\x01vorbis".to_vec()]
+        ))));
+        // Too short for an OpusHead
+        assert!(is_invalid(VoiceNoteInfo::from_ogg(&page(
+            1,
+            0,
+            &[b"OpusHead".to_vec()]
+        ))));
+    }
+
+    #[test]
+    fn packet_samples_from_toc() {
+        assert_eq!(packet_samples(&[]), 0);
+        // SILK 10ms, hybrid 20ms, CELT 2.5ms
+        assert_eq!(packet_samples(&[0]), 480);
+        assert_eq!(packet_samples(&[13 << 3]), 960);
+        assert_eq!(packet_samples(&[16 << 3]), 120);
+        // Two frames, equal or different sizes
+        assert_eq!(packet_samples(&[TOC_20MS | 1]), 1920);
+        assert_eq!(packet_samples(&[TOC_20MS | 2]), 1920);
+        // Frame count in the next byte
+        assert_eq!(packet_samples(&[TOC_20MS | 3, 0x80 | 3]), 2880);
+        assert_eq!(packet_samples(&[TOC_20MS | 3]), 0);
+    }
+
+    #[test]
+    fn waveform_is_always_full_length() {
+        assert_eq!(waveform(&[], 0), vec![0; WAVEFORM_LEN]);
+        assert_eq!(waveform(&[(0, 0), (960, 0)], 1920), vec![0; WAVEFORM_LEN]);
+
+        let few = waveform(&[(0, 10), (960, 40)], 1920);
+        assert_eq!(few.len(), WAVEFORM_LEN);
+        assert_eq!(few[0], 25);
+        assert_eq!(few[WAVEFORM_LEN / 2], 100);
+
+        let slices: Vec<(u64, usize)> =
+            (0..1000).map(|i| (i * 960, (i % 7) as usize * 3)).collect();
+        let many = waveform(&slices, 1000 * 960);
+        assert_eq!(many.len(), WAVEFORM_LEN);
+        assert_eq!(many.iter().max(), Some(&100));
+        assert!(many.iter().all(|&level| level <= 100));
+    }
+}